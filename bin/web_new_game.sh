@@ -1,2 +1,3 @@
+# Starts in a fresh save slot; existing slots are left alone
 open http://localhost:8889
 ttyd -W -p 8889 cargo run -- --new-game
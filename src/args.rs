@@ -1,16 +1,46 @@
 use std::env;
+use std::path::PathBuf;
 
 pub struct GameArgs {
     pub new_game: bool,
     pub dev: bool,
+    pub check_maps: bool,                       // Validate every map, and exit
+    pub export_save: Option<(String, PathBuf)>, // --export-save <slot> <file>
+    pub import_save: Option<(PathBuf, String)>, // --import-save <file> <slot>
 }
 
 // Original function that uses env::args()
-pub fn parse_args() -> GameArgs {
+pub fn parse_args() -> Result<GameArgs, String> {
     let args: Vec<String> = env::args().collect();
-    GameArgs {
+    parse_args_from(&args)
+}
+
+/// Fails if a flag is missing the values that go with it
+pub fn parse_args_from(args: &[String]) -> Result<GameArgs, String> {
+    Ok(GameArgs {
         new_game: args.contains(&"--new-game".to_string()),
         dev: args.contains(&"--dev".to_string()),
+        check_maps: args.contains(&"--check-maps".to_string()),
+        export_save: flag_values(args, "--export-save", "<slot> <file>")?
+            .map(|(slot, file)| (slot, PathBuf::from(file))),
+        import_save: flag_values(args, "--import-save", "<file> <slot>")?
+            .map(|(file, slot)| (PathBuf::from(file), slot)),
+    })
+}
+
+/// The two values following a flag, e.g. `--export-save save_1 run.yaml`, if the flag was
+/// given. `usage` describes the values, for the error if they're missing.
+fn flag_values(
+    args: &[String],
+    flag: &str,
+    usage: &str,
+) -> Result<Option<(String, String)>, String> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    match (args.get(index + 1), args.get(index + 2)) {
+        (Some(first), Some(second)) => Ok(Some((first.clone(), second.clone()))),
+        _ => Err(format!("Usage: {} {}", flag, usage)),
     }
 }
 
@@ -20,10 +50,7 @@ mod tests {
 
     // Test utility function that takes a Vec<String> for testing
     pub fn parse_args_test(args: Vec<String>) -> GameArgs {
-        GameArgs {
-            new_game: args.contains(&"--new-game".to_string()),
-            dev: args.contains(&"--dev".to_string()),
-        }
+        parse_args_from(&args).unwrap()
     }
 
     #[test]
@@ -65,4 +92,60 @@ mod tests {
         assert!(parsed_args.new_game);
         assert!(parsed_args.dev);
     }
+
+    #[test]
+    fn test_parse_args_export_and_import() {
+        let args = vec![
+            "--export-save".to_string(),
+            "save_1".to_string(),
+            "run.yaml".to_string(),
+        ];
+        let parsed_args = parse_args_test(args);
+
+        assert_eq!(
+            parsed_args.export_save,
+            Some(("save_1".to_string(), PathBuf::from("run.yaml")))
+        );
+        assert!(parsed_args.import_save.is_none());
+
+        let args = vec![
+            "--import-save".to_string(),
+            "run.yaml".to_string(),
+            "save_2".to_string(),
+        ];
+        let parsed_args = parse_args_test(args);
+
+        assert_eq!(
+            parsed_args.import_save,
+            Some((PathBuf::from("run.yaml"), "save_2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_args_check_maps() {
+        let parsed_args = parse_args_test(vec!["--check-maps".to_string()]);
+
+        assert!(parsed_args.check_maps);
+        assert!(!parse_args_test(vec![]).check_maps);
+    }
+
+    #[test]
+    fn test_parse_args_export_missing_values() {
+        let args = vec!["--export-save".to_string(), "save_1".to_string()];
+
+        assert_eq!(
+            parse_args_from(&args).err(),
+            Some("Usage: --export-save <slot> <file>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_args_import_missing_values() {
+        let args = vec!["--import-save".to_string()];
+
+        assert_eq!(
+            parse_args_from(&args).err(),
+            Some("Usage: --import-save <file> <slot>".to_string())
+        );
+    }
 }
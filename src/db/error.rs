@@ -0,0 +1,83 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors that can happen while opening, setting up, or migrating a save
+#[derive(Debug)]
+pub enum DbError {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+    /// A migration failed, and was rolled back
+    Migration(Box<MigrationError>),
+    /// The save was written by a newer version of TBG, which we don't know how to read
+    UnknownSchemaVersion {
+        save_path: PathBuf,
+        version: u32,
+        latest_version: u32,
+    },
+}
+
+/// The details of a failed migration. The save is left at `from_version`.
+#[derive(Debug)]
+pub struct MigrationError {
+    pub save_path: PathBuf,
+    pub from_version: u32,
+    pub version: u32,
+    pub description: &'static str,
+    pub backup_path: Option<PathBuf>,
+    pub source: rusqlite::Error,
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Io(e) => write!(f, "Save file error: {}", e),
+            DbError::Sqlite(e) => write!(f, "Database error: {}", e),
+            DbError::Migration(e) => {
+                write!(
+                    f,
+                    "Failed to migrate {:?} to schema version {} ({}): {}. The save was left at version {}",
+                    e.save_path, e.version, e.description, e.source, e.from_version
+                )?;
+                match &e.backup_path {
+                    Some(backup_path) => {
+                        write!(f, ", and a backup was written to {:?}", backup_path)
+                    }
+                    None => write!(f, "."),
+                }
+            }
+            DbError::UnknownSchemaVersion {
+                save_path,
+                version,
+                latest_version,
+            } => write!(
+                f,
+                "{:?} is at schema version {}, but this version of TBG only knows up to version {}",
+                save_path, version, latest_version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DbError::Io(e) => Some(e),
+            DbError::Sqlite(e) => Some(e),
+            DbError::Migration(e) => Some(&e.source),
+            DbError::UnknownSchemaVersion { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for DbError {
+    fn from(e: io::Error) -> Self {
+        DbError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> Self {
+        DbError::Sqlite(e)
+    }
+}
@@ -0,0 +1,575 @@
+use crate::db::error::{DbError, MigrationError};
+use crate::db::{
+    BOOK_PAGE_TABLE, BOOK_PASSIVE_TABLE, BOOK_TABLE, CONTENT_VERSION_TABLE, DECISION_TABLE,
+    DIALOGUE_RESPONSE_TABLE, DIALOGUE_TABLE, EXPLORED_MAP_TABLE, FIRED_TRIGGER_TABLE,
+    GAME_STATE_TABLE, INVENTORY_ITEM_TABLE, NPC_TABLE, OPENED_DOOR_TABLE, PAGE_ACTION_TABLE,
+    PAGE_PASSIVE_TABLE, PAGE_TABLE, PLAYER_TABLE, SAVE_DIR,
+};
+use rusqlite::{Connection, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single, ordered change to the schema of a save.
+///
+/// The schema version of a save is stored in sqlite's `user_version` pragma. A save
+/// at version N has had every migration up to, and including, N applied.
+///
+/// NOTE: Migrations must never be edited or re-ordered once they've shipped--add a new one instead!
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Initial schema",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "Add created_at and updated_at to dialogue, responses, and decisions",
+        up: add_dialogue_timestamps,
+    },
+    Migration {
+        version: 3,
+        description: "Add content_versions, for re-seeding changed fixtures",
+        up: add_content_versions,
+    },
+    Migration {
+        version: 4,
+        description: "Add fired_triggers, for map triggers that only fire once",
+        up: add_fired_triggers,
+    },
+    Migration {
+        version: 5,
+        description: "Add current_map to game_states",
+        up: add_current_map,
+    },
+    Migration {
+        version: 6,
+        description: "Add explored_maps, for the fog of war",
+        up: add_explored_maps,
+    },
+    Migration {
+        version: 7,
+        description: "Add inventory_items, for what the player picks up",
+        up: add_inventory_items,
+    },
+    Migration {
+        version: 8,
+        description: "Let pages be collected before they have an art type or actions",
+        up: allow_unfinished_pages,
+    },
+    Migration {
+        version: 9,
+        description: "Add opened_doors, for doors the player leaves open",
+        up: add_opened_doors,
+    },
+];
+
+/// Where backups are written before migrating a save
+pub const BACKUP_DIR: &str = "backups";
+
+pub fn latest_version() -> u32 {
+    latest_version_of(MIGRATIONS)
+}
+
+fn latest_version_of(migrations: &[Migration]) -> u32 {
+    migrations.last().map_or(0, |m| m.version)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Brings the save at `save_path` up to the latest schema version.
+pub fn migrate(conn: &mut Connection, save_path: &Path) -> Result<(), DbError> {
+    run_migrations(conn, save_path, MIGRATIONS)
+}
+
+/// Applies every migration newer than the save's schema version, in order.
+///
+/// - If the save has any data in it, it's backed up first
+/// - All pending migrations are run in a single transaction, so if any of them fail,
+///   the save is rolled back and left exactly as it was
+pub fn run_migrations(
+    conn: &mut Connection,
+    save_path: &Path,
+    migrations: &[Migration],
+) -> Result<(), DbError> {
+    let from_version = schema_version(conn)?;
+    let latest_version = latest_version_of(migrations);
+
+    if from_version > latest_version {
+        return Err(DbError::UnknownSchemaVersion {
+            save_path: save_path.to_path_buf(),
+            version: from_version,
+            latest_version,
+        });
+    }
+
+    let pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|m| m.version > from_version)
+        .collect();
+
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let backup_path = if has_tables(conn)? {
+        Some(backup_save(save_path, from_version)?)
+    } else {
+        None // A brand new save, nothing to lose
+    };
+
+    let tx = conn.transaction()?;
+
+    for migration in pending {
+        let applied = (migration.up)(&tx)
+            .and_then(|_| tx.pragma_update(None, "user_version", migration.version));
+
+        if let Err(source) = applied {
+            // Dropping the transaction rolls back every pending migration
+            return Err(DbError::Migration(Box::new(MigrationError {
+                save_path: save_path.to_path_buf(),
+                from_version,
+                version: migration.version,
+                description: migration.description,
+                backup_path,
+                source,
+            })));
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn has_tables(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Copies the save into the backup dir, e.g., `saves/backups/save_1.v1.20250306_120000.db`
+fn backup_save(save_path: &Path, version: u32) -> std::io::Result<PathBuf> {
+    let backup_dir = Path::new(SAVE_DIR).join(BACKUP_DIR);
+    fs::create_dir_all(&backup_dir)?;
+
+    let stem = save_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("save");
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let backup_path = backup_dir.join(format!("{}.v{}.{}.db", stem, version, timestamp));
+
+    fs::copy(save_path, &backup_path)?;
+
+    Ok(backup_path)
+}
+
+/// Migration 1: the tables as they were before saves were versioned.
+///
+/// Saves from before versioning already have these tables, hence `IF NOT EXISTS`.
+fn initial_schema(conn: &Connection) -> Result<()> {
+    // Player table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                gender INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                background INTEGER NOT NULL,
+                main_arc INTEGER NOT NULL,
+                created_at TIMESTAMP NOT NULL,
+                updated_at TIMESTAMP NOT NULL
+        )",
+            PLAYER_TABLE
+        ),
+        [],
+    )?;
+
+    // Game state table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                player_id INTEGER NOT NULL,
+                interface_mode INTEGER NOT NULL DEFAULT 0, -- enum for mode (dialogue, free-roaming, battle)
+                current_epic TEXT NOT NULL,
+                current_stage TEXT NOT NULL,
+                x INTEGER NOT NULL DEFAULT 0, -- X coordinate of the player
+                y INTEGER NOT NULL DEFAULT 0, -- Y coordinate of the player
+                direction INTEGER NOT NULL DEFAULT 0, -- enum for last direction of the player
+                total_play_time INTEGER NOT NULL DEFAULT 0, -- total play time in seconds
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (player_id) REFERENCES players(id) ON DELETE CASCADE
+        );",
+            GAME_STATE_TABLE
+        ),
+        [],
+    )?;
+
+    // Book passives
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );",
+            BOOK_PASSIVE_TABLE
+        ),
+        [],
+    )?;
+
+    // Book table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                player_id INTEGER NOT NULL,
+                book_art_type INTEGER NOT NULL,
+                book_passive_id INTEGER NOT NULL,
+                front_cover TEXT,
+                inside_front_cover TEXT,
+                inside_back_cover TEXT,
+                back_cover TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (player_id) REFERENCES players(id),
+                FOREIGN KEY (book_passive_id) REFERENCES book_passives(id)
+        );",
+            BOOK_TABLE
+        ),
+        [],
+    )?;
+
+    // PageAction table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );",
+            PAGE_ACTION_TABLE
+        ),
+        [],
+    )?;
+
+    // PagePassive table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );",
+            PAGE_PASSIVE_TABLE
+        ),
+        [],
+    )?;
+
+    // FIXME: Add NPC, NPCBook, NPCPage
+
+    // Page table
+    // FIXME: This is really the PlayerPage table...
+    //        - Need to break this into Page (any page that exists in the game),
+    //          and PlayerPage (a page in the player's collection)
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                player_id INTEGER NOT NULL,
+                book_art_type INTEGER NOT NULL,
+                primary_action_id INTEGER NOT NULL,
+                secondary_action_id INTEGER, -- optional
+                page_passive_id INTEGER, -- optional
+                front TEXT NOT NULL,
+                back TEXT NOT NULL,
+                FOREIGN KEY (player_id) REFERENCES players(id),
+                FOREIGN KEY (player_id) REFERENCES players(id),
+                FOREIGN KEY (primary_action_id) REFERENCES page_actions(id),
+                FOREIGN KEY (secondary_action_id) REFERENCES page_actions(id),
+                FOREIGN KEY (page_passive_id) REFERENCES page_passives(id)
+        );",
+            PAGE_TABLE
+        ),
+        [],
+    )?;
+
+    // BookPages table (pages assigned to a book at a position)
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                book_id INTEGER NOT NULL,
+                page_id INTEGER NOT NULL,
+                position_index INTEGER NOT NULL,
+                PRIMARY KEY (book_id, page_id),
+                FOREIGN KEY (book_id) REFERENCES books(id),
+                FOREIGN KEY (page_id) REFERENCES pages(id),
+                UNIQUE (book_id, position_index)
+        );",
+            BOOK_PAGE_TABLE
+        ),
+        [],
+    )?;
+
+    // (Non-Player) Character table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                gender INTEGER NOT NULL,
+                created_at TIMESTAMP NOT NULL,
+                updated_at TIMESTAMP NOT NULL
+        );",
+            NPC_TABLE
+        ),
+        [],
+    )?;
+
+    // Dialogue table
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                character_id INTEGER NOT NULL, -- Polymorphic, references either a player or NPC
+                character_type TEXT NOT NULL, -- 'player' or 'non_player_character'
+                root BOOLEAN DEFAULT 0, -- Indicates if the dialogue is a root node
+                root_name TEXT, -- Optional root name for specific root nodes
+                text TEXT NOT NULL,
+                has_input BOOLEAN DEFAULT 0,
+                input_type TEXT,
+                next_id INTEGER,
+                CHECK (character_type IN ('player', 'non_player_character'))
+            );",
+            DIALOGUE_TABLE
+        ),
+        [],
+    )?;
+
+    // Dialogue responses table
+    // For storing dialogue responses / choices
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                dialogue_id INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                next_id INTEGER NOT NULL, -- Dialogue response must have a next_id
+                FOREIGN KEY (dialogue_id) REFERENCES {}(id)
+            );",
+            DIALOGUE_RESPONSE_TABLE, DIALOGUE_TABLE,
+        ),
+        [],
+    )?;
+
+    // Decisions table
+    // For tracking player decisions
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                player_id INTEGER NOT NULL,  -- Tracks which player made the decision
+                dialogue_id INTEGER NOT NULL,  -- Links to the dialogue where the decision was made
+                response_id INTEGER, -- Links to the chosen response (nullable for input-based dialogues)
+                deliberation_time INTEGER NOT NULL,  -- Time taken to decide (stored as milliseconds)
+                FOREIGN KEY (dialogue_id) REFERENCES {}(id),
+                FOREIGN KEY (response_id) REFERENCES {}(id)
+            );",
+            DECISION_TABLE,
+            DIALOGUE_TABLE,
+            DIALOGUE_RESPONSE_TABLE,
+        ),
+        [],
+    )?;
+
+    // TODO: Add battle models. Turns, BattleLog,... everything...
+
+    Ok(())
+}
+
+/// Migration 2: dialogue, responses, and decisions were the only tables without timestamps.
+///
+/// sqlite can't add a column with a non-constant default, so existing rows are backfilled.
+fn add_dialogue_timestamps(conn: &Connection) -> Result<()> {
+    for table in [DIALOGUE_TABLE, DIALOGUE_RESPONSE_TABLE, DECISION_TABLE] {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN created_at TIMESTAMP;
+             ALTER TABLE {table} ADD COLUMN updated_at TIMESTAMP;
+             UPDATE {table} SET created_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP;",
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Migration 3: tracks which version of each seed set was last seeded into the save.
+///
+/// Saves from before this migration have no recorded versions, so all of their
+/// static content gets re-seeded once.
+fn add_content_versions(conn: &Connection) -> Result<()> {
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} (
+                name TEXT PRIMARY KEY, -- Name of the seed set, e.g. 'dialogue'
+                hash TEXT NOT NULL, -- sha256 of the seed set's content
+                updated_at TIMESTAMP NOT NULL
+            );",
+            CONTENT_VERSION_TABLE
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn add_fired_triggers(conn: &Connection) -> Result<()> {
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} (
+                player_id INTEGER NOT NULL,
+                map_name TEXT NOT NULL,
+                trigger_id TEXT NOT NULL, -- Unique within its map
+                created_at TIMESTAMP NOT NULL,
+                PRIMARY KEY (player_id, map_name, trigger_id),
+                FOREIGN KEY (player_id) REFERENCES players(id) ON DELETE CASCADE
+            );",
+            FIRED_TRIGGER_TABLE
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn add_current_map(conn: &Connection) -> Result<()> {
+    // Everyone was on the only map there was. Spelled out, rather than STARTING_MAP, so the
+    // migration doesn't change if the starting map does.
+    conn.execute(
+        &format!(
+            "ALTER TABLE {} ADD COLUMN current_map TEXT NOT NULL DEFAULT 'meadow';",
+            GAME_STATE_TABLE
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn add_explored_maps(conn: &Connection) -> Result<()> {
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} (
+                player_id INTEGER NOT NULL,
+                map_name TEXT NOT NULL,
+                width INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                tiles TEXT NOT NULL, -- Hex bitset, row by row
+                updated_at TIMESTAMP NOT NULL,
+                PRIMARY KEY (player_id, map_name),
+                FOREIGN KEY (player_id) REFERENCES players(id) ON DELETE CASCADE
+            );",
+            EXPLORED_MAP_TABLE
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn add_inventory_items(conn: &Connection) -> Result<()> {
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} (
+                player_id INTEGER NOT NULL,
+                item TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                updated_at TIMESTAMP NOT NULL,
+                PRIMARY KEY (player_id, item),
+                FOREIGN KEY (player_id) REFERENCES players(id) ON DELETE CASCADE
+            );",
+            INVENTORY_ITEM_TABLE
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn allow_unfinished_pages(conn: &Connection) -> Result<()> {
+    // SQLite can't drop NOT NULL from a column, so the table is rebuilt. book_pages points at
+    // it, and foreign keys can't be turned off mid-transaction, so book_pages is set aside
+    // and rebuilt after it.
+    conn.execute_batch(&format!(
+        "CREATE TABLE new_{pages} (
+            id INTEGER PRIMARY KEY,
+            player_id INTEGER NOT NULL,
+            book_art_type INTEGER, -- Unknown for pages found in the world
+            primary_action_id INTEGER, -- Likewise
+            secondary_action_id INTEGER, -- optional
+            page_passive_id INTEGER, -- optional
+            front TEXT NOT NULL,
+            back TEXT NOT NULL,
+            FOREIGN KEY (player_id) REFERENCES players(id),
+            FOREIGN KEY (primary_action_id) REFERENCES page_actions(id),
+            FOREIGN KEY (secondary_action_id) REFERENCES page_actions(id),
+            FOREIGN KEY (page_passive_id) REFERENCES page_passives(id)
+        );
+        INSERT INTO new_{pages} SELECT id, player_id, book_art_type, primary_action_id, secondary_action_id, page_passive_id, front, back FROM {pages};
+        CREATE TEMP TABLE old_{book_pages} AS SELECT book_id, page_id, position_index FROM {book_pages};
+        DROP TABLE {book_pages};
+        DROP TABLE {pages};
+        ALTER TABLE new_{pages} RENAME TO {pages};
+        CREATE TABLE {book_pages} (
+            book_id INTEGER NOT NULL,
+            page_id INTEGER NOT NULL,
+            position_index INTEGER NOT NULL,
+            PRIMARY KEY (book_id, page_id),
+            FOREIGN KEY (book_id) REFERENCES books(id),
+            FOREIGN KEY (page_id) REFERENCES pages(id),
+            UNIQUE (book_id, position_index)
+        );
+        INSERT INTO {book_pages} SELECT book_id, page_id, position_index FROM old_{book_pages};
+        DROP TABLE old_{book_pages};",
+        pages = PAGE_TABLE,
+        book_pages = BOOK_PAGE_TABLE
+    ))
+}
+
+fn add_opened_doors(conn: &Connection) -> Result<()> {
+    conn.execute(
+        &format!(
+            "CREATE TABLE {} (
+                player_id INTEGER NOT NULL,
+                map_name TEXT NOT NULL,
+                x INTEGER NOT NULL,
+                y INTEGER NOT NULL,
+                created_at TIMESTAMP NOT NULL,
+                PRIMARY KEY (player_id, map_name, x, y),
+                FOREIGN KEY (player_id) REFERENCES players(id) ON DELETE CASCADE
+            );",
+            OPENED_DOOR_TABLE
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 // mods
 pub mod connection;
+pub mod error;
+pub mod migrations;
+pub mod repository;
 pub mod save;
+pub mod save_document;
 pub mod seeds;
 
 // DB name
@@ -10,11 +14,16 @@ pub const DEFAULT_DB: &str = "save_file.db";
 pub const BOOK_PAGE_TABLE: &str = "book_pages";
 pub const BOOK_PASSIVE_TABLE: &str = "book_passives";
 pub const BOOK_TABLE: &str = "books";
+pub const CONTENT_VERSION_TABLE: &str = "content_versions";
 pub const DECISION_TABLE: &str = "decisions";
 pub const DIALOGUE_TABLE: &str = "dialogue";
 pub const DIALOGUE_RESPONSE_TABLE: &str = "dialogue_responses";
+pub const EXPLORED_MAP_TABLE: &str = "explored_maps";
+pub const FIRED_TRIGGER_TABLE: &str = "fired_triggers";
 pub const GAME_STATE_TABLE: &str = "game_states";
+pub const INVENTORY_ITEM_TABLE: &str = "inventory_items";
 pub const NPC_TABLE: &str = "non_player_characters";
+pub const OPENED_DOOR_TABLE: &str = "opened_doors";
 pub const PAGE_ACTION_TABLE: &str = "page_actions";
 pub const PAGE_PASSIVE_TABLE: &str = "page_passives";
 pub const PAGE_TABLE: &str = "pages";
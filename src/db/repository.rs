@@ -0,0 +1,57 @@
+//! A thin, typed layer over rusqlite that all of the models share.
+//!
+//! Each model implements `Model` once--its table, its columns, and how to map a row
+//! into it--and then loads itself with `find_one`/`find_all`.
+//!
+//! All values are passed as bound parameters (`?1`, `?2`, ...). The `clause` strings
+//! passed to these functions must only ever be static SQL, __never__ formatted with
+//! data (names, ids, etc.), otherwise we're right back to SQL injection.
+//!
+//! E.g.,
+//! ```rust,ignore
+//! let dialogue: Option<Dialogue> =
+//!     repository::find_one(conn, "WHERE root_name = ?1", params![root_name])?;
+//! ```
+use rusqlite::{Connection, OptionalExtension, Params, Result, Row};
+
+pub trait Model: Sized {
+    /// The table the model is stored in
+    const TABLE: &'static str;
+
+    /// The columns selected when loading the model, in the order `from_row` reads them
+    const COLUMNS: &'static [&'static str];
+
+    /// The one place a row is mapped into the model
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+fn select<M: Model>(clause: &str) -> String {
+    format!(
+        "SELECT {} FROM {} {}",
+        M::COLUMNS.join(", "),
+        M::TABLE,
+        clause
+    )
+}
+
+/// Loads the first model matching the clause, e.g. "WHERE id = ?1"
+pub fn find_one<M: Model, P: Params>(
+    conn: &Connection,
+    clause: &str,
+    params: P,
+) -> Result<Option<M>> {
+    let sql = format!("{} LIMIT 1", select::<M>(clause));
+    conn.query_row(&sql, params, M::from_row).optional()
+}
+
+/// Loads every model matching the clause, e.g. "WHERE dialogue_id = ?1 ORDER BY id"
+pub fn find_all<M: Model, P: Params>(conn: &Connection, clause: &str, params: P) -> Result<Vec<M>> {
+    let mut stmt = conn.prepare(&select::<M>(clause))?;
+    let rows = stmt.query_map(params, M::from_row)?;
+    rows.collect()
+}
+
+/// Loads a model by its primary key
+pub fn find_by_id<M: Model>(conn: &Connection, id: i64) -> Result<Option<M>> {
+    find_one(conn, "WHERE id = ?1", [id])
+}
@@ -1,6 +1,16 @@
+use crate::db::migrations::{latest_version, schema_version};
 use crate::db::{DEFAULT_DB, SAVE_DIR};
+use crate::models::game_state::game_state::GameState;
+use crate::models::player::player::Player;
+use chrono::NaiveDateTime;
+use rusqlite::{Connection, OpenFlags};
+use std::fmt;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const SAVE_EXTENSION: &str = "db";
 
 pub fn get_save_path(db_name: Option<&str>) -> PathBuf {
     let db_file = db_name.unwrap_or(DEFAULT_DB);
@@ -25,3 +35,171 @@ pub fn delete_save(db_name: Option<&str>) -> std::io::Result<()> {
 pub fn save_exists(db_name: Option<&str>) -> bool {
     get_save_path(db_name).exists()
 }
+
+/// A save slot is a single sqlite database living in the `SAVE_DIR`.
+///
+/// Slots are identified by their file stem, e.g., the slot named "save_1"
+/// is stored at `saves/save_1.db`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveSlot {
+    pub name: String,
+}
+
+impl SaveSlot {
+    pub fn new(name: &str) -> Self {
+        SaveSlot {
+            name: name.to_string(),
+        }
+    }
+
+    /// The file name of the slot's database, which is what `get_connection`,
+    /// `delete_save`, etc. expect.
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.name, SAVE_EXTENSION)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        get_save_path(Some(&self.file_name()))
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path().exists()
+    }
+}
+
+impl fmt::Display for SaveSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// A summary of a save slot, shown in the save-selection menu.
+///
+/// A slot that was created, but abandoned before the player finished entering their
+/// name, won't have a player (or game state) yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavePreview {
+    pub from_newer_version: bool, // Written by a newer version of TBG, so it can't be read
+    pub player_name: Option<String>,
+    pub current_epic: Option<String>,
+    pub current_stage: Option<String>,
+    pub play_time: Duration,
+    pub last_played: Option<NaiveDateTime>,
+}
+
+impl fmt::Display for SavePreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.from_newer_version {
+            return write!(f, "(needs a newer version of TBG)");
+        }
+
+        let player_name = match &self.player_name {
+            Some(name) => name,
+            None => return write!(f, "(empty)"),
+        };
+
+        write!(f, "{}", player_name)?;
+
+        if let (Some(epic), Some(stage)) = (&self.current_epic, &self.current_stage) {
+            write!(f, " - {}/{}", epic, stage)?;
+        }
+
+        write!(f, " - {}", format_play_time(self.play_time))?;
+
+        if let Some(last_played) = self.last_played {
+            write!(f, " - {}", last_played.format("%Y-%m-%d %H:%M"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats a play time as hours and minutes, e.g. "1h 02m"
+pub fn format_play_time(play_time: Duration) -> String {
+    let minutes = play_time.as_secs() / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Lists every save slot in the `SAVE_DIR`, most recently modified first.
+pub fn list_save_slots() -> io::Result<Vec<SaveSlot>> {
+    ensure_save_directory()?;
+
+    let mut slots = Vec::new();
+    for entry in fs::read_dir(SAVE_DIR)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SAVE_EXTENSION) {
+            continue;
+        }
+
+        if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            let modified = fs::metadata(&path)?.modified()?;
+            slots.push((modified, SaveSlot::new(stem)));
+        }
+    }
+
+    slots.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    Ok(slots.into_iter().map(|(_, slot)| slot).collect())
+}
+
+/// Loads a preview of a slot, without running any setup or seeds against it.
+pub fn load_save_preview(slot: &SaveSlot) -> rusqlite::Result<SavePreview> {
+    let conn = Connection::open_with_flags(slot.path(), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let from_newer_version = schema_version(&conn)? > latest_version();
+
+    // A newer save's tables may not look like ours, so don't try to read them
+    let player = match from_newer_version {
+        true => None,
+        false => Player::load(&conn)?,
+    };
+    let player = match player {
+        Some(player) => player,
+        None => {
+            return Ok(SavePreview {
+                from_newer_version,
+                player_name: None,
+                current_epic: None,
+                current_stage: None,
+                play_time: Duration::ZERO,
+                last_played: None,
+            })
+        }
+    };
+
+    let game_state = GameState::load_for_player(&conn, player.id)?;
+    let last_played = match &game_state {
+        Some(game_state) => game_state.updated_at.max(player.updated_at),
+        None => player.updated_at,
+    };
+
+    Ok(SavePreview {
+        from_newer_version,
+        player_name: Some(player.name),
+        current_epic: game_state.as_ref().map(|gs| gs.current_epic.clone()),
+        current_stage: game_state.as_ref().map(|gs| gs.current_stage.clone()),
+        play_time: game_state.map_or(Duration::ZERO, |gs| gs.total_play_time.0),
+        last_played: Some(last_played),
+    })
+}
+
+/// Finds the first unused slot name of the form "save_N"
+pub fn next_slot_name() -> String {
+    (1..)
+        .map(|n| format!("save_{}", n))
+        .find(|name| !SaveSlot::new(name).exists())
+        .unwrap()
+}
+
+/// Copies a slot into a new slot. Refuses to overwrite an existing slot.
+pub fn copy_save(from: &SaveSlot, to: &SaveSlot) -> io::Result<()> {
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Save slot {} already exists", to),
+        ));
+    }
+
+    fs::copy(from.path(), to.path())?;
+    Ok(())
+}
@@ -0,0 +1,638 @@
+//! A portable, human-readable copy of a save.
+//!
+//! Saves normally only exist as sqlite files in the `SAVE_DIR`. A `SaveDocument` holds
+//! everything that belongs to the player (the player, their game state, books, pages,
+//! and decisions) as YAML, so a run can be moved between machines, shared, or attached
+//! to a bug report. The map triggers that have fired for the player are included too, as
+//! are the map tiles they've explored, what they're carrying, and the doors they've left
+//! open.
+//!
+//! Static content (dialogue, NPCs, ...) is not included--it comes from the seeds of
+//! whichever version of TBG imports the document.
+use crate::db::connection::get_connection;
+use crate::db::error::DbError;
+use crate::db::migrations;
+use crate::db::repository::{self, Model};
+use crate::db::save::SaveSlot;
+use crate::db::{
+    BOOK_PAGE_TABLE, BOOK_PASSIVE_TABLE, BOOK_TABLE, DECISION_TABLE, DIALOGUE_TABLE,
+    PAGE_ACTION_TABLE, PAGE_PASSIVE_TABLE, PAGE_TABLE,
+};
+use crate::models::book::page::Page;
+use crate::models::game_state::explored_map::ExploredMap;
+use crate::models::game_state::fired_trigger::FiredTrigger;
+use crate::models::game_state::game_state::GameState;
+use crate::models::game_state::opened_door::OpenedDoor;
+use crate::models::player::inventory_item::InventoryItem;
+use crate::models::player::player::Player;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OpenFlags, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The version of the document format. Bump this when the format changes in a way
+/// older versions of TBG can't read.
+pub const SAVE_DOCUMENT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveDocument {
+    pub format_version: u32,
+    pub schema_version: u32, // Schema version of the save this was exported from (informational)
+    pub exported_at: NaiveDateTime,
+    pub player: Player,
+    pub game_state: GameState,
+    pub books: Vec<BookRecord>,
+    pub pages: Vec<Page>,
+    pub book_pages: Vec<BookPageRecord>,
+    pub decisions: Vec<DecisionRecord>,
+    // Documents exported before these were saved don't have them. They're still format
+    // version 1, since older versions of TBG can read (and ignore) them.
+    #[serde(default)] // Missing before map triggers were saved
+    pub fired_triggers: Vec<FiredTrigger>,
+    #[serde(default)] // Missing before the fog of war
+    pub explored_maps: Vec<ExploredMap>,
+    #[serde(default)] // Missing before the inventory
+    pub inventory: Vec<InventoryItem>,
+    #[serde(default)] // Missing before opened doors were saved
+    pub opened_doors: Vec<OpenedDoor>,
+}
+
+// NOTE: Books don't have a real model yet (see models::book), so the document stores
+//       their rows as-is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookRecord {
+    pub id: i64,
+    pub player_id: i32,
+    pub book_art_type: i64,
+    pub book_passive_id: i64,
+    pub front_cover: Option<String>,
+    pub inside_front_cover: Option<String>,
+    pub inside_back_cover: Option<String>,
+    pub back_cover: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A page placed in a book, at a position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookPageRecord {
+    pub book_id: i64,
+    pub page_id: i64,
+    pub position_index: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub id: i64,
+    pub player_id: i32,
+    pub dialogue_id: u32,
+    pub response_id: Option<u32>,
+    pub deliberation_time: i64, // milliseconds
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug)]
+pub enum SaveDocumentError {
+    Io(std::io::Error),
+    Yaml(serde_yaml::Error),
+    Db(DbError),
+    /// The save has no player to export
+    EmptySave,
+    /// The document was exported by a newer version of TBG
+    UnsupportedVersion(u32),
+    /// The document contradicts itself, or refers to content this version of TBG doesn't have
+    Invalid(String),
+    /// Importing never overwrites an existing save
+    SlotExists(SaveSlot),
+}
+
+impl fmt::Display for SaveDocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveDocumentError::Io(e) => write!(f, "Failed to read or write the save document: {}", e),
+            SaveDocumentError::Yaml(e) => write!(f, "The save document isn't valid YAML: {}", e),
+            SaveDocumentError::Db(e) => write!(f, "{}", e),
+            SaveDocumentError::EmptySave => write!(f, "The save has no player to export"),
+            SaveDocumentError::UnsupportedVersion(version) => write!(
+                f,
+                "The save document is format version {}, but this version of TBG only reads up to version {}",
+                version, SAVE_DOCUMENT_VERSION
+            ),
+            SaveDocumentError::Invalid(reason) => write!(f, "Invalid save document: {}", reason),
+            SaveDocumentError::SlotExists(slot) => write!(f, "Save slot {} already exists", slot),
+        }
+    }
+}
+
+impl std::error::Error for SaveDocumentError {}
+
+impl From<std::io::Error> for SaveDocumentError {
+    fn from(e: std::io::Error) -> Self {
+        SaveDocumentError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for SaveDocumentError {
+    fn from(e: serde_yaml::Error) -> Self {
+        SaveDocumentError::Yaml(e)
+    }
+}
+
+impl From<DbError> for SaveDocumentError {
+    fn from(e: DbError) -> Self {
+        SaveDocumentError::Db(e)
+    }
+}
+
+impl From<rusqlite::Error> for SaveDocumentError {
+    fn from(e: rusqlite::Error) -> Self {
+        SaveDocumentError::Db(DbError::Sqlite(e))
+    }
+}
+
+impl SaveDocument {
+    /// Reads the player (the most recent one, like `Player::load`) and everything they own out of a save
+    pub fn export(conn: &Connection) -> Result<SaveDocument, SaveDocumentError> {
+        let player = Player::load(conn)?.ok_or(SaveDocumentError::EmptySave)?;
+        let game_state =
+            GameState::load_for_player(conn, player.id)?.ok_or(SaveDocumentError::EmptySave)?;
+
+        let books: Vec<BookRecord> =
+            repository::find_all(conn, "WHERE player_id = ?1 ORDER BY id", [player.id])?;
+        let pages = Page::load_for_player(conn, player.id)?;
+        let book_pages: Vec<BookPageRecord> = repository::find_all(
+            conn,
+            &format!(
+                "WHERE book_id IN (SELECT id FROM {} WHERE player_id = ?1) ORDER BY book_id, position_index",
+                BOOK_TABLE
+            ),
+            [player.id],
+        )?;
+        let decisions: Vec<DecisionRecord> =
+            repository::find_all(conn, "WHERE player_id = ?1 ORDER BY id", [player.id])?;
+        let fired_triggers = FiredTrigger::load_for_player(conn, player.id)?;
+        let explored_maps = ExploredMap::load_for_player(conn, player.id)?;
+        let inventory = InventoryItem::load_for_player(conn, player.id)?;
+        let opened_doors = OpenedDoor::load_for_player(conn, player.id)?;
+
+        Ok(SaveDocument {
+            format_version: SAVE_DOCUMENT_VERSION,
+            schema_version: migrations::schema_version(conn)?,
+            exported_at: chrono::Local::now().naive_local(),
+            player,
+            game_state,
+            books,
+            pages,
+            book_pages,
+            decisions,
+            fired_triggers,
+            explored_maps,
+            inventory,
+            opened_doors,
+        })
+    }
+
+    pub fn to_yaml(&self) -> Result<String, SaveDocumentError> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Parses and validates a document
+    pub fn from_yaml(yaml: &str) -> Result<SaveDocument, SaveDocumentError> {
+        let document: SaveDocument = serde_yaml::from_str(yaml)?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    /// Checks that the document is consistent with itself
+    pub fn validate(&self) -> Result<(), SaveDocumentError> {
+        if self.format_version > SAVE_DOCUMENT_VERSION {
+            return Err(SaveDocumentError::UnsupportedVersion(self.format_version));
+        }
+
+        let player_id = self.player.id;
+        let invalid = |reason: String| Err(SaveDocumentError::Invalid(reason));
+
+        if self.game_state.player_id != player_id {
+            return invalid(format!(
+                "game_state belongs to player {}, not player {}",
+                self.game_state.player_id, player_id
+            ));
+        }
+
+        let mut book_ids = HashSet::new();
+        for book in &self.books {
+            if book.player_id != player_id {
+                return invalid(format!("book {} belongs to another player", book.id));
+            }
+            if !book_ids.insert(book.id) {
+                return invalid(format!("book {} appears more than once", book.id));
+            }
+        }
+
+        let mut page_ids = HashSet::new();
+        for page in &self.pages {
+            if page.player_id != player_id {
+                return invalid(format!("page {} belongs to another player", page.id));
+            }
+            if !page_ids.insert(page.id) {
+                return invalid(format!("page {} appears more than once", page.id));
+            }
+        }
+
+        let mut placements = HashSet::new();
+        let mut positions = HashSet::new();
+        for book_page in &self.book_pages {
+            if !book_ids.contains(&book_page.book_id) {
+                return invalid(format!(
+                    "a book page refers to book {}, which isn't in the document",
+                    book_page.book_id
+                ));
+            }
+            if !page_ids.contains(&book_page.page_id) {
+                return invalid(format!(
+                    "a book page refers to page {}, which isn't in the document",
+                    book_page.page_id
+                ));
+            }
+            if !placements.insert((book_page.book_id, book_page.page_id)) {
+                return invalid(format!(
+                    "page {} is in book {} more than once",
+                    book_page.page_id, book_page.book_id
+                ));
+            }
+            if !positions.insert((book_page.book_id, book_page.position_index)) {
+                return invalid(format!(
+                    "book {} has more than one page at position {}",
+                    book_page.book_id, book_page.position_index
+                ));
+            }
+        }
+
+        let mut decision_ids = HashSet::new();
+        for decision in &self.decisions {
+            if decision.player_id != player_id {
+                return invalid(format!(
+                    "decision {} belongs to another player",
+                    decision.id
+                ));
+            }
+            if !decision_ids.insert(decision.id) {
+                return invalid(format!("decision {} appears more than once", decision.id));
+            }
+            if decision.deliberation_time < 0 {
+                return invalid(format!(
+                    "decision {} has a negative deliberation time",
+                    decision.id
+                ));
+            }
+        }
+
+        let mut fired_triggers = HashSet::new();
+        for fired_trigger in &self.fired_triggers {
+            if fired_trigger.player_id != player_id {
+                return invalid(format!(
+                    "fired trigger {} belongs to another player",
+                    fired_trigger.trigger_id
+                ));
+            }
+            if !fired_triggers.insert((&fired_trigger.map_name, &fired_trigger.trigger_id)) {
+                return invalid(format!(
+                    "fired trigger {} on map {} appears more than once",
+                    fired_trigger.trigger_id, fired_trigger.map_name
+                ));
+            }
+        }
+
+        let mut explored_maps = HashSet::new();
+        for explored_map in &self.explored_maps {
+            if explored_map.player_id != player_id {
+                return invalid(format!(
+                    "explored map {} belongs to another player",
+                    explored_map.map_name
+                ));
+            }
+            if !explored_maps.insert(&explored_map.map_name) {
+                return invalid(format!(
+                    "explored map {} appears more than once",
+                    explored_map.map_name
+                ));
+            }
+            if explored_map.to_explored().is_none() {
+                return invalid(format!(
+                    "explored map {} has tiles that don't fit its size",
+                    explored_map.map_name
+                ));
+            }
+        }
+
+        let mut items = HashSet::new();
+        for inventory_item in &self.inventory {
+            if inventory_item.player_id != player_id {
+                return invalid(format!(
+                    "inventory item {} belongs to another player",
+                    inventory_item.item
+                ));
+            }
+            if !items.insert(&inventory_item.item) {
+                return invalid(format!(
+                    "inventory item {} appears more than once",
+                    inventory_item.item
+                ));
+            }
+        }
+
+        let mut opened_doors = HashSet::new();
+        for opened_door in &self.opened_doors {
+            let name = format!(
+                "opened door at {},{} on map {}",
+                opened_door.x, opened_door.y, opened_door.map_name
+            );
+            if opened_door.player_id != player_id {
+                return invalid(format!("{} belongs to another player", name));
+            }
+            if !opened_doors.insert((&opened_door.map_name, opened_door.x, opened_door.y)) {
+                return invalid(format!("{} appears more than once", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the document into a brand new save slot.
+    ///
+    /// The slot is created (and seeded) like any new save, and the document is written in a
+    /// single transaction. If anything fails, the new slot is deleted again.
+    pub fn import(&self, slot: &SaveSlot) -> Result<(), SaveDocumentError> {
+        self.validate()?;
+
+        if slot.exists() {
+            return Err(SaveDocumentError::SlotExists(slot.clone()));
+        }
+
+        let result = get_connection(Some(&slot.file_name()))
+            .map_err(SaveDocumentError::from)
+            .and_then(|conn| self.insert(&conn));
+
+        if result.is_err() && slot.exists() {
+            fs::remove_file(slot.path())?;
+        }
+
+        result
+    }
+
+    fn insert(&self, conn: &Connection) -> Result<(), SaveDocumentError> {
+        // Books, pages and decisions point at content from this version of TBG's seeds
+        for book in &self.books {
+            require_content(
+                conn,
+                BOOK_PASSIVE_TABLE,
+                book.book_passive_id,
+                "book",
+                book.id,
+            )?;
+        }
+        for page in &self.pages {
+            for action_id in [page.primary_action_id, page.secondary_action_id]
+                .into_iter()
+                .flatten()
+            {
+                require_content(conn, PAGE_ACTION_TABLE, action_id, "page", page.id)?;
+            }
+            if let Some(passive_id) = page.page_passive_id {
+                require_content(conn, PAGE_PASSIVE_TABLE, passive_id, "page", page.id)?;
+            }
+        }
+        for decision in &self.decisions {
+            require_content(
+                conn,
+                DIALOGUE_TABLE,
+                decision.dialogue_id as i64,
+                "decision",
+                decision.id,
+            )?;
+        }
+
+        let tx = conn.unchecked_transaction()?;
+
+        let player = &self.player;
+        tx.execute(
+            &format!(
+                "INSERT INTO {} (id, name, gender, height, background, main_arc, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                Player::TABLE
+            ),
+            params![player.id, player.name, player.gender, player.height, player.background, player.main_arc, player.created_at, player.updated_at],
+        )?;
+
+        let game_state = &self.game_state;
+        tx.execute(
+            &format!(
+                "INSERT INTO {} (player_id, interface_mode, current_epic, current_stage, current_map, x, y, direction, total_play_time, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                GameState::TABLE
+            ),
+            params![game_state.player_id, game_state.interface_mode, game_state.current_epic, game_state.current_stage, game_state.current_map, game_state.x, game_state.y, game_state.direction, game_state.total_play_time, game_state.created_at, game_state.updated_at],
+        )?;
+
+        for book in &self.books {
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (id, player_id, book_art_type, book_passive_id, front_cover, inside_front_cover, inside_back_cover, back_cover, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    BOOK_TABLE
+                ),
+                params![book.id, book.player_id, book.book_art_type, book.book_passive_id, book.front_cover, book.inside_front_cover, book.inside_back_cover, book.back_cover, book.created_at, book.updated_at],
+            )?;
+        }
+
+        for page in &self.pages {
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (id, player_id, book_art_type, primary_action_id, secondary_action_id, page_passive_id, front, back) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    PAGE_TABLE
+                ),
+                params![page.id, page.player_id, page.book_art_type, page.primary_action_id, page.secondary_action_id, page.page_passive_id, page.front, page.back],
+            )?;
+        }
+
+        for book_page in &self.book_pages {
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (book_id, page_id, position_index) VALUES (?1, ?2, ?3)",
+                    BOOK_PAGE_TABLE
+                ),
+                params![
+                    book_page.book_id,
+                    book_page.page_id,
+                    book_page.position_index
+                ],
+            )?;
+        }
+
+        for decision in &self.decisions {
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (id, player_id, dialogue_id, response_id, deliberation_time, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    DECISION_TABLE
+                ),
+                params![decision.id, decision.player_id, decision.dialogue_id, decision.response_id, decision.deliberation_time, decision.created_at, decision.updated_at],
+            )?;
+        }
+
+        for fired_trigger in &self.fired_triggers {
+            fired_trigger.create(&tx)?;
+        }
+
+        for explored_map in &self.explored_maps {
+            explored_map.save(&tx)?;
+        }
+
+        for inventory_item in &self.inventory {
+            inventory_item.add(&tx)?;
+        }
+
+        for opened_door in &self.opened_doors {
+            opened_door.create(&tx)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+/// Fails unless `table` has a row with the given id
+fn require_content(
+    conn: &Connection,
+    table: &str,
+    id: i64,
+    referenced_by: &str,
+    referenced_by_id: i64,
+) -> Result<(), SaveDocumentError> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = ?1)", table),
+        [id],
+        |row| row.get(0),
+    )?;
+
+    if exists {
+        Ok(())
+    } else {
+        Err(SaveDocumentError::Invalid(format!(
+            "{} {} refers to {} {}, which doesn't exist",
+            referenced_by, referenced_by_id, table, id
+        )))
+    }
+}
+
+/// Exports a save slot to a YAML file.
+///
+/// The slot is only read, never migrated or seeded, so it has to be at the schema version
+/// this version of TBG writes. Saves from older versions are upgraded by playing them once.
+pub fn export_save(slot: &SaveSlot, path: &Path) -> Result<(), SaveDocumentError> {
+    if !slot.exists() {
+        return Err(SaveDocumentError::Invalid(format!(
+            "save slot {} doesn't exist",
+            slot
+        )));
+    }
+
+    let conn = Connection::open_with_flags(slot.path(), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let version = migrations::schema_version(&conn)?;
+    let latest_version = migrations::latest_version();
+    if version > latest_version {
+        return Err(DbError::UnknownSchemaVersion {
+            save_path: slot.path(),
+            version,
+            latest_version,
+        }
+        .into());
+    }
+    if version < latest_version {
+        return Err(SaveDocumentError::Invalid(format!(
+            "save slot {} is at schema version {}, and needs upgrading to version {}--play it once first",
+            slot, version, latest_version
+        )));
+    }
+
+    let document = SaveDocument::export(&conn)?;
+    fs::write(path, document.to_yaml()?)?;
+
+    Ok(())
+}
+
+/// Imports a YAML file into a new save slot
+pub fn import_save(path: &Path, slot: &SaveSlot) -> Result<(), SaveDocumentError> {
+    let yaml = fs::read_to_string(path)?;
+    SaveDocument::from_yaml(&yaml)?.import(slot)
+}
+
+impl Model for BookRecord {
+    const TABLE: &'static str = BOOK_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "player_id",
+        "book_art_type",
+        "book_passive_id",
+        "front_cover",
+        "inside_front_cover",
+        "inside_back_cover",
+        "back_cover",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(BookRecord {
+            id: row.get(0)?,
+            player_id: row.get(1)?,
+            book_art_type: row.get(2)?,
+            book_passive_id: row.get(3)?,
+            front_cover: row.get(4)?,
+            inside_front_cover: row.get(5)?,
+            inside_back_cover: row.get(6)?,
+            back_cover: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+}
+
+impl Model for BookPageRecord {
+    const TABLE: &'static str = BOOK_PAGE_TABLE;
+    const COLUMNS: &'static [&'static str] = &["book_id", "page_id", "position_index"];
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(BookPageRecord {
+            book_id: row.get(0)?,
+            page_id: row.get(1)?,
+            position_index: row.get(2)?,
+        })
+    }
+}
+
+impl Model for DecisionRecord {
+    const TABLE: &'static str = DECISION_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "player_id",
+        "dialogue_id",
+        "response_id",
+        "deliberation_time",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(DecisionRecord {
+            id: row.get(0)?,
+            player_id: row.get(1)?,
+            dialogue_id: row.get(2)?,
+            response_id: row.get(3)?,
+            deliberation_time: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+}
@@ -1,9 +1,16 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, Result};
 
-pub fn run(_conn: &Connection) {
+// FIXME: Books are player-owned, so re-seeding books would have to leave the
+//        player's books alone. Only "starter" books (if any) would be seeded here.
+pub fn content_hash() -> String {
+    super::hash_content(b"")
+}
+
+pub fn run(_conn: &Connection) -> Result<()> {
     println!("Seeding books...");
 
     // FIXME: Is seeding books needed..?
     //        - Maybe seeding the "starter" books?
     //        - Books == PlayerBook ?
+    Ok(())
 }
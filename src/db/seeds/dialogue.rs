@@ -1,45 +1,56 @@
+use crate::db::{DIALOGUE_RESPONSE_TABLE, DIALOGUE_TABLE};
 use crate::models::dialogue::dialogue::{self, Dialogue};
-use rusqlite::Connection;
-use std::collections::HashMap;
+use rusqlite::{Connection, Result};
+use std::fs;
 
-pub fn run(conn: &Connection) {
+const FIXTURES: [&str; 2] = ["character_creation", "first_quest"];
+
+/// Hash of the raw fixture files, so any edit to them triggers a re-seed
+pub fn content_hash() -> String {
+    let mut content = Vec::new();
+
+    for fixture_name in &FIXTURES {
+        content.extend(fixture_name.as_bytes());
+        content.extend(
+            fs::read(dialogue::fixture_path(fixture_name))
+                .expect("Failed to read dialogue fixture"),
+        );
+    }
+
+    super::hash_content(&content)
+}
+
+pub fn run(conn: &Connection) -> Result<()> {
     println!("Seeding dialogue from fixtures...");
 
-    let fixtures = ["character_creation", "first_quest"];
-    let mut dialogue_id_map: HashMap<u32, u32> = HashMap::new();
+    // Dialogue keeps the ids from the fixtures, so decisions still point at the right dialogue
+    conn.execute(&format!("DELETE FROM {}", DIALOGUE_RESPONSE_TABLE), [])?;
+    conn.execute(&format!("DELETE FROM {}", DIALOGUE_TABLE), [])?;
+
+    let dialogue_trees: Vec<Vec<Dialogue>> = FIXTURES
+        .iter()
+        .map(|fixture_name| {
+            println!("Creating dialogue from fixtures at {:?}", fixture_name);
+            dialogue::load_from_fixture(fixture_name).expect("Failed to load dialogue fixture")
+        })
+        .collect();
 
     // First pass: Create all dialogues
-    for fixture_name in &fixtures {
-        println!("Creating dialogue from fixtures at {:?}", fixture_name);
-        let dialogue_tree: Vec<Dialogue> = dialogue::load_from_fixture(fixture_name).unwrap();
-
-        for dialogue in &dialogue_tree {
-            let dialogue_id = dialogue.create(&conn);
-            dialogue_id_map.insert(dialogue.id, dialogue_id);
-            println!("Created Dialogue with ID: {}", dialogue_id);
-        }
+    for dialogue in dialogue_trees.iter().flatten() {
+        let dialogue_id = dialogue.create(conn)?;
+        println!("Created Dialogue with ID: {}", dialogue_id);
     }
 
     // Second pass: Create all responses (now that all dialogue IDs exist, otherwise fk errors)
-    for fixture_name in &fixtures {
-        println!("Creating responses from fixtures at {:?}", fixture_name);
-
-        let mut dialogue_tree: Vec<Dialogue> = dialogue::load_from_fixture(fixture_name).unwrap();
-
-        for dialogue in &mut dialogue_tree {
-            if let Some(responses) = dialogue.get_responses_mut() {
-                for response in responses.iter_mut() {
-                    if let Some(&mapped_next_id) = dialogue_id_map.get(&response.next_id) {
-                        response.next_id = mapped_next_id;
-                    } else {
-                        println!(
-                            "Warning: Could not find dialogue with ID: {}",
-                            response.next_id
-                        );
-                    }
-                    response.create(&conn).unwrap();
-                }
+    for dialogue in dialogue_trees.iter().flatten() {
+        if let Some(responses) = dialogue.get_responses() {
+            for response in responses {
+                let mut response = response.clone();
+                response.dialogue_id = dialogue.id; // Always belongs to the dialogue it's nested under
+                response.create(conn)?;
             }
         }
     }
+
+    Ok(())
 }
@@ -1,7 +1,16 @@
-use rusqlite::Connection;
+use crate::db::CONTENT_VERSION_TABLE;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use sha2::{Digest, Sha256};
 
 /// Seeds (must be run in certain order!)
 ///
+/// Each seed set is versioned by a hash of its content. When a save is opened, any set
+/// whose content has changed since it was last seeded is re-seeded, so edits to e.g.
+/// `character_creation.yaml` reach existing saves.
+///
+/// Only static content is seeded. Player-owned rows (players, game states, books,
+/// decisions, ...) are never touched by seeds.
+///
 // TODO:
 // - Books
 // - Pages
@@ -14,12 +23,93 @@ mod dialogue;
 mod non_player_characters;
 mod pages;
 
-pub fn run(conn: &Connection) {
-    println!("Running seeds!");
+/// A set of static content, seeded (and re-seeded) as a unit
+struct SeedSet {
+    name: &'static str,
+    content_hash: fn() -> String,
+    // Replaces all of the set's rows with the current content
+    reseed: fn(&Connection) -> Result<()>,
+}
+
+const SEED_SETS: &[SeedSet] = &[
+    SeedSet {
+        name: "pages",
+        content_hash: pages::content_hash,
+        reseed: pages::run,
+    },
+    SeedSet {
+        name: "non_player_characters",
+        content_hash: non_player_characters::content_hash,
+        reseed: non_player_characters::run,
+    },
+    SeedSet {
+        name: "books",
+        content_hash: books::content_hash,
+        reseed: books::run,
+    },
+    SeedSet {
+        name: "dialogue",
+        content_hash: dialogue::content_hash,
+        reseed: dialogue::run,
+    },
+];
+
+/// Seeds every set whose content has changed since it was last seeded (or was never seeded).
+///
+/// Returns the names of the sets that were seeded.
+pub fn run(conn: &Connection) -> Result<Vec<&'static str>> {
+    let mut seeded = Vec::new();
+
+    for seed_set in SEED_SETS {
+        let content_hash = (seed_set.content_hash)();
+
+        if stored_content_hash(conn, seed_set.name)?.as_deref() == Some(content_hash.as_str()) {
+            continue; // Up to date
+        }
 
-    pages::run(conn);
-    non_player_characters::run(conn);
-    books::run(conn);
+        if seeded.is_empty() {
+            println!("Running seeds!");
+        }
+
+        // Each set is replaced atomically, so a failed seed can't leave half of the content behind.
+        //
+        // Foreign keys are only checked on commit, since player-owned rows (e.g., decisions)
+        // point at content that is briefly deleted while it's re-seeded with the same ids.
+        let tx = conn.unchecked_transaction()?;
+        tx.pragma_update(None, "defer_foreign_keys", true)?;
+        (seed_set.reseed)(&tx)?;
+        store_content_hash(&tx, seed_set.name, &content_hash)?;
+        tx.commit()?;
+
+        seeded.push(seed_set.name);
+    }
+
+    Ok(seeded)
+}
+
+/// The hash of a seed set's content, as of the last time it was seeded into this save
+pub fn stored_content_hash(conn: &Connection, name: &str) -> Result<Option<String>> {
+    conn.query_row(
+        &format!("SELECT hash FROM {} WHERE name = ?1", CONTENT_VERSION_TABLE),
+        params![name],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn store_content_hash(conn: &Connection, name: &str, hash: &str) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO {} (name, hash, updated_at) VALUES (?1, ?2, ?3)
+                ON CONFLICT (name) DO UPDATE SET hash = excluded.hash, updated_at = excluded.updated_at",
+            CONTENT_VERSION_TABLE
+        ),
+        params![name, hash, chrono::Local::now().naive_local()],
+    )?;
+    Ok(())
+}
 
-    dialogue::run(conn);
+/// Hex-encoded sha256 of some content
+fn hash_content(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
 }
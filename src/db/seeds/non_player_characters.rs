@@ -1,14 +1,34 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, Result};
 
+use crate::db::NPC_TABLE;
 use crate::models::non_player_character::non_player_character::NPC;
 use crate::models::player::gender::Gender;
 
-pub fn run(conn: &Connection) {
+/// (id, name, gender)
+///
+/// NOTE: It's important that the Narrator's id is 1 for the dialogue fixtures
+const NPCS: &[(i32, &str, Gender)] = &[
+    (1, "Narrator", Gender::Male),
+    // Random encounters
+    (2, "Bat", Gender::Unspecified),
+    (3, "Bandit", Gender::Unspecified),
+];
+
+pub fn content_hash() -> String {
+    super::hash_content(format!("{:?}", NPCS).as_bytes())
+}
+
+pub fn run(conn: &Connection) -> Result<()> {
     println!("Seeding non-player characters...");
 
-    // NOTE: It's important that the Narrator's id is 1 for the dialogue fixtures
-    let npc = NPC::new("Narrator".to_string(), Gender::Male);
-    npc.create(conn).unwrap();
+    conn.execute(&format!("DELETE FROM {}", NPC_TABLE), [])?;
+
+    for (id, name, gender) in NPCS {
+        let mut npc = NPC::new(name.to_string(), gender.clone());
+        npc.id = *id;
+        npc.create(conn)?;
+    }
 
     // TODO: Seed their books after creation? Narrator doesn't have a book obviously
+    Ok(())
 }
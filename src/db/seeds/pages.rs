@@ -1,7 +1,14 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, Result};
 
-pub fn run(_conn: &Connection) {
+pub fn content_hash() -> String {
+    super::hash_content(b"")
+}
+
+pub fn run(_conn: &Connection) -> Result<()> {
     println!("Seeding pages...");
 
     // TODO: Seed all of the pages in TBG!
+    //       - Re-seeding must only replace the game's pages, not the player's
+    //         collection (see the FIXME on the pages table)
+    Ok(())
 }
@@ -1,14 +1,50 @@
+use crate::game_engine::interactions::methods::menu_select::show_menu;
+use crate::game_engine::routines::map_editor::MapEditorRoutine;
+use crate::terminal_utils::{clear_console, get_input};
+use crate::tui::menu::Menu;
+use crate::world::atlas::World;
+use crate::world::editor::MapEditor;
+use crate::world::map::Map;
 use crossterm::terminal;
 
-/// WIP implementation of book building!
+const NEW_MAP: &str = "New map";
+const NEW_MAP_SIZE: (usize, usize) = (40, 20);
+
+/// Opens the map editor on one of the world's maps, or a new one
 pub fn run() {
-    // Enable raw mode
-    terminal::enable_raw_mode().unwrap();
+    let world = World::default();
+    let mut options = world.map_names().unwrap_or_default();
+    options.push(NEW_MAP.to_string());
+
+    clear_console(None);
+    let menu = Menu::new("Which map do you want to edit? (Esc to quit)", options).with_cancel();
+    let Some(choice) = show_menu(menu) else {
+        return;
+    };
 
-    // loop {
-    //     // Update size dynamically
-    //     viewport.update_size();
-    // }
+    let editor = if choice == NEW_MAP {
+        println!("\rName of the new map:");
+        let name = get_input();
+        if name.is_empty() {
+            terminal::disable_raw_mode().unwrap();
+            return;
+        }
+        let (width, height) = NEW_MAP_SIZE;
+        MapEditor::new(
+            Map::new(width, height).with_name(&name),
+            world.map_path(&name),
+        )
+    } else {
+        match MapEditor::open(world.map_path(&choice)) {
+            Ok(editor) => editor,
+            Err(e) => {
+                terminal::disable_raw_mode().unwrap();
+                println!("{}", e);
+                return;
+            }
+        }
+    };
 
-    terminal::disable_raw_mode().unwrap();
+    MapEditorRoutine::new(editor).run();
+    clear_console(None);
 }
@@ -1,5 +1,8 @@
 use super::routines::dialogue::DialogueRoutine;
 use crate::db;
+use crate::db::error::DbError;
+use crate::db::save::{next_slot_name, SaveSlot};
+use crate::game_engine::interactions::methods::menu_select::menu_select;
 use crate::game_engine::routines;
 // use crate::models::dialogue::decision::capture_decision;
 use crate::models::dialogue::dialogue;
@@ -7,15 +10,17 @@ use crate::models::game_state;
 use crate::models::player;
 use crate::music::music_player::MusicPlayer;
 use crate::terminal_utils;
-use game_state::game_state::GameState;
+use game_state::game_state::{GameState, SqlDuration};
 use player::gender::Gender;
 use player::height::Height;
 use player::player::Player;
-// TODO: Add battle::BattleRoutine, book_builder::BookBuilderRoutine
-use super::interface_mode::InterfaceMode;
+// TODO: Add book_builder::BookBuilderRoutine
+use crate::models::non_player_character::non_player_character::NPC;
 use routines::{
-    book_tutorial::BookTutorialRoutine, title_menu::TitleMenuRoutine,
-    world_navigation::WorldNavigationRoutine,
+    battle::BattleRoutine,
+    book_tutorial::BookTutorialRoutine,
+    title_menu::TitleMenuRoutine,
+    world_navigation::{NavigationOutcome, WorldNavigationRoutine},
 };
 use rusqlite::{Connection, Result};
 use std::error::Error;
@@ -23,58 +28,104 @@ use std::time::Instant;
 
 pub struct GameEngine {
     music_player: MusicPlayer,
-    conn: Connection,
+    conn: Option<Connection>, // None until the player picks a save slot
     session_start: Instant,
+    new_game: bool, // Skip the save-selection menu, and start in a fresh slot
 }
 
 impl GameEngine {
     pub fn new() -> Self {
         let music_player = MusicPlayer::new();
-        let conn = db::connection::get_connection(None)
-            .expect("Failed to initialize database connection in game engine constructor");
         let session_start = Instant::now();
 
         Self {
             music_player,
-            conn,
+            conn: None,
             session_start,
+            new_game: false,
         }
     }
 
+    /// Starts a new game in a fresh save slot, instead of letting the player pick one.
+    /// Existing saves are left alone.
+    pub fn with_new_game(mut self) -> Self {
+        self.new_game = true;
+        self
+    }
+
+    /// The connection to the currently loaded save slot
+    fn conn(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("No save slot loaded. Was the title menu skipped?")
+    }
+
+    /// Opens the connection to a save slot (creating the save if it doesn't exist yet).
+    ///
+    /// Fails if the save couldn't be migrated, or was written by a newer version of TBG.
+    fn load_save(&mut self, slot: &SaveSlot) -> Result<(), DbError> {
+        let conn = db::connection::get_connection(Some(&slot.file_name()))?;
+        self.conn = Some(conn);
+        Ok(())
+    }
+
+    /// Adds the time spent in this session to the player's total play time
+    fn record_play_time(&self) -> Result<(), Box<dyn Error>> {
+        let conn = match &self.conn {
+            Some(conn) => conn,
+            None => return Ok(()), // Never got past the title menu
+        };
+
+        let player = match Player::load(conn)? {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+
+        if let Some(mut game_state) = GameState::load_for_player(conn, player.id)? {
+            game_state.total_play_time =
+                SqlDuration(game_state.total_play_time.0 + self.session_start.elapsed());
+            game_state.update(conn)?;
+        }
+
+        Ok(())
+    }
+
     pub fn start(&mut self) {
         if false {
             // Hard-code this to off for now... It's annoying!
             self.music_player.play();
         }
 
-        // FIXME: on exit, update game state with new
-        //        total_play_time (now - session_start)
-        //
-        //        Can we catch all errors and exit states and run
+        // FIXME: Can we catch all errors and exit states and run
         //        cleanup code?
         self.start_game().expect("Failed to start game");
 
-        println!("{:?}", self.session_start);
+        self.record_play_time()
+            .expect("Failed to record total play time");
     }
 
     pub fn start_game(&mut self) -> Result<(), Box<dyn Error>> {
         // Run title screen and menu routine
         // - Show title screen
-        // - TODO: Let user select save file
-        TitleMenuRoutine.new().run();
+        // - Let user select (or create, copy, delete) a save file
+        match self.new_game {
+            // The slot doesn't exist yet, so loading it creates it
+            true => self.load_save(&SaveSlot::new(&next_slot_name()))?,
+            false => TitleMenuRoutine::new().run(|slot| self.load_save(slot))?,
+        }
 
         // Start game by loading player, or
         // guiding them through the character creation intro
-        let mut player = match Player::load(&self.conn)? {
+        let mut player = match Player::load(self.conn())? {
             Some(player) => player,
             None => {
                 // New player!
                 // Load the character creation dialogue tree.
-                let root_node = dialogue::load_by_root_name(&self.conn, "character_creation")
+                let root_node = dialogue::load_by_root_name(self.conn(), "character_creation")
                     .unwrap()
                     .unwrap();
 
-                let mut dialogue_routine = DialogueRoutine::new(root_node);
+                let mut dialogue_routine = DialogueRoutine::new(self.conn(), root_node);
                 let args = dialogue_routine.run();
 
                 // Unpack args (player's name)
@@ -82,25 +133,25 @@ impl GameEngine {
 
                 // We save with a default Gender and Height. These get overwritten in the next steps.
                 let mut new_player = Player::new(player_name, Gender::Male, Height::Average);
-                new_player = new_player.create(&self.conn);
+                new_player = new_player.create(self.conn())?;
 
                 // Using the newly created player's id from the database,
                 // create the player's game state
-                GameState::new(new_player.id).create(&self.conn);
+                GameState::new(new_player.id).create(self.conn())?;
 
                 new_player
             }
         };
 
         // Reload game state
-        let mut game_state = GameState::load_for_player(&self.conn, player.id)?.unwrap();
+        let mut game_state = GameState::load_for_player(self.conn(), player.id)?.unwrap();
 
         // FIXME: Implement this as a routine
         if game_state.current_stage == "character_creation" {
             // Start gender selection experience
             // FIXME: start_timer (so we can capture deliberation time)...
             let options = vec![Gender::Male, Gender::Female, Gender::Unspecified];
-            let gender = self.menu_select(
+            let gender = menu_select(
                 "Wait. One more thing before we continue. Are you a...:",
                 options,
             );
@@ -109,7 +160,7 @@ impl GameEngine {
 
             // Update player's gender
             player.gender = gender.clone();
-            player.update(&self.conn)?;
+            player.update(self.conn())?;
 
             terminal_utils::simulate_typing(&format!("You selected: {}", player.gender));
             terminal_utils::prompt_enter_to_continue();
@@ -122,7 +173,7 @@ impl GameEngine {
                 Height::VeryTall,
             ];
             // FIXME: start_timer (so we can capture deliberation time)...
-            let height = self.menu_select(
+            let height = menu_select(
                 "And, I know this is maybe a weird question, but I have to ask. How tall are you?:",
                 options,
             );
@@ -131,14 +182,14 @@ impl GameEngine {
 
             // Update player's height
             player.height = height.clone();
-            player.update(&self.conn)?;
+            player.update(self.conn())?;
 
             // Update game state, finished with choosing their name and gender
             game_state.current_stage = "book_tutorial".to_string();
-            game_state.update(&self.conn);
+            game_state.update(self.conn())?;
 
             // Reload player
-            player = Player::load(&self.conn)?.unwrap();
+            player = Player::load(self.conn())?.unwrap();
 
             terminal_utils::simulate_typing(&format!("You selected: {}", player.height));
             terminal_utils::prompt_enter_to_continue();
@@ -154,14 +205,26 @@ impl GameEngine {
         );
         terminal_utils::prompt_enter_to_continue();
 
-        let new_inferface_mode = WorldNavigationRoutine::new(game_state).run();
-        // let new_inferface_mode = InterfaceMode::BookBuilder;  // swap out for easier development
-
-        // Handle new interface mode (for now this is just BookBuilder)
-        if new_inferface_mode == InterfaceMode::BookBuilder {
-            BookTutorialRoutine::new(player).run();
-        } else {
-            panic!("For development purposes, this is just handling BookBuilder")
+        // Explore the world, and run whatever the player sets off along the way
+        let mut navigation =
+            WorldNavigationRoutine::new(self.conn(), &mut game_state, player.seed());
+        loop {
+            match navigation.run()? {
+                NavigationOutcome::StartDialogue { root_name } => {
+                    if let Some(root_node) = dialogue::load_by_root_name(self.conn(), &root_name)? {
+                        DialogueRoutine::new(self.conn(), root_node).run();
+                    }
+                }
+                NavigationOutcome::StartBattle { npc_id } => {
+                    if let Some(enemy) = NPC::load_by_id(self.conn(), npc_id)? {
+                        BattleRoutine::new(player.clone(), enemy).run();
+                    }
+                }
+                NavigationOutcome::OpenBookBuilder => {
+                    BookTutorialRoutine::new(player.clone()).run()
+                }
+                NavigationOutcome::Exit => break,
+            }
         }
 
         Ok(())
@@ -1,53 +1,90 @@
-/// menu_select.rs is an impl block for GameEngine which abstracts out
-/// selecting items from a menu.
+/// menu_select.rs runs a `tui::menu::Menu` in the terminal: it types out the menu's message,
+/// then draws the menu in a window after every key press until an option is picked.
 ///
-use crate::game_engine::game_engine::GameEngine;
-use crate::terminal_utils;
+/// It's a free fn, so that routines which run before the GameEngine has loaded a save
+/// (e.g., the title menu) can use it too.
+///
+use crate::terminal_utils::clear_console;
+use crate::tui::backend::{Backend, TerminalBackend};
+use crate::tui::frame::Frame;
+use crate::tui::menu::{Menu, MenuChoice};
+use crate::tui::size_guard;
+use crate::tui::window::Window;
 use crossterm::{
     cursor::{Hide, Show},
-    event::{self, Event, KeyCode},
+    event::Event,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode},
 };
 use std::io;
+use std::thread;
+use std::time::Duration;
 
-impl GameEngine {
-    pub fn menu_select<T: Clone + std::fmt::Display>(&self, message: &str, options: Vec<T>) -> T {
-        let mut stdout = io::stdout();
-        enable_raw_mode().expect("Failed to enable raw mode");
-
-        let mut selected_index = 0;
-        execute!(stdout, Hide).expect("Cursor failed to hide");
-
-        terminal_utils::print_menu(message, &options, selected_index, true) // pass a slice
-            .expect("Printing menu failed");
-
-        let selected_item = loop {
-            if let Ok(Event::Key(key_event)) = event::read() {
-                match key_event.code {
-                    KeyCode::Up => {
-                        if selected_index > 0 {
-                            selected_index -= 1;
-                        }
-                    }
-                    KeyCode::Down => {
-                        if selected_index < options.len() - 1 {
-                            selected_index += 1;
-                        }
-                    }
-                    KeyCode::Enter => break options[selected_index].clone(),
-                    _ => break options[0].clone(), // Default to first option on unexpected input
-                }
-
-                terminal_utils::print_menu(message, &options, selected_index, false)
-                    .expect("Printing menu failed");
-            }
-        };
+/// Room the window needs around the menu's options: its borders, the message, and a few lines
+/// for scroll arrows, the filter, and notices
+const MENU_CHROME_HEIGHT: usize = 10;
+
+/// Shows a menu of `options`, and returns the one picked
+pub fn menu_select<T: Clone + std::fmt::Display>(message: &str, options: Vec<T>) -> T {
+    show_menu(Menu::new(message, options)).expect("A menu without a cancel can't be cancelled")
+}
 
-        disable_raw_mode().expect("Failed to disable raw mode");
-        execute!(stdout, Show).expect("Cursor failed to show");
-        terminal_utils::clear_console(None);
+/// Shows a menu until an option is picked, and returns it, or until it's cancelled (if it
+/// can be), and returns `None`
+pub fn show_menu<T: Clone>(mut menu: Menu<T>) -> Option<T> {
+    let mut stdout = io::stdout();
+    let mut backend = TerminalBackend::stdout();
+    enable_raw_mode().expect("Failed to enable raw mode");
+    execute!(stdout, Hide).expect("Cursor failed to hide");
 
-        selected_item
+    let (mut width, height) = backend.size().unwrap_or((80, 24));
+    menu.set_page_size(height.saturating_sub(MENU_CHROME_HEIGHT));
+
+    // Type the message out first, like the rest of the game's text
+    for typed in 1..=menu.get_message().chars().count() {
+        draw(&mut backend, &menu.render_typing(menu_width(width), typed));
+        thread::sleep(Duration::from_millis(25));
     }
+    draw(&mut backend, &menu.render(menu_width(width)));
+
+    let selected_item = loop {
+        match size_guard::read_event(Duration::from_millis(100)) {
+            Some(Event::Key(key_event)) => match menu.handle_key(key_event) {
+                Some(MenuChoice::Picked(item)) => break Some(item),
+                Some(MenuChoice::Cancelled) => break None,
+                None => {}
+            },
+            Some(Event::Resize(..)) => {
+                let height;
+                (width, height) = backend.size().unwrap_or((80, 24));
+                menu.set_page_size(height.saturating_sub(MENU_CHROME_HEIGHT));
+                backend.invalidate();
+            }
+            _ => continue,
+        }
+        draw(&mut backend, &menu.render(menu_width(width)));
+    };
+
+    disable_raw_mode().expect("Failed to disable raw mode");
+    execute!(stdout, Show).expect("Cursor failed to show");
+    clear_console(None);
+
+    selected_item
+}
+
+/// How wide a menu can be in a window on a screen `screen_width` wide: the window's borders,
+/// and the column of space it leaves inside each of them, take 4 columns. Any wider, and the
+/// window would wrap the menu's lines again.
+pub fn menu_width(screen_width: usize) -> usize {
+    screen_width.saturating_sub(4)
+}
+
+/// Draws the menu in the middle of a window
+pub fn draw(backend: &mut impl Backend, frame: &Frame) {
+    let content: Vec<String> = (0..frame.get_height())
+        .map(|y| frame.styled_row(y))
+        .collect();
+    Window::new(&content.join("\n"))
+        .render_to(backend)
+        .expect("Printing menu failed");
 }
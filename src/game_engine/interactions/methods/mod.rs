@@ -1,7 +1,6 @@
 //! This module (tbg::game_engine::interactions::methods) holds methods which execute interactions.
 //!
-//! I.e., the menu_select method is actually just an impl block which
-//! implements the menu_select fn on the GameEngine struct.
+//! I.e., menu_select runs a tui::menu::Menu in the terminal, and returns what was picked.
 //!
 //! TODO: The whole "interactions" folder might just be impl blocks on GameEngine...
 //!       - [ ] Remove the methods folder and use the interactions folder for this?
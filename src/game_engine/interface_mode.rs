@@ -1,8 +1,9 @@
 use rusqlite::types::FromSql;
 use rusqlite::types::{FromSqlResult, ValueRef};
 use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InterfaceMode {
     Dialogue,
     WorldNavigation,
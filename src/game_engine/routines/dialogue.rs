@@ -1,12 +1,11 @@
-use crate::db;
 use crate::models::dialogue::dialogue::{process_dialogue_text, Dialogue};
 use crate::terminal_utils;
 use crossterm::terminal;
 use rusqlite::Connection;
 use std::collections::HashMap;
 
-pub struct DialogueRoutine {
-    conn: Connection,
+pub struct DialogueRoutine<'a> {
+    conn: &'a Connection,
     root_node: Dialogue,
 }
 
@@ -14,11 +13,8 @@ pub struct DialogueRoutine {
 /// - Starting a dialogue tree and running it to "completion" (until an end-node is reached)
 /// - Capturing and recording all decisions made in the dialogue tree (saved as `Decision`s in the database)
 /// - Returning any value(s) that was/were determined by this dialogue tree
-impl DialogueRoutine {
-    pub fn new(root_node: Dialogue) -> Self {
-        // TODO: I am opening a new conn here because I don't want to deal with lifetimes. Not great.
-        let conn = db::connection::get_connection(None)
-            .expect("Failed to initialize database connection in dialogue routine");
+impl<'a> DialogueRoutine<'a> {
+    pub fn new(conn: &'a Connection, root_node: Dialogue) -> Self {
         DialogueRoutine { conn, root_node }
     }
 
@@ -74,7 +70,7 @@ impl DialogueRoutine {
                 terminal_utils::prompt_enter_to_continue();
                 break;
             } else {
-                current_node = current_node.next(&self.conn).unwrap().unwrap();
+                current_node = current_node.next(self.conn).unwrap().unwrap();
             }
         }
 
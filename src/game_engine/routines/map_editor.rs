@@ -0,0 +1,267 @@
+use crate::terminal_utils::get_input;
+use crate::tui::size_guard;
+use crate::world::editor::{MapEditor, PALETTE};
+use crate::world::map::tile_to_char;
+use crate::world::navigation::Direction;
+use crate::world::viewport::Viewport;
+use crossterm::cursor::{MoveTo, Show};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{execute, style::Print};
+use std::io;
+use std::time::Duration;
+
+const HELP: &str = "Arrows: move  0-9/Tab: tile  Space: paint  v: select  f: fill  s: spawn  t: trigger  n: NPC  Del: erase  u/r: undo/redo  w: save  Esc: quit";
+
+/// Something the editor can be told to do, from a key press
+#[derive(Debug, PartialEq, Eq)]
+pub enum EditorAction {
+    Move(Direction),
+    SelectTile(usize), // Index into the palette
+    NextTile,
+    Paint,
+    ToggleSelection,
+    Fill,
+    PlaceSpawn,
+    PlaceTrigger,
+    PlaceNpc,
+    Erase,
+    Undo,
+    Redo,
+    Save,
+    Help,
+    Quit,
+}
+
+impl EditorAction {
+    pub fn from_key(key: KeyEvent) -> Option<EditorAction> {
+        let action = match key.code {
+            KeyCode::Up => EditorAction::Move(Direction::Up),
+            KeyCode::Down => EditorAction::Move(Direction::Down),
+            KeyCode::Left => EditorAction::Move(Direction::Left),
+            KeyCode::Right => EditorAction::Move(Direction::Right),
+            KeyCode::Char(c @ '1'..='9') => EditorAction::SelectTile(c as usize - '1' as usize),
+            KeyCode::Char('0') => EditorAction::SelectTile(9),
+            KeyCode::Tab => EditorAction::NextTile,
+            KeyCode::Char(' ') => EditorAction::Paint,
+            KeyCode::Char('v') => EditorAction::ToggleSelection,
+            KeyCode::Char('f') => EditorAction::Fill,
+            KeyCode::Char('s') => EditorAction::PlaceSpawn,
+            KeyCode::Char('t') => EditorAction::PlaceTrigger,
+            KeyCode::Char('n') => EditorAction::PlaceNpc,
+            KeyCode::Delete | KeyCode::Backspace => EditorAction::Erase,
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                EditorAction::Undo
+            }
+            KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                EditorAction::Redo
+            }
+            KeyCode::Char('u') => EditorAction::Undo,
+            KeyCode::Char('r') => EditorAction::Redo,
+            KeyCode::Char('w') => EditorAction::Save,
+            KeyCode::Char('?') => EditorAction::Help,
+            KeyCode::Esc => EditorAction::Quit,
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+/// Lets a developer edit a map in the terminal. Reachable with `--dev`.
+pub struct MapEditorRoutine {
+    editor: MapEditor,
+    message: Option<String>, // Shown instead of the status line until the next key press
+}
+
+impl MapEditorRoutine {
+    pub fn new(editor: MapEditor) -> Self {
+        MapEditorRoutine {
+            editor,
+            message: None,
+        }
+    }
+
+    /// Runs the editor until the developer quits. Quitting with unsaved changes has to be
+    /// asked for twice.
+    pub fn run(&mut self) {
+        terminal::enable_raw_mode().unwrap();
+        let mut viewport = Viewport::new();
+        let mut confirm_quit = false;
+
+        loop {
+            viewport.update_size();
+            let status = self.message.clone().unwrap_or_else(|| self.status());
+            let editor = &self.editor;
+            let frame =
+                viewport.draw_cells(editor.get_map(), editor.cursor(), Some(&status), |x, y| {
+                    editor.cell(x, y)
+                });
+            viewport.present(frame);
+
+            let Some(action) = read_action() else {
+                continue;
+            };
+            self.message = None;
+            if action != EditorAction::Quit {
+                confirm_quit = false;
+            }
+
+            match action {
+                EditorAction::Move(direction) => self.editor.move_cursor(direction),
+                EditorAction::SelectTile(index) => self.editor.select_tile(index),
+                EditorAction::NextTile => self.editor.next_tile(),
+                EditorAction::Paint => self.editor.paint(),
+                EditorAction::ToggleSelection => self.editor.toggle_selection(),
+                EditorAction::Fill => self.editor.fill(),
+                EditorAction::PlaceSpawn => {
+                    let name = prompt(&mut viewport, "Spawn name:");
+                    if !name.is_empty() {
+                        self.editor.place_spawn(&name);
+                    }
+                }
+                EditorAction::PlaceTrigger => {
+                    let text = prompt(
+                        &mut viewport,
+                        "Trigger (id enter|inspect once|repeat event [args...]):",
+                    );
+                    if !text.is_empty() {
+                        if let Err(e) = self.editor.place_trigger(&text) {
+                            self.message = Some(format!("Invalid trigger: {}", e.message));
+                        }
+                    }
+                }
+                EditorAction::PlaceNpc => {
+                    let text = prompt(
+                        &mut viewport,
+                        "NPC (id npc_id facing [dialogue root] [sprite c] [behavior]):",
+                    );
+                    if !text.is_empty() {
+                        if let Err(e) = self.editor.place_npc(&text) {
+                            self.message = Some(format!("Invalid NPC: {}", e.message));
+                        }
+                    }
+                }
+                EditorAction::Erase => {
+                    if !self.editor.erase() {
+                        self.message = Some("Nothing to erase here.".to_string());
+                    }
+                }
+                EditorAction::Undo => {
+                    if !self.editor.undo() {
+                        self.message = Some("Nothing to undo.".to_string());
+                    }
+                }
+                EditorAction::Redo => {
+                    if !self.editor.redo() {
+                        self.message = Some("Nothing to redo.".to_string());
+                    }
+                }
+                EditorAction::Save => {
+                    self.message = Some(match self.editor.save() {
+                        Ok(()) => format!("Saved {}", self.editor.get_path().display()),
+                        Err(e) => format!("Failed to save: {}", e),
+                    });
+                }
+                EditorAction::Help => self.message = Some(HELP.to_string()),
+                EditorAction::Quit => {
+                    if !self.editor.is_modified() || confirm_quit {
+                        break;
+                    }
+                    confirm_quit = true;
+                    self.message = Some(
+                        "Unsaved changes! Press Esc again to quit without saving.".to_string(),
+                    );
+                }
+            }
+        }
+
+        execute!(io::stdout(), Show).unwrap();
+        terminal::disable_raw_mode().unwrap();
+    }
+
+    /// The line under the map: where the cursor is, what's being painted, and so on
+    fn status(&self) -> String {
+        let (x, y) = self.editor.cursor();
+        // Each tile as its number key and how it's drawn, since the names don't all fit
+        let palette: Vec<String> = PALETTE
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| {
+                let key = (i + 1) % 10;
+                let glyph = tile_to_char(*tile, None);
+                if *tile == self.editor.tile() {
+                    format!("[{}{}]", key, glyph)
+                } else {
+                    format!("{}{}", key, glyph)
+                }
+            })
+            .collect();
+
+        format!(
+            "{}{} ({},{}) {} {}{}  ? for help",
+            self.editor.get_map().get_name(),
+            if self.editor.is_modified() { "*" } else { "" },
+            x,
+            y,
+            palette.join(" "),
+            self.editor.tile().name(),
+            if self.editor.is_selecting() {
+                "  selecting"
+            } else {
+                ""
+            },
+        )
+    }
+}
+
+fn read_action() -> Option<EditorAction> {
+    match size_guard::read_event(Duration::from_millis(100)) {
+        Some(Event::Key(key)) => EditorAction::from_key(key),
+        _ => None,
+    }
+}
+
+/// Asks for a line of text under the map. The screen is redrawn afterwards.
+fn prompt(viewport: &mut Viewport, label: &str) -> String {
+    execute!(
+        io::stdout(),
+        MoveTo(0, viewport.height as u16),
+        Clear(ClearType::FromCursorDown),
+        Print(label),
+        Show
+    )
+    .unwrap();
+    let input = get_input(); // Clears the screen
+    viewport.invalidate();
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(
+            EditorAction::from_key(key(KeyCode::Left)),
+            Some(EditorAction::Move(Direction::Left))
+        );
+        assert_eq!(
+            EditorAction::from_key(key(KeyCode::Char('3'))),
+            Some(EditorAction::SelectTile(2))
+        );
+        assert_eq!(
+            EditorAction::from_key(key(KeyCode::Char('0'))),
+            Some(EditorAction::SelectTile(9))
+        );
+        assert_eq!(
+            EditorAction::from_key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+            Some(EditorAction::Undo)
+        );
+        assert_eq!(EditorAction::from_key(key(KeyCode::Char('q'))), None);
+    }
+}
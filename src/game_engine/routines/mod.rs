@@ -11,6 +11,7 @@
 //! - `Dialogue` – Interactive text sequences with decision-making elements.
 //! - `BookBuilder` – Inventory-style interface for book building / editing (similar to  a deck builder)
 //! - `WorldNavigation` – Free-roam movement and world exploration.
+//! - `MapEditor` – Editing maps in the terminal (a developer tool, reached with `--dev`).
 //!
 //! Each Routine is built from `GameEngine::Interactions`, orchestrating user input,
 //! game state updates, and rendering.
@@ -35,5 +36,6 @@ pub mod battle;
 pub mod book_builder;
 pub mod book_tutorial;
 pub mod dialogue;
+pub mod map_editor;
 pub mod title_menu;
 pub mod world_navigation;
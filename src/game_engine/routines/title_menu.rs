@@ -1,17 +1,176 @@
-use crate::terminal_utils::{prompt_enter_to_continue, title_screen};
+use crate::db::error::DbError;
+use crate::db::save::{
+    copy_save, delete_save, list_save_slots, load_save_preview, next_slot_name, SaveSlot,
+};
+use crate::game_engine::interactions::methods::menu_select::{menu_select, show_menu};
+use crate::terminal_utils::{prompt_enter_to_continue, simulate_typing, title_screen};
+use crate::tui::menu::Menu;
 use crossterm::terminal;
+use std::fmt;
 
+#[derive(Default)]
 pub struct TitleMenuRoutine;
 
+/// An entry in the save-selection menu
+#[derive(Clone)]
+enum SaveMenuOption {
+    Slot(SaveSlot, String, bool), // The slot, its rendered preview, and if it's too new to load
+    NewGame,
+}
+
+impl fmt::Display for SaveMenuOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveMenuOption::Slot(slot, preview, _) => write!(f, "{}: {}", slot, preview),
+            SaveMenuOption::NewGame => write!(f, "New game"),
+        }
+    }
+}
+
+/// What to do with a save slot, once it's been picked
+#[derive(Clone)]
+enum SlotAction {
+    Continue,
+    Copy,
+    Delete,
+    Back,
+}
+
+impl fmt::Display for SlotAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action_str = match self {
+            SlotAction::Continue => "Continue",
+            SlotAction::Copy => "Copy",
+            SlotAction::Delete => "Delete",
+            SlotAction::Back => "Back",
+        };
+        write!(f, "{}", action_str)
+    }
+}
+
 impl TitleMenuRoutine {
-    pub fn new(&self) -> Self {
+    pub fn new() -> Self {
         TitleMenuRoutine
     }
 
-    pub fn run(&mut self) {
+    /// Shows the title screen, then lets the player pick which save slot to play, and opens
+    /// it with `load_save`. If the player chose to start a new game, the slot won't exist
+    /// yet--`get_connection` will create it.
+    ///
+    /// If a save can't be opened, the player is told why, and picks again. The error is only
+    /// returned if there was nothing else to pick.
+    pub fn run(
+        &mut self,
+        mut load_save: impl FnMut(&SaveSlot) -> Result<(), DbError>,
+    ) -> Result<(), DbError> {
         terminal::enable_raw_mode().unwrap();
         title_screen();
         prompt_enter_to_continue();
         terminal::disable_raw_mode().unwrap();
+
+        loop {
+            let slots = list_save_slots().expect("Failed to list save slots");
+
+            // Nothing to pick from, so don't bother showing the menu
+            if slots.is_empty() {
+                return load_save(&SaveSlot::new(&next_slot_name()));
+            }
+
+            let mut options: Vec<SaveMenuOption> = slots
+                .into_iter()
+                .map(|slot| match load_save_preview(&slot) {
+                    Ok(preview) => {
+                        let from_newer_version = preview.from_newer_version;
+                        SaveMenuOption::Slot(slot, preview.to_string(), from_newer_version)
+                    }
+                    Err(_) => SaveMenuOption::Slot(slot, "(unreadable)".to_string(), false),
+                })
+                .collect();
+            options.push(SaveMenuOption::NewGame);
+
+            let (slot, from_newer_version) = match menu_select("Choose a save file:", options) {
+                SaveMenuOption::NewGame => {
+                    if self.try_load(&mut load_save, &SaveSlot::new(&next_slot_name())) {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                SaveMenuOption::Slot(slot, _, from_newer_version) => (slot, from_newer_version),
+            };
+
+            let actions = vec![
+                SlotAction::Continue,
+                SlotAction::Copy,
+                SlotAction::Delete,
+                SlotAction::Back,
+            ];
+            let mut menu = Menu::new(
+                &format!("What would you like to do with {}?", slot),
+                actions,
+            )
+            .with_cancel();
+            if from_newer_version {
+                menu = menu.with_disabled(0, "needs a newer version of TBG");
+            }
+            match show_menu(menu).unwrap_or(SlotAction::Back) {
+                SlotAction::Continue => {
+                    if self.try_load(&mut load_save, &slot) {
+                        return Ok(());
+                    }
+                }
+                SlotAction::Copy => {
+                    let copy = SaveSlot::new(&next_slot_name());
+                    match copy_save(&slot, &copy) {
+                        Ok(()) => simulate_typing(&format!("Copied {} to {}.", slot, copy)),
+                        Err(e) => simulate_typing(&format!("Failed to copy {}: {}", slot, e)),
+                    }
+                    self.prompt_enter_to_continue();
+                }
+                SlotAction::Delete => {
+                    // "No" comes first, so that it's what's selected to begin with
+                    let confirmation = show_menu(
+                        Menu::new(
+                            &format!(
+                                "Are you sure you want to delete {}? This can't be undone.",
+                                slot
+                            ),
+                            vec!["No", "Yes"],
+                        )
+                        .with_cancel(),
+                    );
+
+                    if confirmation == Some("Yes") {
+                        match delete_save(Some(&slot.file_name())) {
+                            Ok(()) => simulate_typing(&format!("Deleted {}.", slot)),
+                            Err(e) => simulate_typing(&format!("Failed to delete {}: {}", slot, e)),
+                        }
+                        self.prompt_enter_to_continue();
+                    }
+                }
+                SlotAction::Back => {}
+            }
+        }
+    }
+
+    /// Opens a slot, or tells the player why it couldn't be opened. Returns whether it was.
+    fn try_load(
+        &self,
+        load_save: &mut impl FnMut(&SaveSlot) -> Result<(), DbError>,
+        slot: &SaveSlot,
+    ) -> bool {
+        match load_save(slot) {
+            Ok(()) => true,
+            Err(e) => {
+                simulate_typing(&e.to_string());
+                self.prompt_enter_to_continue();
+                false
+            }
+        }
+    }
+
+    fn prompt_enter_to_continue(&self) {
+        terminal::enable_raw_mode().unwrap();
+        prompt_enter_to_continue();
+        terminal::disable_raw_mode().unwrap();
     }
 }
@@ -1,107 +1,495 @@
-use crate::game_engine::interface_mode::InterfaceMode;
+use crate::models::book::page::Page;
+use crate::models::game_state::explored_map::ExploredMap;
+use crate::models::game_state::fired_trigger::FiredTrigger;
 use crate::models::game_state::game_state::GameState;
-use crate::world::map_file_utils::generate_demo_map;
-use crate::world::navigation::{prompt_for_action, Direction, NavigationAction};
+use crate::models::game_state::opened_door::OpenedDoor;
+use crate::models::player::inventory_item::InventoryItem;
+use crate::models::player::player::Player;
+use crate::tui::backend::{Backend, TerminalBackend};
+use crate::tui::layout::{NavigationLayout, Rect};
+use crate::tui::message_log::MessageLog;
+use crate::tui::sidebar::Status;
+use crate::world::atlas::{World, WorldError, DEFAULT_SPAWN, STARTING_MAP};
+use crate::world::encounter::roll_encounter;
+use crate::world::fog::{Explored, Fog};
+use crate::world::interaction::{self, Interaction};
+use crate::world::map::{Ability, Map};
+use crate::world::minimap::world_map;
+use crate::world::navigation::{
+    action_to_direction, prompt_for_action, try_move, MoveOutcome, NavigationAction,
+};
+use crate::world::npc::tick_npcs;
+use crate::world::trigger::{Trigger, TriggerActivation, TriggerEvent, TriggerFrequency};
 use crate::world::viewport::Viewport;
-use crossterm::terminal;
+use crossterm::cursor::Show;
+use crossterm::{execute, terminal};
+use rusqlite::Connection;
+use std::error::Error;
+use std::io;
 
-pub struct WorldNavigationRoutine {
-    game_state: GameState,
+/// How far (in tiles) the player can walk around the middle of the screen before it scrolls
+const DEAD_ZONE: (usize, usize) = (8, 4);
+
+/// Why the world navigation routine stopped, i.e. what `GameEngine` should do next
+#[derive(Debug, PartialEq, Eq)]
+pub enum NavigationOutcome {
+    StartDialogue { root_name: String },
+    StartBattle { npc_id: i32 },
+    OpenBookBuilder,
+    Exit, // The player pressed Esc
+}
+
+pub struct WorldNavigationRoutine<'a> {
+    conn: &'a Connection,
+    game_state: &'a mut GameState,
+    world: World,
+    abilities: Vec<Ability>,
+    log: MessageLog,  // What the player's been told, shown under the map
+    map: Option<Map>, // Kept between runs, so NPCs stay where they walked to
+    fog: Option<Fog>, // What the player has seen of the map, saved when they leave it
+    seed: u64,        // The player's seed, which NPC movement and encounters derive from
+    tick: u64,        // Advances once per player action
 }
 
-impl WorldNavigationRoutine {
-    pub fn new(game_state: GameState) -> Self {
-        WorldNavigationRoutine { game_state }
+impl<'a> WorldNavigationRoutine<'a> {
+    pub fn new(conn: &'a Connection, game_state: &'a mut GameState, seed: u64) -> Self {
+        WorldNavigationRoutine {
+            conn,
+            game_state,
+            world: World::default(),
+            // TODO: Let the player learn abilities (e.g. swimming) as they progress
+            abilities: Vec::new(),
+            log: MessageLog::new(),
+            map: None,
+            fog: None,
+            seed,
+            tick: 0,
+        }
     }
 
     // Runs the world navigation routine until the player:
-    // - Enters a battle
-    // - Enters the book builder
+    // - Sets off a trigger that starts a dialogue, battle, or the book builder
     // - Enters the settings?
     // - Exits the game
     //
-    // FIXME: Is InterfaceMode the best result type to tell
-    //        GameEngine what to do next?
+    // Triggers that only move the player (even to another map), or advance the story,
+    // are handled here. The game state is saved before returning.
     //
-    //        Might want to define a "RoutineResult" or "RoutineAction"
-    //        or something that is more clear.
-    pub fn run(&mut self) -> InterfaceMode {
+    // The routine can be run again afterwards, and picks up where it left off.
+    pub fn run(&mut self) -> Result<NavigationOutcome, Box<dyn Error>> {
         terminal::enable_raw_mode().unwrap();
 
-        // FIXME: Load map based on some conditional
-        //        - Might need to add "current_map" to
-        //          GameState.
-        let map = generate_demo_map(500, 100);
+        let mut map = match self.map.take() {
+            Some(map) => map,
+            None => {
+                let mut map = self.enter_current_map()?;
+                self.restore_map(&mut map)?;
+                map
+            }
+        };
+        let mut fog = match self.fog.take() {
+            Some(fog) => fog,
+            None => self.load_fog(&map)?,
+        };
 
         // Instantiate viewport!
-        let mut viewport = Viewport::new();
+        let mut viewport = Viewport::new()
+            .with_dead_zone(DEAD_ZONE.0, DEAD_ZONE.1)
+            .without_message_line();
+        let mut backend = TerminalBackend::stdout().with_hidden_cursor();
+        let name = Player::load_by_id(self.conn, self.game_state.player_id)?
+            .map(|player| player.name)
+            .unwrap_or_default();
 
-        loop {
-            // Update size dynamically
-            viewport.update_size();
-            viewport.render(
+        let outcome = loop {
+            // The panes are worked out again every time, in case the terminal was resized
+            let layout = self.layout(&backend);
+            viewport.fit(layout.map.width, layout.map.height);
+            fog.update(&map, (self.game_state.x, self.game_state.y));
+            let fired = self.fired_on(&map)?;
+            viewport.set_fired_triggers(&fired);
+            let map_frame = viewport.draw(
                 &map,
                 self.game_state.x,
                 self.game_state.y,
                 self.game_state.direction,
+                None,
+                Some(&fog),
             );
-
-            // Arbitrary location for development purposes
-            // If player moves to the right 1 and down 2, exit and
-            // tell GameEngine to launch BookBuilder
-            if self.game_state.x == 1 && self.game_state.y == 2 {
-                return InterfaceMode::BookBuilder;
-            }
+            let status = Status {
+                name: name.clone(),
+                stage: self.game_state.current_stage.clone(),
+                location: map.get_name().to_string(),
+            };
+            backend.draw(&layout.draw(&map_frame, &status, &self.log))?;
 
             // FIXME: Should this *save* the player? And how often?
-            //
-            // TODO: Implement world interactions
-            //       - Pressing x to interact
-            //       - Crossing thresholds, loading new maps
-            //       - If the player crosses
-            //
-            if let Some(action) = prompt_for_action() {
-                match action {
-                    NavigationAction::Up => {
-                        if self.game_state.y > 0 && self.game_state.direction == Direction::Up {
-                            self.game_state.y -= 1; // Move up (decrease y) only if we're not at the top
+            let action = match prompt_for_action() {
+                Some(action) => action,
+                None => continue,
+            };
+            self.log.mark_read();
+
+            // Which tile the player activated, and how
+            let activated = match action {
+                NavigationAction::Up
+                | NavigationAction::Down
+                | NavigationAction::Left
+                | NavigationAction::Right => {
+                    let direction = action_to_direction(action).unwrap();
+                    match try_move(
+                        &map,
+                        self.game_state.x,
+                        self.game_state.y,
+                        self.game_state.direction,
+                        direction,
+                        &self.abilities,
+                    ) {
+                        MoveOutcome::Turned(direction) => {
+                            self.game_state.direction = direction;
+                            None
                         }
-                        self.game_state.direction = Direction::Up;
-                    }
-                    NavigationAction::Down => {
-                        if self.game_state.y < map.get_height() - 1
-                            && self.game_state.direction == Direction::Down
-                        {
-                            self.game_state.y += 1; // Move down (increase y) only if we're not at the bottom
+                        MoveOutcome::Moved { x, y } => {
+                            self.game_state.x = x;
+                            self.game_state.y = y;
+                            Some((x, y, TriggerActivation::Enter))
                         }
-                        self.game_state.direction = Direction::Down;
-                    }
-                    NavigationAction::Left => {
-                        if self.game_state.x > 0 && self.game_state.direction == Direction::Left {
-                            self.game_state.x -= 1; // Move left (decrease x) only if we're not at the left edge
+                        MoveOutcome::Blocked(bump_message) => {
+                            self.log.push(&bump_message);
+                            None
                         }
-                        self.game_state.direction = Direction::Left;
                     }
-                    NavigationAction::Right => {
-                        if self.game_state.x < map.get_width() - 1
-                            && self.game_state.direction == Direction::Right
-                        {
-                            self.game_state.x += 1; // Move right (increase x) only if we're not at the right edge
+                }
+                NavigationAction::Inspect => {
+                    // The PlayerFocus tile, i.e. the one the player is facing
+                    let x = self.game_state.x as isize + self.game_state.direction.dx();
+                    let y = self.game_state.y as isize + self.game_state.direction.dy();
+                    if !map.in_bounds(x, y) {
+                        continue;
+                    }
+                    let (x, y) = (x as usize, y as usize);
+
+                    // Talking to someone comes before anything else on their tile
+                    if let Some(npc) = map.npc_at(x, y) {
+                        match &npc.dialogue {
+                            Some(root_name) => {
+                                break NavigationOutcome::StartDialogue {
+                                    root_name: root_name.clone(),
+                                }
+                            }
+                            None => {
+                                self.log.push("They don't have anything to say.");
+                                continue;
+                            }
                         }
-                        self.game_state.direction = Direction::Right;
                     }
-                    NavigationAction::Resize => {
-                        println!("Updating size!!");
-                        viewport.update_size();
+
+                    // Doors, signs, chests, ...
+                    if let Some(interaction) = self.inspect_tile(&map, x, y)? {
+                        self.interact(&mut map, x, y, &interaction)?;
+                    }
+
+                    Some((x, y, TriggerActivation::Inspect))
+                }
+                // Neither of these take any time
+                NavigationAction::ToggleMinimap => {
+                    viewport.toggle_minimap();
+                    continue;
+                }
+                NavigationAction::WorldMap => {
+                    let screen = layout.screen;
+                    backend.draw(&world_map(
+                        &map,
+                        Some(&fog),
+                        &fired,
+                        (self.game_state.x, self.game_state.y),
+                        self.game_state.direction,
+                        screen.width,
+                        screen.height,
+                    ))?;
+                    prompt_for_action(); // Any key closes it
+                    continue;
+                }
+                NavigationAction::ScrollLogUp => {
+                    self.log
+                        .scroll_up(layout.log.height.saturating_sub(1).max(1));
+                    continue;
+                }
+                NavigationAction::ScrollLogDown => {
+                    self.log
+                        .scroll_down(layout.log.height.saturating_sub(1).max(1));
+                    continue;
+                }
+                // Resizing can leave bits of the old screen behind, so start over
+                NavigationAction::Resize => {
+                    backend.invalidate();
+                    continue;
+                }
+                _ => break NavigationOutcome::Exit,
+            };
+
+            // Everyone else gets to move after the player does
+            self.tick += 1;
+            tick_npcs(
+                &mut map,
+                (self.game_state.x, self.game_state.y),
+                self.seed,
+                self.tick,
+            );
+
+            if let Some((x, y, activation)) = activated {
+                let map_name = map.get_name().to_string();
+                if let Some(outcome) = self.fire_triggers(&mut map, x, y, activation)? {
+                    break outcome;
+                }
+
+                // The fog belongs to the map the player just left
+                if map.get_name() != map_name {
+                    self.save_fog(&map_name, &fog)?;
+                    fog = self.load_fog(&map)?;
+                }
+
+                let warped =
+                    map.get_name() != map_name || (self.game_state.x, self.game_state.y) != (x, y);
+                if warped {
+                    viewport.reset_camera();
+                }
+
+                // Stepping somewhere (without being warped away) might run into someone
+                if activation == TriggerActivation::Enter && !warped {
+                    if let Some(npc_id) = roll_encounter(&map, x, y, self.seed, self.tick) {
+                        break NavigationOutcome::StartBattle { npc_id };
                     }
-                    _ => break,
                 }
             }
-        }
+        };
 
+        execute!(io::stdout(), Show).unwrap();
         terminal::disable_raw_mode().unwrap();
-        // FIXME: This isn't a descriptive or very logical return type.
-        //        This is another indication that we shouldn't be returning an InterfaceMode
-        return InterfaceMode::WorldNavigation;
+
+        self.game_state.update(self.conn)?;
+        self.save_fog(map.get_name(), &fog)?;
+        self.map = Some(map);
+        self.fog = Some(fog);
+
+        Ok(outcome)
+    }
+
+    /// How the terminal is split up right now. The last row is left free, like the rest of
+    /// the game's screens.
+    fn layout(&self, backend: &impl Backend) -> NavigationLayout {
+        let (width, height) = backend.size().unwrap_or((80, 24)); // Default to 80x24 if it fails
+        NavigationLayout::new(Rect::new(0, 0, width, height.saturating_sub(1)))
+    }
+
+    /// Loads the map the player is on, and makes sure they're standing somewhere sensible on it
+    fn enter_current_map(&mut self) -> Result<Map, WorldError> {
+        let map = match self.world.map(&self.game_state.current_map) {
+            Ok(map) => map.clone(),
+            // The map was removed (or renamed) since the game was saved
+            Err(WorldError::UnknownMap(_)) => {
+                self.game_state.current_map = STARTING_MAP.to_string();
+                self.game_state.x = 0;
+                self.game_state.y = 0;
+                self.world.map(STARTING_MAP)?.clone()
+            }
+            Err(e) => return Err(e),
+        };
+
+        // New games start at 0,0, which may well be inside a wall
+        let (x, y) = (self.game_state.x, self.game_state.y);
+        if !map.in_bounds(x as isize, y as isize)
+            || !map.get_tile(x, y).is_passable(&self.abilities)
+        {
+            if let Some((x, y)) = map
+                .get_spawn(DEFAULT_SPAWN)
+                .or_else(|| map.nearest_passable(x, y, &self.abilities))
+            {
+                self.game_state.x = x;
+                self.game_state.y = y;
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// What the player has explored of the map so far. Starts over if the map changed size
+    /// since it was explored, since the old record wouldn't line up with it anymore.
+    fn load_fog(&self, map: &Map) -> rusqlite::Result<Fog> {
+        let (width, height) = (map.get_width(), map.get_height());
+        let explored = ExploredMap::load(self.conn, self.game_state.player_id, map.get_name())?
+            .and_then(|explored_map| explored_map.to_explored())
+            .filter(|explored| explored.size() == (width, height))
+            .unwrap_or_else(|| Explored::new(width, height));
+
+        Ok(Fog::new(explored))
+    }
+
+    fn save_fog(&self, map_name: &str, fog: &Fog) -> rusqlite::Result<()> {
+        ExploredMap::new(self.game_state.player_id, map_name, fog.explored()).save(self.conn)
+    }
+
+    /// Opens the chests the player has emptied, and unlocks (and opens) the doors they've
+    /// unlocked (and left open), on a freshly loaded copy of a map
+    fn restore_map(&self, map: &mut Map) -> rusqlite::Result<()> {
+        let player_id = self.game_state.player_id;
+        let used = self.fired_on(map)?;
+        let opened_doors: Vec<(usize, usize)> =
+            OpenedDoor::load_for_map(self.conn, player_id, map.get_name())?
+                .into_iter()
+                .map(|door| (door.x, door.y))
+                .collect();
+        interaction::restore(map, |id| used.iter().any(|used| used == id), &opened_doors);
+
+        Ok(())
+    }
+
+    /// The ids of the triggers (and one-off interactions) the player has fired on a map
+    fn fired_on(&self, map: &Map) -> rusqlite::Result<Vec<String>> {
+        Ok(
+            FiredTrigger::load_for_player(self.conn, self.game_state.player_id)?
+                .into_iter()
+                .filter(|fired| fired.map_name == map.get_name())
+                .map(|fired| fired.trigger_id)
+                .collect(),
+        )
+    }
+
+    /// What inspecting the tile at (x, y) would do, given what the player has and has done
+    fn inspect_tile(&self, map: &Map, x: usize, y: usize) -> rusqlite::Result<Option<Interaction>> {
+        let player_id = self.game_state.player_id;
+        let inventory = InventoryItem::load_for_player(self.conn, player_id)?;
+        let used: Vec<String> = map
+            .contents_at(x, y)
+            .map(|content| {
+                FiredTrigger::has_fired(self.conn, player_id, map.get_name(), &content.id)
+                    .map(|used| used.then(|| content.id.clone()))
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Ok(interaction::inspect(
+            map,
+            x,
+            y,
+            |item| {
+                inventory
+                    .iter()
+                    .any(|held| held.item == item && held.count > 0)
+            },
+            |id| used.iter().any(|used| used == id),
+        ))
+    }
+
+    /// Does what inspecting a tile does: swaps the tile, hands over what was found, and
+    /// records anything that shouldn't happen twice
+    fn interact(
+        &mut self,
+        map: &mut Map,
+        x: usize,
+        y: usize,
+        interaction: &Interaction,
+    ) -> rusqlite::Result<()> {
+        let player_id = self.game_state.player_id;
+
+        for id in interaction.used_ids() {
+            FiredTrigger::new(player_id, map.get_name(), &id).create(self.conn)?;
+        }
+        if let Interaction::Take { items, pages, .. } = interaction {
+            for (item, count) in items {
+                InventoryItem::new(player_id, item, *count).add(self.conn)?;
+            }
+            for page in pages {
+                Page::found(player_id, page).create(self.conn)?;
+            }
+        }
+        match interaction {
+            Interaction::OpenDoor | Interaction::Unlock { .. } => {
+                OpenedDoor::new(player_id, map.get_name(), x, y).create(self.conn)?
+            }
+            Interaction::CloseDoor => {
+                OpenedDoor::delete(self.conn, player_id, map.get_name(), x, y)?
+            }
+            _ => {}
+        }
+        if let Some(tile) = interaction.changes_tile(map.get_tile(x, y)) {
+            map.replace_tile(x, y, tile);
+        }
+        self.log.push(&interaction.message());
+
+        Ok(())
+    }
+
+    /// Moves the player to a spawn point on another (or the same) map
+    fn warp(&mut self, map_name: &str, spawn: &str) -> Result<Map, WorldError> {
+        let (x, y) = self.world.spawn_point(map_name, spawn)?;
+        let map = self.world.map(map_name)?.clone();
+
+        self.game_state.current_map = map_name.to_string();
+        self.game_state.x = x;
+        self.game_state.y = y;
+
+        Ok(map)
+    }
+
+    /// Fires the triggers at (x, y), in the order the map lists them.
+    ///
+    /// Stops at the first trigger that needs another routine, and returns it, or at the first
+    /// warp, since the rest of the triggers belong to the map the player just left.
+    fn fire_triggers(
+        &mut self,
+        map: &mut Map,
+        x: usize,
+        y: usize,
+        activation: TriggerActivation,
+    ) -> Result<Option<NavigationOutcome>, Box<dyn Error>> {
+        let triggers: Vec<Trigger> = map.triggers_at(x, y, activation).cloned().collect();
+
+        for trigger in triggers {
+            let player_id = self.game_state.player_id;
+
+            if trigger.frequency == TriggerFrequency::Once {
+                if FiredTrigger::has_fired(self.conn, player_id, map.get_name(), &trigger.id)? {
+                    continue;
+                }
+                FiredTrigger::new(player_id, map.get_name(), &trigger.id).create(self.conn)?;
+            }
+
+            match trigger.event {
+                TriggerEvent::StartDialogue { root_name } => {
+                    return Ok(Some(NavigationOutcome::StartDialogue { root_name }))
+                }
+                TriggerEvent::StartBattle { npc_id } => {
+                    return Ok(Some(NavigationOutcome::StartBattle { npc_id }))
+                }
+                TriggerEvent::OpenBookBuilder => {
+                    return Ok(Some(NavigationOutcome::OpenBookBuilder))
+                }
+                TriggerEvent::Warp {
+                    map: map_name,
+                    spawn,
+                } => {
+                    match self.warp(&map_name, &spawn) {
+                        Ok(new_map) => {
+                            *map = new_map;
+                            self.restore_map(map)?;
+                            self.game_state.update(self.conn)?;
+                        }
+                        // A broken portal shouldn't end the game. The map validator catches these.
+                        Err(e) => self.log.push(&format!("The way is blocked. ({})", e)),
+                    }
+                    return Ok(None);
+                }
+                TriggerEvent::AdvanceStage { epic, stage } => {
+                    self.game_state.current_epic = epic;
+                    self.game_state.current_stage = stage;
+                    self.game_state.update(self.conn)?;
+                }
+            }
+        }
+
+        Ok(None)
     }
 }
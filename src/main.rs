@@ -9,32 +9,59 @@ pub mod tui;
 pub mod world;
 use args::parse_args;
 pub use db::connection::get_connection;
-pub use db::save::{delete_save, save_exists};
+pub use db::save::{delete_save, save_exists, SaveSlot};
+use db::save_document::{export_save, import_save};
 use game_engine::game_engine::GameEngine;
 pub use game_engine::interactions;
 pub use game_engine::routines;
 pub use models::book::page;
 pub use models::dialogue;
+use world::atlas::World;
 pub use world::navigation;
+use world::validator::validate_world;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let game_args = parse_args();
+    let game_args = parse_args()?;
 
     if game_args.dev {
+        tui::size_guard::ensure_size()?;
         dev::dev::run();
         return Ok(()); // Early exit!
     }
 
-    if game_args.new_game {
-        if save_exists(None) {
-            delete_save(None)?;
-            println!("Previous save deleted. Starting a new game...");
-        } else {
-            println!("No existing save found. Starting a new game...");
+    if game_args.check_maps {
+        let reports = validate_world(&mut World::default())?;
+        for (name, problems) in &reports {
+            for problem in problems {
+                println!("{}: {}", name, problem);
+            }
+        }
+        if !reports.is_empty() {
+            return Err(format!("{} map(s) have problems", reports.len()).into());
         }
+        println!("Every map is valid");
+        return Ok(());
     }
 
+    if let Some((slot, file)) = &game_args.export_save {
+        export_save(&SaveSlot::new(slot), file)?;
+        println!("Exported {} to {:?}", slot, file);
+        return Ok(());
+    }
+
+    if let Some((file, slot)) = &game_args.import_save {
+        import_save(file, &SaveSlot::new(slot))?;
+        println!("Imported {:?} into {}", file, slot);
+        return Ok(());
+    }
+
+    // Wait for a terminal big enough to play in
+    tui::size_guard::ensure_size()?;
+
     let mut game_engine = GameEngine::new();
+    if game_args.new_game {
+        game_engine = game_engine.with_new_game();
+    }
     game_engine.start();
 
     Ok(())
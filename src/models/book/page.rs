@@ -1,6 +1,22 @@
-// Page
+//! A page in the player's collection.
+//!
+//! Pages found in the world (e.g. in a bookshelf) start out as just their title, written on
+//! the front. They don't have an art type or actions until the book builder sets them up.
+use crate::db::repository::{self, Model};
+use crate::db::PAGE_TABLE;
+use rusqlite::{params, Connection, Error as RusqliteError, Result, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Page {
-    _name: String,
+    pub id: i64,
+    pub player_id: i32,
+    pub book_art_type: Option<i64>,
+    pub primary_action_id: Option<i64>,
+    pub secondary_action_id: Option<i64>,
+    pub page_passive_id: Option<i64>,
+    pub front: String,
+    pub back: String,
 }
 
 pub enum PageSide {
@@ -8,6 +24,67 @@ pub enum PageSide {
     Back,
 }
 
+impl Model for Page {
+    const TABLE: &'static str = PAGE_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "player_id",
+        "book_art_type",
+        "primary_action_id",
+        "secondary_action_id",
+        "page_passive_id",
+        "front",
+        "back",
+    ];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(Page {
+            id: row.get(0)?,
+            player_id: row.get(1)?,
+            book_art_type: row.get(2)?,
+            primary_action_id: row.get(3)?,
+            secondary_action_id: row.get(4)?,
+            page_passive_id: row.get(5)?,
+            front: row.get(6)?,
+            back: row.get(7)?,
+        })
+    }
+}
+
+impl Page {
+    /// A loose page found in the world, with its title on the front
+    pub fn found(player_id: i32, title: &str) -> Self {
+        Page {
+            id: 0, // Set when it's created
+            player_id,
+            book_art_type: None,
+            primary_action_id: None,
+            secondary_action_id: None,
+            page_passive_id: None,
+            front: title.to_string(),
+            back: String::new(),
+        }
+    }
+
+    /// Adds the page to the player's collection
+    pub fn create(&self, conn: &Connection) -> Result<Page> {
+        conn.execute(
+            &format!(
+                "INSERT INTO {} (player_id, book_art_type, primary_action_id, secondary_action_id, page_passive_id, front, back) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                PAGE_TABLE
+            ),
+            params![self.player_id, self.book_art_type, self.primary_action_id, self.secondary_action_id, self.page_passive_id, self.front, self.back],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        repository::find_by_id(conn, id)?.ok_or(RusqliteError::QueryReturnedNoRows)
+    }
+
+    pub fn load_for_player(conn: &Connection, player_id: i32) -> Result<Vec<Page>> {
+        repository::find_all(conn, "WHERE player_id = ?1 ORDER BY id", [player_id])
+    }
+}
+
 // Page brainstorm
 // - Name
 // - Type (natus, metafora, bellum, sin, piety, etc.) -- BookArtType
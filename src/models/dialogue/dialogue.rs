@@ -1,13 +1,14 @@
 use super::character_type::{deserialize_character_type, CharacterType};
 use super::dialogue_response::DialogueResponse;
+use crate::db::repository::{self, Model};
 use crate::db::DIALOGUE_TABLE;
 use regex::Regex;
 use rusqlite::params;
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, Result, Row};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 const DIALOGUE_SEEDS_ROOT: &str = "src/db/seeds/dialogue_fixtures/";
 
@@ -72,43 +73,33 @@ impl Dialogue {
         }
     }
 
-    // Save the dialogue to the database and insert responses into the dialogue responses table
-    pub fn create(&self, conn: &Connection) -> u32 {
-        // FIXME: Do this in a transaction (so we can grab the dialogue id reliably?)
-
-        // Insert the Dialogue
+    // Save the dialogue to the database
+    //
+    // The dialogue's id is kept as-is (dialogue is seeded from fixtures, which set their own ids),
+    // so that decisions pointing at a dialogue still point at it after a re-seed.
+    pub fn create(&self, conn: &Connection) -> Result<u32> {
         conn.execute(
-                &format!(
-                    "INSERT INTO {} (character_id, character_type, text, root, root_name, has_input, input_type, next_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                    DIALOGUE_TABLE
-                ),
-                params![
-                    self.character_id,
-                    self.character_type,
-                    self.text,
-                    self.root,
-                    self.root_name,
-                    self.has_input,
-                    self.input_type,
-                    self.next_id,
-                ],
-            ).unwrap();
-
-        // Retrieve the id of the inserted Dialogue
-        // FIXME: This is a little scary
-        let dialogue_id: u32 = conn.last_insert_rowid() as u32;
-
-        // If there are responses, insert them into the dialogue_responses table
-        // if let Some(responses) = &self.responses {
-        //     for dialogue_response in responses {
-        //         let mut dialogue_response = dialogue_response.clone(); // Clone so we can mutate
-        //         dialogue_response.dialogue_id = dialogue_id; // set dialogue id to parent's id
-        //         println!("{:?}", dialogue_response);
-        //         dialogue_response.create(conn).unwrap();
-        //     }
-        // }
-
-        dialogue_id
+            &format!(
+                "INSERT INTO {} (id, character_id, character_type, text, root, root_name, has_input, input_type, next_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
+                DIALOGUE_TABLE
+            ),
+            params![
+                self.id,
+                self.character_id,
+                self.character_type,
+                self.text,
+                self.root,
+                self.root_name,
+                self.has_input,
+                self.input_type,
+                self.next_id,
+                chrono::Local::now().naive_local(),
+            ],
+        )?;
+
+        // Responses are created separately, once every dialogue they might point to exists
+
+        Ok(self.id)
     }
 
     // Method to check if the dialogue entry is an end node
@@ -154,143 +145,69 @@ impl Dialogue {
     }
 }
 
-pub fn load(conn: &Connection, id: u32) -> Result<Option<Dialogue>> {
-    let mut stmt = conn.prepare(
-    &format!("SELECT id, character_id, character_type, root, root_name, text, has_input, input_type, next_id
-                FROM {}
-                WHERE id = {}
-                LIMIT 1;",
-            DIALOGUE_TABLE,
-            id),
-    )?;
-    let mut dialogue_iter = stmt.query_map([], |row| {
-        let id: u32 = row.get(0)?;
-        let character_id: u32 = row.get(1)?;
-        let character_type: CharacterType = row.get(2)?;
-        let root: Option<bool> = row.get(3)?;
-        let root_name: Option<String> = row.get(4)?;
-        let text: String = row.get(5)?;
-        let has_input: Option<bool> = row.get(6)?;
-        let input_type: Option<String> = row.get(7)?;
-        let next_id: Option<u32> = row.get(8)?;
-
-        // Fetch DialogueResponses based on the dialogue_id
-        let mut resp_stmt = conn.prepare(
-            "SELECT id, dialogue_id, text, next_id FROM dialogue_responses WHERE dialogue_id = ?",
-        )?;
-        let responses_iter = resp_stmt.query_map([id], |resp_row| {
-            let resp_id: u32 = resp_row.get(0)?;
-            let dialogue_id: u32 = resp_row.get(1)?;
-            let text: String = resp_row.get(2)?;
-            let next_id: u32 = resp_row.get(3)?;
-            Ok(DialogueResponse {
-                id: resp_id,
-                dialogue_id,
-                text,
-                next_id,
-            })
-        })?;
-
-        // Collect DialogueResponses if any exist, otherwise return None
-        let responses: Option<Vec<DialogueResponse>> = {
-            let responses_vec: Vec<DialogueResponse> =
-                responses_iter.filter_map(Result::ok).collect();
-            if responses_vec.is_empty() {
-                None
-            } else {
-                Some(responses_vec)
-            }
-        };
-
+impl Model for Dialogue {
+    const TABLE: &'static str = DIALOGUE_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "character_id",
+        "character_type",
+        "root",
+        "root_name",
+        "text",
+        "has_input",
+        "input_type",
+        "next_id",
+    ];
+
+    // Responses live in their own table, see `with_responses`
+    fn from_row(row: &Row) -> Result<Self> {
         Ok(Dialogue {
-            id,
-            character_id,
-            character_type,
-            root,
-            root_name,
-            text,
-            responses,
-            has_input,
-            input_type,
-            next_id,
+            id: row.get(0)?,
+            character_id: row.get(1)?,
+            character_type: row.get(2)?,
+            root: row.get(3)?,
+            root_name: row.get(4)?,
+            text: row.get(5)?,
+            responses: None,
+            has_input: row.get(6)?,
+            input_type: row.get(7)?,
+            next_id: row.get(8)?,
         })
-    })?;
-
-    if let Some(dialogue) = dialogue_iter.next() {
-        return Ok(Some(dialogue?));
     }
-
-    Ok(None)
 }
 
-pub fn load_by_root_name(conn: &Connection, root_name: &str) -> Result<Option<Dialogue>> {
-    let mut stmt = conn.prepare(
-    &format!("SELECT id, character_id, character_type, root, root_name, text, has_input, input_type, next_id
-                FROM {}
-                WHERE root_name = '{}'
-                LIMIT 1;",
-            DIALOGUE_TABLE,
-            root_name),
-    )?;
-
-    let mut dialogue_iter = stmt.query_map([], |row| {
-        let id: u32 = row.get(0)?;
-        let character_id: u32 = row.get(1)?;
-        let character_type: CharacterType = row.get(2)?;
-        let root: Option<bool> = row.get(3)?;
-        let root_name: Option<String> = row.get(4)?;
-        let text: String = row.get(5)?;
-        let has_input: Option<bool> = row.get(6)?;
-        let input_type: Option<String> = row.get(7)?;
-        let next_id: Option<u32> = row.get(8)?;
-
-        // Fetch DialogueResponses based on the dialogue_id
-        let mut resp_stmt = conn.prepare(
-            "SELECT id, dialogue_id, text, next_id FROM dialogue_responses WHERE dialogue_id = ?",
-        )?;
-        let responses_iter = resp_stmt.query_map([id], |resp_row| {
-            let resp_id: u32 = resp_row.get(0)?;
-            let dialogue_id: u32 = resp_row.get(1)?;
-            let text: String = resp_row.get(2)?;
-            let next_id: u32 = resp_row.get(3)?;
-            Ok(DialogueResponse {
-                id: resp_id,
-                dialogue_id,
-                text,
-                next_id,
-            })
-        })?;
-
-        // Collect DialogueResponses if any exist, otherwise return None
-        let responses: Option<Vec<DialogueResponse>> = {
-            let responses_vec: Vec<DialogueResponse> =
-                responses_iter.filter_map(Result::ok).collect();
-            if responses_vec.is_empty() {
-                None
-            } else {
-                Some(responses_vec)
-            }
+impl Dialogue {
+    // Fetch the dialogue's responses, if any exist
+    fn with_responses(mut self, conn: &Connection) -> Result<Self> {
+        let responses: Vec<DialogueResponse> =
+            repository::find_all(conn, "WHERE dialogue_id = ?1 ORDER BY id", [self.id])?;
+
+        self.responses = if responses.is_empty() {
+            None
+        } else {
+            Some(responses)
         };
 
-        Ok(Dialogue {
-            id,
-            character_id,
-            character_type,
-            root,
-            root_name,
-            text,
-            responses,
-            has_input,
-            input_type,
-            next_id,
-        })
-    })?;
-
-    if let Some(dialogue) = dialogue_iter.next() {
-        return Ok(Some(dialogue?));
+        Ok(self)
     }
+}
+
+pub fn load(conn: &Connection, id: u32) -> Result<Option<Dialogue>> {
+    let dialogue: Option<Dialogue> = repository::find_by_id(conn, id.into())?;
+    dialogue.map(|d| d.with_responses(conn)).transpose()
+}
 
-    Ok(None)
+pub fn load_by_root_name(conn: &Connection, root_name: &str) -> Result<Option<Dialogue>> {
+    let dialogue: Option<Dialogue> =
+        repository::find_one(conn, "WHERE root_name = ?1", params![root_name])?;
+    dialogue.map(|d| d.with_responses(conn)).transpose()
+}
+
+/// Combines the fixtures root path with a fixture's name, e.g. "character_creation"
+pub fn fixture_path<P: AsRef<Path>>(filename: P) -> PathBuf {
+    Path::new(DIALOGUE_SEEDS_ROOT)
+        .join(filename)
+        .with_extension("yaml")
 }
 
 /// Function to load a YAML dialogue file into a <Vec<Dialogue>
@@ -298,12 +215,7 @@ pub fn load_by_root_name(conn: &Connection, root_name: &str) -> Result<Option<Di
 pub fn load_from_fixture<P: AsRef<Path>>(
     filename: P,
 ) -> Result<Vec<Dialogue>, Box<dyn std::error::Error>> {
-    // Combine the root path with the filename
-    let full_path = Path::new(DIALOGUE_SEEDS_ROOT)
-        .join(filename)
-        .with_extension("yaml");
-
-    let yaml_str = fs::read_to_string(full_path)?;
+    let yaml_str = fs::read_to_string(fixture_path(filename))?;
     let dialogues: Vec<Dialogue> = serde_yaml::from_str(&yaml_str)?;
     Ok(dialogues)
 }
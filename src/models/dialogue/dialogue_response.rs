@@ -1,6 +1,7 @@
+use crate::db::repository::Model;
 use crate::db::DIALOGUE_RESPONSE_TABLE;
 use rusqlite::params;
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, Result, Row};
 use serde::{Deserialize, Serialize};
 
 // Represents a possible response to a dialogue, where the player can choose one option
@@ -25,13 +26,23 @@ impl DialogueResponse {
     }
 
     // Create method for DialogueResponse
+    //
+    // Responses from fixtures keep their ids, while new responses (id 0) get one from the database
     pub fn create(&self, conn: &Connection) -> Result<u32, rusqlite::Error> {
+        let id = if self.id == 0 { None } else { Some(self.id) };
+
         conn.execute(
             &format!(
-                "INSERT INTO {} (dialogue_id, text, next_id) VALUES (?1, ?2, ?3)",
+                "INSERT INTO {} (id, dialogue_id, text, next_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
                 DIALOGUE_RESPONSE_TABLE
             ),
-            params![self.dialogue_id, self.text, self.next_id],
+            params![
+                id,
+                self.dialogue_id,
+                self.text,
+                self.next_id,
+                chrono::Local::now().naive_local()
+            ],
         )?;
 
         // Return the id of the new response (usually returned by the database after insertion)
@@ -39,3 +50,17 @@ impl DialogueResponse {
         Ok(last_inserted_id)
     }
 }
+
+impl Model for DialogueResponse {
+    const TABLE: &'static str = DIALOGUE_RESPONSE_TABLE;
+    const COLUMNS: &'static [&'static str] = &["id", "dialogue_id", "text", "next_id"];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(DialogueResponse {
+            id: row.get(0)?,
+            dialogue_id: row.get(1)?,
+            text: row.get(2)?,
+            next_id: row.get(3)?,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+//! The tiles of a map a player has explored, for drawing the fog of war. Stored as a hex
+//! bitset (see `world::fog::Explored`).
+use crate::db::repository::{self, Model};
+use crate::db::EXPLORED_MAP_TABLE;
+use crate::world::fog::Explored;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExploredMap {
+    pub player_id: i32,
+    pub map_name: String,
+    pub width: usize,
+    pub height: usize,
+    pub tiles: String,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Model for ExploredMap {
+    const TABLE: &'static str = EXPLORED_MAP_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "player_id",
+        "map_name",
+        "width",
+        "height",
+        "tiles",
+        "updated_at",
+    ];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(ExploredMap {
+            player_id: row.get(0)?,
+            map_name: row.get(1)?,
+            width: row.get(2)?,
+            height: row.get(3)?,
+            tiles: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+}
+
+impl ExploredMap {
+    pub fn new(player_id: i32, map_name: &str, explored: &Explored) -> Self {
+        let (width, height) = explored.size();
+        ExploredMap {
+            player_id,
+            map_name: map_name.to_string(),
+            width,
+            height,
+            tiles: explored.to_hex(),
+            updated_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    /// Saves the record, replacing whatever was explored of the map before
+    pub fn save(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            &format!(
+                "INSERT INTO {} (player_id, map_name, width, height, tiles, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (player_id, map_name) DO UPDATE SET width = ?3, height = ?4, tiles = ?5, updated_at = ?6",
+                EXPLORED_MAP_TABLE
+            ),
+            params![self.player_id, self.map_name, self.width, self.height, self.tiles, self.updated_at],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn load(conn: &Connection, player_id: i32, map_name: &str) -> Result<Option<ExploredMap>> {
+        repository::find_one(
+            conn,
+            "WHERE player_id = ?1 AND map_name = ?2",
+            params![player_id, map_name],
+        )
+    }
+
+    pub fn load_for_player(conn: &Connection, player_id: i32) -> Result<Vec<ExploredMap>> {
+        repository::find_all(conn, "WHERE player_id = ?1 ORDER BY map_name", [player_id])
+    }
+
+    /// The explored tiles. `None` if the record is corrupt.
+    pub fn to_explored(&self) -> Option<Explored> {
+        Explored::from_hex(self.width, self.height, &self.tiles)
+    }
+}
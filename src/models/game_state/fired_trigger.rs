@@ -0,0 +1,76 @@
+//! A record of a map trigger that has fired for a player. Triggers that only fire once
+//! check for one of these before firing again.
+use crate::db::repository::{self, Model};
+use crate::db::FIRED_TRIGGER_TABLE;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiredTrigger {
+    pub player_id: i32,
+    pub map_name: String,
+    pub trigger_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Model for FiredTrigger {
+    const TABLE: &'static str = FIRED_TRIGGER_TABLE;
+    const COLUMNS: &'static [&'static str] = &["player_id", "map_name", "trigger_id", "created_at"];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(FiredTrigger {
+            player_id: row.get(0)?,
+            map_name: row.get(1)?,
+            trigger_id: row.get(2)?,
+            created_at: row.get(3)?,
+        })
+    }
+}
+
+impl FiredTrigger {
+    pub fn new(player_id: i32, map_name: &str, trigger_id: &str) -> Self {
+        FiredTrigger {
+            player_id,
+            map_name: map_name.to_string(),
+            trigger_id: trigger_id.to_string(),
+            created_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    /// Records the trigger as fired. Recording it again is a no-op.
+    pub fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO {} (player_id, map_name, trigger_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+                FIRED_TRIGGER_TABLE
+            ),
+            params![self.player_id, self.map_name, self.trigger_id, self.created_at],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn has_fired(
+        conn: &Connection,
+        player_id: i32,
+        map_name: &str,
+        trigger_id: &str,
+    ) -> Result<bool> {
+        let fired: Option<FiredTrigger> = repository::find_one(
+            conn,
+            "WHERE player_id = ?1 AND map_name = ?2 AND trigger_id = ?3",
+            params![player_id, map_name, trigger_id],
+        )?;
+
+        Ok(fired.is_some())
+    }
+
+    pub fn load_for_player(conn: &Connection, player_id: i32) -> Result<Vec<FiredTrigger>> {
+        repository::find_all(
+            conn,
+            "WHERE player_id = ?1 ORDER BY map_name, trigger_id",
+            [player_id],
+        )
+    }
+}
@@ -1,15 +1,31 @@
+use crate::db::repository::{self, Model};
 use crate::db::GAME_STATE_TABLE;
 use crate::game_engine::interface_mode::InterfaceMode;
+use crate::world::atlas::STARTING_MAP;
 use crate::world::navigation::Direction;
 use chrono::NaiveDateTime;
 use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
 use rusqlite::ToSql;
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, Error, Result, Row};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "u64", from = "u64")] // Serialized as seconds, like in the database
 pub struct SqlDuration(pub Duration); // Wrapper type (so we can implement ToSql and FromSql)
 
+impl From<SqlDuration> for u64 {
+    fn from(duration: SqlDuration) -> Self {
+        duration.as_secs()
+    }
+}
+
+impl From<u64> for SqlDuration {
+    fn from(secs: u64) -> Self {
+        SqlDuration::from_secs(secs)
+    }
+}
+
 impl ToSql for SqlDuration {
     fn to_sql(&self) -> Result<ToSqlOutput> {
         Ok(ToSqlOutput::from(self.as_secs() as i64)) // Store as integer (seconds)
@@ -33,21 +49,28 @@ impl SqlDuration {
     }
 }
 
+fn starting_map() -> String {
+    STARTING_MAP.to_string()
+}
+
 // FIXME: There's an argument that some of the things being stored shouldn't be
 //        persisted in the database. Or, player x, y, and direction should be updated in memory,
 //        and only on some event or schedule dumped into the DB.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameState {
     pub player_id: i32,                // Foreign key to the player
     pub interface_mode: InterfaceMode, // Track the state of UI / interface, its mode (Dialogue, WorldNavigation, Battle)
     pub current_epic: String,          // Represents a larger story arc of the game
     pub current_stage: String,         // Represents the current stage of the epic
-    pub x: usize,                      // Player's X coordinate
-    pub y: usize,                      // Player's Y coordinate
-    pub direction: Direction,          // Track last movement
-    pub total_play_time: SqlDuration,  // Total playtime // TODO: Implement Duration to_sql
-    pub created_at: NaiveDateTime,     // Timestamp when the game state was created
-    pub updated_at: NaiveDateTime,     // Timestamp when the game state was last updated
+    // Saves exported before maps had names were all on the first map
+    #[serde(default = "starting_map")]
+    pub current_map: String, // Name of the map the player is on
+    pub x: usize,                     // Player's X coordinate
+    pub y: usize,                     // Player's Y coordinate
+    pub direction: Direction,         // Track last movement
+    pub total_play_time: SqlDuration, // Total playtime // TODO: Implement Duration to_sql
+    pub created_at: NaiveDateTime,    // Timestamp when the game state was created
+    pub updated_at: NaiveDateTime,    // Timestamp when the game state was last updated
 }
 
 impl GameState {
@@ -56,6 +79,7 @@ impl GameState {
             interface_mode: InterfaceMode::Dialogue,
             current_epic: "intro".to_string(),
             current_stage: "character_creation".to_string(),
+            current_map: STARTING_MAP.to_string(),
             player_id,
             x: 0,
             y: 0,
@@ -74,69 +98,80 @@ impl GameState {
         )
     }
 
-    pub fn create(&self, conn: &Connection) -> GameState {
+    pub fn create(&self, conn: &Connection) -> Result<GameState> {
         conn.execute(
             &format!(
-                "INSERT INTO {} (interface_mode, current_epic, current_stage, player_id, x, y, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                "INSERT INTO {} (interface_mode, current_epic, current_stage, current_map, player_id, x, y, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
                 GAME_STATE_TABLE
             ),
-            rusqlite::params![self.interface_mode, self.current_epic, self.current_stage, self.player_id, self.x, self.y, self.created_at, self.created_at],
-        ).unwrap();
+            rusqlite::params![self.interface_mode, self.current_epic, self.current_stage, self.current_map, self.player_id, self.x, self.y, self.created_at, self.created_at],
+        )?;
 
-        return GameState::load_for_player(&conn, self.player_id)
-            .unwrap()
-            .unwrap();
+        GameState::load_for_player(conn, self.player_id)?.ok_or(Error::QueryReturnedNoRows)
     }
 
-    pub fn update(&self, conn: &Connection) -> GameState {
-        conn.execute(
+    pub fn update(&self, conn: &Connection) -> Result<GameState> {
+        let rows_updated = conn.execute(
             &format!(
                 "UPDATE {}
-                    SET current_epic = ?1, current_stage = ?2, x = ?3, y = ?4, updated_at = ?5
-                    WHERE player_id = ?6",
+                    SET current_epic = ?1, current_stage = ?2, current_map = ?3, x = ?4, y = ?5, total_play_time = ?6, updated_at = ?7
+                    WHERE player_id = ?8",
                 GAME_STATE_TABLE
             ),
             rusqlite::params![
                 self.current_epic,
                 self.current_stage,
+                self.current_map,
                 self.x,
                 self.y,
+                self.total_play_time,
                 chrono::Local::now().naive_local(),
                 self.player_id
             ],
-        )
-        .unwrap();
+        )?;
+
+        if rows_updated == 0 {
+            // No game state for this player
+            return Err(Error::QueryReturnedNoRows);
+        }
 
-        return GameState::load_for_player(&conn, self.player_id)
-            .unwrap()
-            .unwrap();
+        GameState::load_for_player(conn, self.player_id)?.ok_or(Error::QueryReturnedNoRows)
     }
 
     pub fn load_for_player(conn: &Connection, player_id: i32) -> Result<Option<Self>> {
-        let mut stmt = conn.prepare(&format!(
-            "SELECT interface_mode, current_epic, current_stage, x, y, direction, total_play_time, created_at, updated_at FROM {} WHERE player_id = ?1",
-            GAME_STATE_TABLE
-        ))?;
-
-        let mut game_state_iter = stmt.query_map([player_id], |row| {
-            Ok(GameState {
-                interface_mode: row.get(0)?,
-                current_epic: row.get(1)?,
-                current_stage: row.get(2)?,
-                player_id,
-                x: row.get(3)?,
-                y: row.get(4)?,
-                direction: row.get(5)?,
-                total_play_time: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        })?;
-
-        if let Some(game_state) = game_state_iter.next() {
-            return Ok(Some(game_state?));
-        }
+        repository::find_one(conn, "WHERE player_id = ?1", [player_id])
+    }
+}
 
-        Ok(None)
+impl Model for GameState {
+    const TABLE: &'static str = GAME_STATE_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "player_id",
+        "interface_mode",
+        "current_epic",
+        "current_stage",
+        "current_map",
+        "x",
+        "y",
+        "direction",
+        "total_play_time",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(GameState {
+            player_id: row.get(0)?,
+            interface_mode: row.get(1)?,
+            current_epic: row.get(2)?,
+            current_stage: row.get(3)?,
+            current_map: row.get(4)?,
+            x: row.get(5)?,
+            y: row.get(6)?,
+            direction: row.get(7)?,
+            total_play_time: row.get(8)?,
+            created_at: row.get(9)?,
+            updated_at: row.get(10)?,
+        })
     }
 }
@@ -1,13 +1,14 @@
 use rusqlite::types::FromSql;
 use rusqlite::types::{FromSqlResult, ValueRef};
 use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
 
 /// MainArc
 /// - At some point in the story, the player will make a decision
 ///   that will entangle them with one of the 4 protagonists of TBG
 /// - Once the MainArc value is set, it will never change for the rest of that
 ///   player's save file
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MainArc {
     Undetermined,
     ThomasMoss,
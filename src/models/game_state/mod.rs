@@ -1,4 +1,7 @@
 // pub mod epic;
+pub mod explored_map;
+pub mod fired_trigger;
 pub mod game_state;
 pub mod main_arc;
+pub mod opened_door;
 // pub mod stage;
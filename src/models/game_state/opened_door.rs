@@ -0,0 +1,95 @@
+//! A door the player has left open. Doors are shut again when a map is loaded, unless one of
+//! these says otherwise.
+use crate::db::repository::{self, Model};
+use crate::db::OPENED_DOOR_TABLE;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenedDoor {
+    pub player_id: i32,
+    pub map_name: String,
+    pub x: usize,
+    pub y: usize,
+    pub created_at: NaiveDateTime,
+}
+
+impl Model for OpenedDoor {
+    const TABLE: &'static str = OPENED_DOOR_TABLE;
+    const COLUMNS: &'static [&'static str] = &["player_id", "map_name", "x", "y", "created_at"];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(OpenedDoor {
+            player_id: row.get(0)?,
+            map_name: row.get(1)?,
+            x: row.get(2)?,
+            y: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
+}
+
+impl OpenedDoor {
+    pub fn new(player_id: i32, map_name: &str, x: usize, y: usize) -> Self {
+        OpenedDoor {
+            player_id,
+            map_name: map_name.to_string(),
+            x,
+            y,
+            created_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    /// Records the door as open. Recording it again is a no-op.
+    pub fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO {} (player_id, map_name, x, y, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                OPENED_DOOR_TABLE
+            ),
+            params![self.player_id, self.map_name, self.x, self.y, self.created_at],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records the door as shut again
+    pub fn delete(
+        conn: &Connection,
+        player_id: i32,
+        map_name: &str,
+        x: usize,
+        y: usize,
+    ) -> Result<()> {
+        conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE player_id = ?1 AND map_name = ?2 AND x = ?3 AND y = ?4",
+                OPENED_DOOR_TABLE
+            ),
+            params![player_id, map_name, x, y],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn load_for_map(
+        conn: &Connection,
+        player_id: i32,
+        map_name: &str,
+    ) -> Result<Vec<OpenedDoor>> {
+        repository::find_all(
+            conn,
+            "WHERE player_id = ?1 AND map_name = ?2 ORDER BY y, x",
+            params![player_id, map_name],
+        )
+    }
+
+    pub fn load_for_player(conn: &Connection, player_id: i32) -> Result<Vec<OpenedDoor>> {
+        repository::find_all(
+            conn,
+            "WHERE player_id = ?1 ORDER BY map_name, y, x",
+            [player_id],
+        )
+    }
+}
@@ -1,8 +1,9 @@
 /// Non-Player Character
+use crate::db::repository::{self, Model};
 use crate::db::NPC_TABLE;
 use crate::models::player::gender::Gender;
 use chrono::NaiveDateTime;
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, Result, Row};
 
 // FIXME: Use getters, not pub values
 #[derive(Debug, Clone, PartialEq)]
@@ -14,6 +15,21 @@ pub struct NPC {
     pub updated_at: NaiveDateTime,
 }
 
+impl Model for NPC {
+    const TABLE: &'static str = NPC_TABLE;
+    const COLUMNS: &'static [&'static str] = &["id", "name", "gender", "created_at", "updated_at"];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(NPC {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            gender: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+}
+
 impl NPC {
     // Create a new NPC instance
     pub fn new(name: String, gender: Gender) -> Self {
@@ -27,39 +43,23 @@ impl NPC {
     }
 
     pub fn load_by_id(conn: &Connection, id: i32) -> Result<Option<Self>> {
-        let mut stmt = conn.prepare(
-            &format!("SELECT id, name, gender, created_at, updated_at FROM {} WHERE id = {} ORDER BY updated_at DESC LIMIT 1", NPC_TABLE, id),
-        )?;
-        let mut npc_iter = stmt.query_map([], |row| {
-            let id: i32 = row.get(0)?;
-            let name: String = row.get(1)?;
-            let gender: Gender = row.get(2)?;
-            let created_at: NaiveDateTime = row.get(3)?;
-            let updated_at: NaiveDateTime = row.get(4)?;
-            Ok(NPC {
-                id,
-                name,
-                gender,
-                created_at,
-                updated_at,
-            })
-        })?;
-
-        if let Some(npc) = npc_iter.next() {
-            return Ok(Some(npc?));
-        }
-
-        Ok(None)
+        repository::find_by_id(conn, id.into())
     }
 
     // Save the NPC to the database
+    //
+    // Seeded NPCs keep their ids (dialogue fixtures refer to them), while new NPCs (id 0)
+    // get one from the database
     pub fn create(&self, conn: &Connection) -> Result<()> {
+        let id = if self.id == 0 { None } else { Some(self.id) };
+
         conn.execute(
             &format!(
-                "INSERT INTO {} (name, gender, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                "INSERT INTO {} (id, name, gender, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
                 NPC_TABLE
             ),
             rusqlite::params![
+                id,
                 self.name,
                 self.gender,
                 self.created_at,
@@ -1,10 +1,11 @@
 use rusqlite::types::FromSql;
 use rusqlite::types::{FromSqlResult, ValueRef};
 use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
 
 /// Background
 /// - Character creation ends with the user choosing their background
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Background {
     Undetermined,
     Farmer,
@@ -1,10 +1,11 @@
 use rusqlite::types::FromSql;
 use rusqlite::types::{FromSqlResult, ValueRef};
 use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Represents the gender of a player in the game.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Gender {
     Male,
     Female,
@@ -0,0 +1,71 @@
+//! Something the player is carrying, and how many of it. Items are picked up from chests and
+//! bookshelves (see `world::interaction`), and some of them unlock doors.
+//!
+//! Pages aren't items: pages found in the world go into the player's collection (see
+//! `models::book::page`).
+use crate::db::repository::{self, Model};
+use crate::db::INVENTORY_ITEM_TABLE;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InventoryItem {
+    pub player_id: i32,
+    pub item: String,
+    pub count: u32,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Model for InventoryItem {
+    const TABLE: &'static str = INVENTORY_ITEM_TABLE;
+    const COLUMNS: &'static [&'static str] = &["player_id", "item", "count", "updated_at"];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(InventoryItem {
+            player_id: row.get(0)?,
+            item: row.get(1)?,
+            count: row.get(2)?,
+            updated_at: row.get(3)?,
+        })
+    }
+}
+
+impl InventoryItem {
+    pub fn new(player_id: i32, item: &str, count: u32) -> Self {
+        InventoryItem {
+            player_id,
+            item: item.to_string(),
+            count,
+            updated_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    /// Adds the item to the player's inventory, on top of any they already have
+    pub fn add(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            &format!(
+                "INSERT INTO {} (player_id, item, count, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (player_id, item) DO UPDATE SET count = count + ?3, updated_at = ?4",
+                INVENTORY_ITEM_TABLE
+            ),
+            params![self.player_id, self.item, self.count, self.updated_at],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn has(conn: &Connection, player_id: i32, item: &str) -> Result<bool> {
+        let found: Option<InventoryItem> = repository::find_one(
+            conn,
+            "WHERE player_id = ?1 AND item = ?2 AND count > 0",
+            params![player_id, item],
+        )?;
+
+        Ok(found.is_some())
+    }
+
+    pub fn load_for_player(conn: &Connection, player_id: i32) -> Result<Vec<InventoryItem>> {
+        repository::find_all(conn, "WHERE player_id = ?1 ORDER BY item", [player_id])
+    }
+}
@@ -1,4 +1,5 @@
 pub mod background;
 pub mod gender;
 pub mod height;
+pub mod inventory_item;
 pub mod player;
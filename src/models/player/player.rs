@@ -1,3 +1,4 @@
+use crate::db::repository::{self, Model};
 use crate::db::PLAYER_TABLE;
 use crate::models::game_state::main_arc::MainArc;
 use crate::models::player::background::Background;
@@ -5,11 +6,12 @@ use crate::models::player::gender::Gender;
 use crate::models::player::height::Height;
 use chrono::NaiveDateTime;
 use rusqlite::Error as RusqliteError;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 // FIXME: Use getters, not pub values
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Player {
     pub id: i32,
     pub name: String,
@@ -21,6 +23,33 @@ pub struct Player {
     pub updated_at: NaiveDateTime,
 }
 
+impl Model for Player {
+    const TABLE: &'static str = PLAYER_TABLE;
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "gender",
+        "height",
+        "background",
+        "main_arc",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(Player {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            gender: row.get(2)?,
+            height: row.get(3)?,
+            background: row.get(4)?,
+            main_arc: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+        })
+    }
+}
+
 impl Player {
     // Create a new player instance
     pub fn new(name: String, gender: Gender, height: Height) -> Self {
@@ -42,39 +71,15 @@ impl Player {
 
     // Load the most recent player by the updated_at field
     pub fn load_most_recent(conn: &Connection) -> Result<Option<Self>> {
-        let mut stmt = conn.prepare(
-            &format!("SELECT id, name, gender, height, background, main_arc, created_at, updated_at FROM {} ORDER BY updated_at DESC LIMIT 1", PLAYER_TABLE),
-        )?;
-        let mut player_iter = stmt.query_map([], |row| {
-            let id: i32 = row.get(0)?;
-            let name: String = row.get(1)?;
-            let gender: Gender = row.get(2)?;
-            let height: Height = row.get(3)?;
-            let background: Background = row.get(4)?;
-            let main_arc: MainArc = row.get(5)?;
-            let created_at: NaiveDateTime = row.get(6)?;
-            let updated_at: NaiveDateTime = row.get(7)?;
-            Ok(Player {
-                id,
-                name,
-                gender,
-                height,
-                background,
-                main_arc,
-                created_at,
-                updated_at,
-            })
-        })?;
-
-        if let Some(player) = player_iter.next() {
-            return Ok(Some(player?));
-        }
+        repository::find_one(conn, "ORDER BY updated_at DESC", [])
+    }
 
-        Ok(None)
+    pub fn load_by_id(conn: &Connection, id: i32) -> Result<Option<Self>> {
+        repository::find_by_id(conn, id.into())
     }
 
     // Save the player to the database and return
-    pub fn create(&self, conn: &Connection) -> Player {
+    pub fn create(&self, conn: &Connection) -> Result<Player> {
         conn.execute(
             &format!("INSERT INTO {} (name, gender, height, background, main_arc, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", PLAYER_TABLE),
             rusqlite::params![
@@ -86,9 +91,10 @@ impl Player {
                 self.created_at,
                 self.created_at // Default updated at to created at value
             ],
-        ).unwrap();
+        )?;
 
-        return Player::load(conn).unwrap().unwrap();
+        let id = conn.last_insert_rowid() as i32;
+        Player::load_by_id(conn, id)?.ok_or(RusqliteError::QueryReturnedNoRows)
     }
 
     pub fn update(&self, conn: &Connection) -> Result<()> {
@@ -108,7 +114,18 @@ impl Player {
         Ok(())
     }
 
-    // TODO: Add height, background, and use this.
+    /// This player's seed, for anything random in the world that should play out the same
+    /// way every time for them (e.g. how NPCs wander)
+    pub fn seed(&self) -> u64 {
+        Player::generate_seed(
+            &self.name,
+            &self.gender.to_string(),
+            self.height.clone() as u8,
+            &format!("{:?}", self.background),
+            self.created_at.and_utc().timestamp() as u64,
+        )
+    }
+
     pub fn generate_seed(
         player_name: &str,
         gender: &str,
@@ -138,5 +155,15 @@ mod tests {
         assert_eq!(player.gender, Gender::Male);
     }
 
+    #[test]
+    fn test_seed() {
+        let player = Player::new("Test Player".to_string(), Gender::Male, Height::Average);
+        assert_eq!(player.seed(), player.clone().seed());
+
+        let mut other = player.clone();
+        other.name = "Other Player".to_string();
+        assert_ne!(player.seed(), other.seed());
+    }
+
     // TODO: Add tests! For everything!
 }
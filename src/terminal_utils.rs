@@ -1,7 +1,7 @@
 use crate::tui;
 use crossterm::{
     cursor::{self, Hide, Show},
-    event::{self, Event, KeyCode},
+    event::{Event, KeyCode},
     execute,
     style::{Color as TermionColor, ResetColor, SetForegroundColor},
     terminal::{self, ClearType},
@@ -22,10 +22,10 @@ use std::{
 /// TBGColors, not to be confused with termion::Color (aliased to TermionColor),
 /// are a subset of ANSI colors that are used to style the TUI
 // TODO: store these in settings!
-struct TBGColors;
+pub(crate) struct TBGColors;
 impl TBGColors {
-    const ACTION_COLOR: TermionColor = TermionColor::DarkCyan;
-    const TEXT_COLOR: TermionColor = TermionColor::DarkYellow;
+    pub(crate) const ACTION_COLOR: TermionColor = TermionColor::DarkCyan;
+    pub(crate) const TEXT_COLOR: TermionColor = TermionColor::DarkYellow;
 
     // A single method to get the ANSI escape code for any color
     fn fg_string(color: TermionColor) -> String {
@@ -105,18 +105,20 @@ pub fn prompt_enter_to_continue() {
         .expect("Failed to print prompt to continue");
     stdout.flush().unwrap();
 
-    // Block and wait for Enter key press
+    wait_for_enter();
+    clear_console(None);
+}
+
+/// Blocks until the player presses enter
+fn wait_for_enter() {
     loop {
-        if let Ok(true) = event::poll(Duration::from_millis(100)) {
-            if let Ok(Event::Key(key_event)) = event::read() {
-                if key_event.code == KeyCode::Enter {
-                    break; // Exit loop when Enter is pressed
-                }
+        if let Some(Event::Key(key_event)) = tui::size_guard::read_event(Duration::from_millis(100))
+        {
+            if key_event.code == KeyCode::Enter {
+                break; // Exit loop when Enter is pressed
             }
         }
     }
-
-    clear_console(None);
 }
 
 /// Simple utility method to print dialogue/narration using the text styling
@@ -147,34 +149,73 @@ pub fn action_required(message: &str) -> String {
 /// Prints a message (usually dialogue or narration) to the user,
 /// one char at a time. Helps to add some style/life to the game,
 /// and encourages them to actually read the storyline.
+///
+/// Messages too long for the window are typed a page at a time, and the
+/// player presses enter to go on to the next page.
 pub fn simulate_typing(message: &str) {
     let mut stdout = io::stdout();
 
     // TODO: Move typing speed to settings!
     // (The smaller the number, the faster the typing)
     let typing_speed = 10;
-    let mut displayed_message = String::new();
+    let mut backend = tui::backend::TerminalBackend::stdout();
+
+    // Apply TEXT_COLOR to the whole message, and wrap it into pages up front,
+    // so words don't jump between lines as they're typed
+    let colored_message = format!(
+        "{}{}{}",
+        TBGColors::fg_string(TBGColors::TEXT_COLOR), // Apply the color
+        message,
+        TBGColors::fg_str_reset() // Reset the color after message
+    );
+    let window = tui::window::Window::new(&colored_message);
+    let (width, height) =
+        tui::window::Window::frame_size(&backend).expect("Failed to get terminal size");
+    let pages = window.pages(width, height);
 
     // Hide the cursor before typing starts
     execute!(stdout, Hide).expect("Failed to hide cursor");
 
-    for c in message.chars() {
-        displayed_message.push(c);
+    for (i, page) in pages.iter().enumerate() {
+        if i > 0 {
+            write!(
+                stdout,
+                "{}",
+                action_required("\n\rPress enter for more... ")
+            )
+            .expect("Failed to print prompt for more");
+            stdout.flush().unwrap();
+            wait_for_enter();
+        }
 
-        // Apply TEXT_COLOR to the message as it is typed
-        let colored_message = format!(
-            "{}{}{}",
-            TBGColors::fg_string(TBGColors::TEXT_COLOR), // Apply the color
-            displayed_message,
-            TBGColors::fg_str_reset() // Reset the color after message
-        );
+        let page = page.join("\n");
+        let mut typed = 0;
+        while typed < page.len() {
+            // Color codes are "typed" along with the char after them
+            let rest = &page[typed..];
+            if let Some((code, _)) = tui::frame::escape_code(rest) {
+                typed += code.len();
+                continue;
+            }
+            typed += rest.chars().next().map_or(1, char::len_utf8);
 
-        // Draw the window with the colored message
-        tui::window::Window::new(&colored_message)
-            .render()
-            .expect("Render window failed");
+            // Pauses the typing if the terminal's been made too small
+            tui::size_guard::ensure_size().ok();
 
-        thread::sleep(Duration::from_millis(typing_speed));
+            // The page is already wrapped
+            tui::window::Window::new(&page[..typed])
+                .without_wrapping()
+                .render_to(&mut backend)
+                .expect("Render window failed");
+
+            thread::sleep(Duration::from_millis(typing_speed));
+        }
+
+        // The whole page, with which page it is on the border
+        window
+            .with_page(i)
+            .render_to(&mut backend)
+            .expect("Render window failed");
     }
 
     // Show the cursor again after typing is done
@@ -203,7 +244,9 @@ pub fn title_screen() {
     let formatted_message = draw_title_with_gradient(message);
 
     // Now pass that formatted message to draw_window
+    // It's ASCII art, so it's cut off rather than wrapped in narrow terminals
     tui::window::Window::new(&formatted_message)
+        .without_wrapping()
         .render()
         .expect("Render window failed");
 }
@@ -239,109 +282,6 @@ pub fn reset_cursor(stdout: &mut dyn Write) {
     write!(stdout, "{}", cursor::MoveTo(0, 0)).unwrap();
 }
 
-/// Parameters:
-/// - message (usually a prompt, providing details on the choice the user is going to make)
-/// - options (which will be printed out below the message)
-/// - selected_index (the caller is responsible for setting this)
-/// - use_simulate_typing (usually true during first render, then false in subsequent renders)
-///
-/// Output:
-/// - Should be a &str, which can then be rendered in some rendering loop
-///
-/// Usage:
-/// - The caller (the Menu struct), will loop, and print the menu until an option is selected
-/// - The print_menu fn is "dumb", in that it just displays the content--the caller
-///   is responsible for handling input and updating the display with the selected_index, etc.
-///
-// TODO: When the menu struct is implemented, move this to its impl block
-// FIXME: This method should not be calling draw_window!
-//        - This method should return a string slice, and that should
-//          be passed to the rendering loop, which can call draw_window!
-pub fn print_menu<T: std::fmt::Display>(
-    message: &str,
-    options: &Vec<T>,
-    selected_index: usize,
-    use_simulate_typing: bool,
-) -> io::Result<()> {
-    let mut content = String::new();
-
-    // Pre-fill the content with spaces to prevent jumping
-    content.push_str(&format!("{}\n", message));
-    for _ in options.iter() {
-        content.push_str("\n");
-    }
-
-    // Draw initial empty window before typing starts
-    // FIXME: Is this really the right place to call draw_window?
-    tui::window::Window::new(&content).render()?;
-
-    if use_simulate_typing {
-        let mut typed_message = String::new();
-        for c in message.chars() {
-            typed_message.push(c);
-
-            // Apply the text color using fg_string
-            let colored_message = format!(
-                "{}{}{}", // Text color + typed message + reset color
-                TBGColors::fg_string(TBGColors::TEXT_COLOR),
-                typed_message,
-                TBGColors::fg_str_reset()
-            );
-
-            // Ensure the rest of the content stays intact, just adding the typed message
-            // FIXME: Is this really the right place to call draw_window?
-            tui::window::Window::new(&format!(
-                "{}\n{}", // typed message + remaining content
-                colored_message,
-                content.split_once('\n').unwrap().1
-            ))
-            .render()?;
-
-            thread::sleep(Duration::from_millis(25));
-        }
-    } else {
-        tui::window::Window::new(&content).render()?;
-    }
-
-    // Replace placeholder spaces with actual menu options
-    let mut final_content = String::new();
-
-    // Apply color to the message and reset it
-    let colored_message = format!(
-        "{}{}{}",
-        TBGColors::fg_string(TBGColors::TEXT_COLOR),
-        message,
-        TBGColors::fg_str_reset()
-    );
-    final_content.push_str(&format!("{}\n", colored_message));
-
-    // Build the final content with colored message and options
-    for (i, option) in options.iter().enumerate() {
-        let colored_option = if i == selected_index {
-            format!(
-                "{}> {}{}",
-                TBGColors::fg_string(TBGColors::ACTION_COLOR),
-                option,
-                TBGColors::fg_str_reset()
-            )
-        } else {
-            format!(
-                "  {}{}{}", // Apply ACTION_COLOR + option + reset color
-                TBGColors::fg_string(TBGColors::ACTION_COLOR),
-                option,
-                TBGColors::fg_str_reset()
-            )
-        };
-
-        final_content.push_str(&format!("{}\n", colored_option));
-    }
-
-    // Draw final stable window with full content
-    tui::window::Window::new(&final_content).render()?;
-
-    Ok(())
-}
-
 // FIXME: These should be in terminal_util_tests probably.
 #[cfg(test)]
 mod tests {
@@ -358,7 +298,6 @@ mod tests {
     // - prompt_enter_to_continue
     // - p
     // - action_required
-    // - print_menu
     // - draw_window
     // - reset_cursor
     // - title_screen
@@ -0,0 +1,245 @@
+//! Where frames end up. Components draw into a `Frame`, and a backend puts it on a screen:
+//! the terminal, through crossterm, or an in-memory screen that tests can look at.
+use crate::tui::frame::Frame;
+use crossterm::cursor::{Hide, MoveTo};
+use crossterm::queue;
+use crossterm::terminal::{self, Clear, ClearType};
+use std::io::{self, Stdout, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The last frame drawn to the terminal (by a `TerminalBackend::stdout`), so it can be put
+/// back after something else has covered it
+static SCREEN: Mutex<Option<Frame>> = Mutex::new(None);
+/// Goes up whenever the whole terminal has been drawn over
+static TERMINAL_REDRAWS: AtomicUsize = AtomicUsize::new(0);
+
+/// What was last drawn to the terminal
+pub fn last_screen() -> Option<Frame> {
+    SCREEN.lock().unwrap().clone()
+}
+
+/// Makes every terminal backend redraw everything the next time it draws, e.g. after the
+/// size guard has covered the screen
+pub fn invalidate_terminal() {
+    TERMINAL_REDRAWS.fetch_add(1, Ordering::SeqCst);
+}
+
+pub trait Backend {
+    /// The size of the screen, as (width, height) in cells
+    fn size(&self) -> io::Result<(usize, usize)>;
+
+    /// Shows a frame, with its top left corner in the screen's top left corner
+    fn draw(&mut self, frame: &Frame) -> io::Result<()>;
+
+    /// Forgets what's on the screen, so the next draw redraws everything, e.g. after
+    /// something else wrote to it
+    fn invalidate(&mut self);
+}
+
+/// Draws to the terminal, only writing what changed since the last frame
+pub struct TerminalBackend<W: Write = Stdout> {
+    out: W,
+    previous: Option<Frame>, // What's on the screen
+    redraws: usize,          // `TERMINAL_REDRAWS`, when `previous` was drawn
+    hide_cursor: bool,
+    is_screen: bool, // Whether this draws to the terminal itself, which `SCREEN` remembers
+}
+
+impl TerminalBackend {
+    pub fn stdout() -> Self {
+        TerminalBackend {
+            is_screen: true,
+            ..TerminalBackend::new(io::stdout())
+        }
+    }
+}
+
+impl<W: Write> TerminalBackend<W> {
+    pub fn new(out: W) -> Self {
+        TerminalBackend {
+            out,
+            previous: None,
+            redraws: TERMINAL_REDRAWS.load(Ordering::SeqCst),
+            hide_cursor: false,
+            is_screen: false,
+        }
+    }
+
+    /// Hides the cursor whenever the screen is redrawn from scratch, e.g. for the map, where
+    /// it would just be in the way
+    pub fn with_hidden_cursor(mut self) -> Self {
+        self.hide_cursor = true;
+        self
+    }
+}
+
+impl<W: Write> Backend for TerminalBackend<W> {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        let (width, height) = terminal::size()?;
+        Ok((width as usize, height as usize))
+    }
+
+    /// Leaves the cursor at the start of the frame's last row, so anything printed after it
+    /// (like a prompt) goes underneath
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        let redraws = TERMINAL_REDRAWS.load(Ordering::SeqCst);
+        if self.is_screen && redraws != self.redraws {
+            self.redraws = redraws;
+            self.previous = None;
+        }
+
+        // Nothing to diff against, so start from a blank screen
+        if self.previous.is_none() {
+            queue!(self.out, Clear(ClearType::All), MoveTo(0, 0))?;
+            if self.hide_cursor {
+                queue!(self.out, Hide)?;
+            }
+        }
+
+        frame.present(self.previous.as_ref(), &mut self.out)?;
+        self.previous = Some(frame.clone());
+        if self.is_screen {
+            *SCREEN.lock().unwrap() = Some(frame.clone());
+        }
+
+        queue!(
+            self.out,
+            MoveTo(0, frame.get_height().saturating_sub(1) as u16)
+        )?;
+        self.out.flush()
+    }
+
+    fn invalidate(&mut self) {
+        self.previous = None;
+    }
+}
+
+/// A screen in memory, for tests. Frames are drawn over what's already there, like on a
+/// terminal.
+pub struct MemoryBackend {
+    screen: Frame,
+    draws: usize,
+}
+
+impl MemoryBackend {
+    pub fn new(width: usize, height: usize) -> Self {
+        MemoryBackend {
+            screen: Frame::new(width, height),
+            draws: 0,
+        }
+    }
+
+    /// Changes the screen's size, like resizing a terminal. What's on it is kept, as far as
+    /// it fits.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut screen = Frame::new(width, height);
+        screen.blit(0, 0, &self.screen);
+        self.screen = screen;
+    }
+
+    /// Everything on the screen
+    pub fn screen(&self) -> &Frame {
+        &self.screen
+    }
+
+    /// How many frames have been drawn
+    pub fn draws(&self) -> usize {
+        self.draws
+    }
+
+    /// The screen's text, row by row, without trailing spaces; for comparing with snapshots
+    pub fn snapshot(&self) -> String {
+        (0..self.screen.get_height())
+            .map(|y| self.screen.row(y).trim_end().to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        Ok((self.screen.get_width(), self.screen.get_height()))
+    }
+
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        self.screen.blit(0, 0, frame);
+        self.draws += 1;
+        Ok(())
+    }
+
+    fn invalidate(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_backend_only_writes_changes() {
+        let mut backend = TerminalBackend::new(Vec::new());
+        let mut frame = Frame::new(3, 2);
+        frame.print(0, 0, "abc");
+
+        backend.draw(&frame).unwrap();
+        let first = String::from_utf8(backend.out.clone()).unwrap();
+        assert!(first.starts_with("\x1b[2J\x1b[1;1H")); // Cleared first
+        assert!(first.contains("abc"));
+
+        backend.out.clear();
+        frame.set(1, 1, '#');
+        backend.draw(&frame).unwrap();
+        assert_eq!(
+            String::from_utf8(backend.out.clone()).unwrap(),
+            "\x1b[2;2H#\x1b[2;1H"
+        );
+
+        // Starts over once invalidated
+        backend.out.clear();
+        backend.invalidate();
+        backend.draw(&frame).unwrap();
+        assert!(String::from_utf8(backend.out.clone())
+            .unwrap()
+            .contains("abc"));
+    }
+
+    #[test]
+    fn test_memory_backend() {
+        let mut backend = MemoryBackend::new(4, 2);
+        let mut frame = Frame::new(2, 1);
+        frame.print(0, 0, "ab");
+
+        backend.draw(&frame).unwrap();
+
+        assert_eq!(backend.size().unwrap(), (4, 2));
+        assert_eq!(backend.snapshot(), "ab\n");
+        assert_eq!(backend.draws(), 1);
+
+        backend.resize(1, 3);
+        assert_eq!(backend.snapshot(), "a\n\n");
+    }
+
+    #[test]
+    fn test_invalidate_terminal() {
+        let mut backend = TerminalBackend {
+            is_screen: true,
+            ..TerminalBackend::new(Vec::new())
+        };
+        let frame = Frame::new(2, 1);
+        backend.draw(&frame).unwrap();
+
+        backend.out.clear();
+        backend.draw(&frame).unwrap();
+        assert!(!String::from_utf8(backend.out.clone())
+            .unwrap()
+            .contains("2J"));
+
+        // Everything's drawn again, cleared first
+        invalidate_terminal();
+        backend.out.clear();
+        backend.draw(&frame).unwrap();
+        assert!(String::from_utf8(backend.out.clone())
+            .unwrap()
+            .contains("2J"));
+    }
+}
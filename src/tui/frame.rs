@@ -0,0 +1,546 @@
+//! The canvas that every TUI component draws into: an off-screen grid of cells, which a
+//! `tui::backend::Backend` puts on the screen. Drawing to a frame rather than straight to the
+//! terminal means what's drawn can be checked in tests.
+//!
+//! Frames are drawn to the terminal by writing only what changed since the last frame.
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use std::io::{self, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Fills the cell to the right of a double-width character, which the character covers on
+/// the screen. It's never written out.
+const WIDE_CONTINUATION: char = '\0';
+
+/// How a cell is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    #[default]
+    Normal,
+    Dim,     // e.g. tiles the player remembers, but can't see right now
+    Reverse, // Swapped colors, e.g. for a cursor
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cell {
+    c: char,
+    style: Style,
+    color: Option<Color>, // The terminal's own text color, if none
+}
+
+impl Cell {
+    const BLANK: Cell = Cell {
+        c: ' ',
+        style: Style::Normal,
+        color: None,
+    };
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>, // Row by row
+}
+
+/// A run of changed cells on one row, starting at (x, y), all in the same style and color
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub x: usize,
+    pub y: usize,
+    pub text: String,
+    pub style: Style,
+    pub color: Option<Color>,
+}
+
+/// How many columns text takes up on the screen, leaving out ANSI escape codes and counting
+/// double-width characters (like CJK) twice
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        width += UnicodeWidthStr::width(&rest[..start]);
+        rest = match escape_code(&rest[start..]) {
+            Some((code, _)) => &rest[start + code.len()..],
+            None => "",
+        };
+    }
+    width + UnicodeWidthStr::width(rest)
+}
+
+/// The escape code at the start of `text`, and its final character, e.g. `m` for styling
+/// codes. `None` if it's cut off.
+pub(crate) fn escape_code(text: &str) -> Option<(&str, char)> {
+    let body = text.strip_prefix("\x1b[")?;
+    let end = body.find(|c: char| !(c.is_ascii_digit() || c == ';'))?;
+    let last = body[end..].chars().next()?;
+    Some((&text[..2 + end + last.len_utf8()], last))
+}
+
+/// Applies an SGR ("select graphic rendition") code's parameters, e.g. `33` from `\x1b[33m`,
+/// to the current style and color. Anything a frame can't show is ignored.
+fn apply_sgr(params: &str, style: &mut Style, color: &mut Option<Color>) {
+    const DARK: [Color; 8] = [
+        Color::Black,
+        Color::DarkRed,
+        Color::DarkGreen,
+        Color::DarkYellow,
+        Color::DarkBlue,
+        Color::DarkMagenta,
+        Color::DarkCyan,
+        Color::Grey,
+    ];
+    const BRIGHT: [Color; 8] = [
+        Color::DarkGrey,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+    ];
+
+    let numbers: Vec<u8> = params.split(';').map(|n| n.parse().unwrap_or(0)).collect();
+    let mut numbers = numbers.into_iter();
+    while let Some(n) = numbers.next() {
+        match n {
+            0 => (*style, *color) = (Style::Normal, None),
+            2 => *style = Style::Dim,
+            7 => *style = Style::Reverse,
+            22 | 27 => *style = Style::Normal,
+            30..=37 => *color = Some(DARK[(n - 30) as usize]),
+            90..=97 => *color = Some(BRIGHT[(n - 90) as usize]),
+            39 => *color = None,
+            38 => match numbers.next() {
+                Some(5) => *color = numbers.next().map(Color::AnsiValue),
+                Some(2) => {
+                    if let (Some(r), Some(g), Some(b)) =
+                        (numbers.next(), numbers.next(), numbers.next())
+                    {
+                        *color = Some(Color::Rgb { r, g, b });
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// The escape codes that switch the terminal to a style and color
+fn sgr(style: Style, color: Option<Color>) -> String {
+    let mut out = String::new();
+    match style {
+        Style::Normal => {}
+        Style::Dim => out.push_str(&SetAttribute(Attribute::Dim).to_string()),
+        Style::Reverse => out.push_str(&SetAttribute(Attribute::Reverse).to_string()),
+    }
+    if let Some(color) = color {
+        out.push_str(&SetForegroundColor(color).to_string());
+    }
+    out
+}
+
+impl Frame {
+    /// A frame of blank cells
+    pub fn new(width: usize, height: usize) -> Self {
+        Frame {
+            width,
+            height,
+            cells: vec![Cell::BLANK; width * height],
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Sets a cell. Anything outside of the frame is dropped.
+    pub fn set(&mut self, x: usize, y: usize, c: char) {
+        self.set_styled(x, y, c, Style::Normal);
+    }
+
+    pub fn set_styled(&mut self, x: usize, y: usize, c: char, style: Style) {
+        self.set_colored(x, y, c, style, None);
+    }
+
+    /// Sets a cell, with its text in `color` (`None` for the terminal's own color)
+    pub fn set_colored(&mut self, x: usize, y: usize, c: char, style: Style, color: Option<Color>) {
+        self.set_cell(x, y, Cell { c, style, color });
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, cell: Cell) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = y * self.width + x;
+
+        // Overwriting half of a double-width character blanks the other half, like a
+        // terminal does
+        if self.cells[i].c == WIDE_CONTINUATION && cell.c != WIDE_CONTINUATION && x > 0 {
+            self.cells[i - 1] = Cell::BLANK;
+        }
+        if x + 1 < self.width
+            && self.cells[i + 1].c == WIDE_CONTINUATION
+            && cell.c != WIDE_CONTINUATION
+        {
+            self.cells[i + 1] = Cell::BLANK;
+        }
+        self.cells[i] = cell;
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.get_styled(x, y).map(|(c, _)| c)
+    }
+
+    pub fn get_styled(&self, x: usize, y: usize) -> Option<(char, Style)> {
+        self.get_cell(x, y).map(|cell| (cell.c, cell.style))
+    }
+
+    /// The color of a cell's text; `None` for the terminal's own color
+    pub fn get_color(&self, x: usize, y: usize) -> Option<Color> {
+        self.get_cell(x, y).and_then(|cell| cell.color)
+    }
+
+    fn get_cell(&self, x: usize, y: usize) -> Option<Cell> {
+        (x < self.width && y < self.height).then(|| self.cells[y * self.width + x])
+    }
+
+    /// Writes text from (x, y) rightwards, cutting it off at the frame's edge
+    pub fn print(&mut self, x: usize, y: usize, text: &str) {
+        self.print_cells(x, y, text, Style::Normal, None);
+    }
+
+    /// Writes text that may have ANSI escape codes in it (like the game's colored narration)
+    /// from (x, y) rightwards, turning the codes into the cells' styles and colors
+    pub fn print_ansi(&mut self, x: usize, y: usize, text: &str) {
+        let (mut style, mut color) = (Style::Normal, None);
+        let mut x = x;
+        let mut rest = text;
+        while let Some(start) = rest.find('\x1b') {
+            x = self.print_cells(x, y, &rest[..start], style, color);
+            rest = match escape_code(&rest[start..]) {
+                Some((code, last)) => {
+                    if last == 'm' {
+                        apply_sgr(&code[2..code.len() - 1], &mut style, &mut color);
+                    }
+                    &rest[start + code.len()..]
+                }
+                None => "", // Cut off, e.g. halfway through typing it out
+            };
+        }
+        self.print_cells(x, y, rest, style, color);
+    }
+
+    /// Writes plain text, returning the column after it. Double-width characters take up two
+    /// cells, and characters with no width (like control characters) are left out.
+    fn print_cells(
+        &mut self,
+        mut x: usize,
+        y: usize,
+        text: &str,
+        style: Style,
+        color: Option<Color>,
+    ) -> usize {
+        for c in text.chars() {
+            let width = c.width().unwrap_or(0);
+            if width == 0 {
+                continue;
+            }
+            // Half of a double-width character can't be shown
+            if x + width > self.width {
+                break;
+            }
+            self.set_cell(x, y, Cell { c, style, color });
+            if width == 2 {
+                let c = WIDE_CONTINUATION;
+                self.set_cell(x + 1, y, Cell { c, style, color });
+            }
+            x += width;
+        }
+        x
+    }
+
+    /// Copies another frame onto this one, with its top left corner at (x, y)
+    pub fn blit(&mut self, x: usize, y: usize, other: &Frame) {
+        for other_y in 0..other.height {
+            for other_x in 0..other.width {
+                let cell = other.cells[other_y * other.width + other_x];
+                self.set_cell(x + other_x, y + other_y, cell);
+            }
+        }
+    }
+
+    fn row_cells(&self, y: usize) -> &[Cell] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row(&self, y: usize) -> String {
+        self.row_cells(y)
+            .iter()
+            .map(|cell| cell.c)
+            .filter(|c| *c != WIDE_CONTINUATION)
+            .collect()
+    }
+
+    /// A row with its styles and colors as ANSI escape codes, for code that prints lines of
+    /// text (like `tui::window::Window`) rather than presenting whole frames
+    pub fn styled_row(&self, y: usize) -> String {
+        let mut out = String::new();
+        let mut current = (Style::Normal, None);
+        for cell in self.row_cells(y) {
+            if (cell.style, cell.color) != current {
+                if current != (Style::Normal, None) {
+                    out.push_str(&SetAttribute(Attribute::Reset).to_string());
+                }
+                out.push_str(&sgr(cell.style, cell.color));
+                current = (cell.style, cell.color);
+            }
+            if cell.c != WIDE_CONTINUATION {
+                out.push(cell.c);
+            }
+        }
+        if current != (Style::Normal, None) {
+            out.push_str(&SetAttribute(Attribute::Reset).to_string());
+        }
+        out
+    }
+
+    /// What has to be written to turn `previous` into this frame. Everything, if there's no
+    /// previous frame, or it's a different size.
+    pub fn diff(&self, previous: Option<&Frame>) -> Vec<Change> {
+        let previous = previous
+            .filter(|previous| previous.width == self.width && previous.height == self.height);
+
+        let mut changes = Vec::new();
+        for y in 0..self.height {
+            let mut run: Option<Change> = None;
+            for x in 0..self.width {
+                let cell = self.cells[y * self.width + x];
+                let changed = previous.is_none_or(|previous| previous.get_cell(x, y) != Some(cell));
+
+                // A change of style or color starts a new run. The right half of a wide
+                // character stays with its left half.
+                if cell.c != WIDE_CONTINUATION
+                    && run.as_ref().is_some_and(|run| {
+                        !changed || run.style != cell.style || run.color != cell.color
+                    })
+                {
+                    changes.push(run.take().unwrap());
+                }
+                if changed && cell.c != WIDE_CONTINUATION {
+                    run.get_or_insert_with(|| Change {
+                        x,
+                        y,
+                        text: String::new(),
+                        style: cell.style,
+                        color: cell.color,
+                    })
+                    .text
+                    .push(cell.c);
+                }
+            }
+            changes.extend(run);
+        }
+
+        changes
+    }
+
+    /// Writes the changes since `previous` to `out`, with a cursor move before each run
+    pub fn present(&self, previous: Option<&Frame>, out: &mut impl Write) -> io::Result<()> {
+        for change in self.diff(previous) {
+            queue!(out, MoveTo(change.x as u16, change.y as u16))?;
+            if change.style == Style::Normal && change.color.is_none() {
+                queue!(out, Print(change.text))?;
+            } else {
+                queue!(
+                    out,
+                    Print(sgr(change.style, change.color)),
+                    Print(change.text),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            }
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(rows: &[&str]) -> Frame {
+        let mut frame = Frame::new(rows[0].chars().count(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            frame.print(0, y, row);
+        }
+        frame
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let mut frame = Frame::new(3, 2);
+        frame.set(2, 1, '#');
+        frame.set(3, 1, '#'); // Off the edge
+
+        assert_eq!(frame.get(2, 1), Some('#'));
+        assert_eq!(frame.get(3, 1), None);
+        assert_eq!(frame.row(1), "  #");
+    }
+
+    #[test]
+    fn test_styled_row() {
+        let mut frame = frame(&["abcd"]);
+        frame.set_styled(1, 0, 'b', Style::Reverse);
+        frame.set_styled(2, 0, 'c', Style::Dim);
+
+        assert_eq!(frame.styled_row(0), "a\x1b[7mb\x1b[0m\x1b[2mc\x1b[0md");
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut frame = frame(&["....", "...."]);
+        frame.blit(2, 1, &self::frame(&["ab", "cd"]));
+
+        assert_eq!(frame.row(0), "....");
+        assert_eq!(frame.row(1), "..ab"); // The rest is off the edge
+    }
+
+    #[test]
+    fn test_diff_without_previous_frame_is_everything() {
+        let frame = frame(&["ab", "cd"]);
+
+        assert_eq!(
+            frame.diff(None),
+            vec![
+                Change {
+                    x: 0,
+                    y: 0,
+                    text: "ab".to_string(),
+                    style: Style::Normal,
+                    color: None
+                },
+                Change {
+                    x: 0,
+                    y: 1,
+                    text: "cd".to_string(),
+                    style: Style::Normal,
+                    color: None
+                },
+            ]
+        );
+        // A resize means starting over
+        assert_eq!(frame.diff(Some(&Frame::new(3, 2))).len(), 2);
+    }
+
+    #[test]
+    fn test_diff_only_has_changed_runs() {
+        let previous = frame(&["#....#", "#....#"]);
+        let next = frame(&["#.@@.#", "#....#"]);
+
+        assert_eq!(
+            next.diff(Some(&previous)),
+            vec![Change {
+                x: 2,
+                y: 0,
+                text: "@@".to_string(),
+                style: Style::Normal,
+                color: None
+            }]
+        );
+        assert!(next.diff(Some(&next)).is_empty());
+    }
+
+    #[test]
+    fn test_present_moves_the_cursor() {
+        let previous = frame(&["....", "...."]);
+        let next = frame(&["....", ".#.#"]);
+
+        let mut out = Vec::new();
+        next.present(Some(&previous), &mut out).unwrap();
+
+        // Rows and columns are 1-based in escape codes
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[2;2H#\x1b[2;4H#");
+    }
+
+    #[test]
+    fn test_style_changes_split_runs() {
+        let previous = frame(&["...."]);
+        let mut next = frame(&["...."]);
+        next.set(1, 0, '#');
+        next.set_styled(2, 0, '#', Style::Dim);
+        next.set_styled(3, 0, '.', Style::Dim); // Same character, but dimmed now
+
+        let changes = next.diff(Some(&previous));
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.x, change.text.as_str(), change.style))
+                .collect::<Vec<_>>(),
+            vec![(1, "#", Style::Normal), (2, "#.", Style::Dim)]
+        );
+
+        let mut out = Vec::new();
+        next.present(Some(&previous), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1;2H#\x1b[1;3H\x1b[2m#.\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_print_ansi() {
+        let mut frame = Frame::new(6, 1);
+        frame.print_ansi(0, 0, "a\x1b[33mbc\x1b[39m\x1b[2md\x1b[0me\x1b[3");
+
+        assert_eq!(frame.row(0), "abcde "); // The cut off code is dropped
+        assert_eq!(frame.get_color(0, 0), None);
+        assert_eq!(frame.get_color(1, 0), Some(Color::DarkYellow));
+        assert_eq!(frame.get_styled(3, 0), Some(('d', Style::Dim)));
+        assert_eq!(frame.get_styled(4, 0), Some(('e', Style::Normal)));
+
+        frame.print_ansi(0, 0, "\x1b[38;2;1;2;3mX");
+        assert_eq!(frame.get_color(0, 0), Some(Color::Rgb { r: 1, g: 2, b: 3 }));
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("\x1b[33mhello\x1b[39m"), 5);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("╼◈╾"), 3);
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let mut frame = Frame::new(5, 1);
+        frame.print(0, 0, "日本語");
+
+        // The third one doesn't fit
+        assert_eq!(frame.row(0), "日本 ");
+        assert_eq!(frame.diff(None)[0].text, "日本 ");
+
+        // Overwriting half of one blanks the other half
+        frame.set(1, 0, 'x');
+        assert_eq!(frame.row(0), " x本 ");
+    }
+
+    #[test]
+    fn test_present_colors() {
+        let mut frame = Frame::new(2, 1);
+        frame.print_ansi(0, 0, "\x1b[36mab");
+
+        let mut out = Vec::new();
+        frame.present(None, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[1;1H\x1b[38;5;6mab\x1b[0m"
+        );
+        assert_eq!(frame.styled_row(0), "\x1b[38;5;6mab\x1b[0m");
+    }
+}
@@ -0,0 +1,192 @@
+//! Splits the terminal into panes, each of which is drawn into its own `Frame` and copied
+//! onto the screen.
+//!
+//! Layouts are worked out from the terminal's size every time it's drawn, so panes follow
+//! the terminal when it's resized, and panes that don't fit collapse (or disappear).
+use crate::tui::frame::Frame;
+use crate::tui::message_log::MessageLog;
+use crate::tui::sidebar::Status;
+
+/// A rectangle of the screen, in cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Cuts `width` columns (or as many as there are) off the right, and returns what's left
+    /// and what was cut off
+    pub fn split_right(self, width: usize) -> (Rect, Rect) {
+        let width = width.min(self.width);
+        let left = Rect::new(self.x, self.y, self.width - width, self.height);
+        let right = Rect::new(self.x + left.width, self.y, width, self.height);
+        (left, right)
+    }
+
+    /// Cuts `height` rows (or as many as there are) off the bottom, and returns what's left
+    /// and what was cut off
+    pub fn split_bottom(self, height: usize) -> (Rect, Rect) {
+        let height = height.min(self.height);
+        let top = Rect::new(self.x, self.y, self.width, self.height - height);
+        let bottom = Rect::new(self.x, self.y + top.height, self.width, height);
+        (top, bottom)
+    }
+}
+
+/// The sidebar's width, including its border
+pub const SIDEBAR_WIDTH: usize = 24;
+/// The message log's height, including its border
+pub const LOG_HEIGHT: usize = 6;
+/// How small the map can get before the sidebar and log make way for it
+pub const MIN_MAP_WIDTH: usize = 40;
+pub const MIN_MAP_HEIGHT: usize = 10;
+
+/// The panes of the world navigation screen: the map, with a status sidebar on its right,
+/// and a message log along the bottom
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NavigationLayout {
+    pub screen: Rect,
+    pub map: Rect,
+    pub sidebar: Option<Rect>, // Left out of narrow terminals
+    pub log: Rect,             // Only a single line (the latest message) in short terminals
+}
+
+impl NavigationLayout {
+    pub fn new(screen: Rect) -> Self {
+        let log_height = if screen.height >= MIN_MAP_HEIGHT + LOG_HEIGHT {
+            LOG_HEIGHT
+        } else {
+            1
+        };
+        let (top, log) = screen.split_bottom(log_height);
+
+        let (map, sidebar) = if top.width >= MIN_MAP_WIDTH + SIDEBAR_WIDTH {
+            let (map, sidebar) = top.split_right(SIDEBAR_WIDTH);
+            (map, Some(sidebar))
+        } else {
+            (top, None)
+        };
+
+        NavigationLayout {
+            screen,
+            map,
+            sidebar,
+            log,
+        }
+    }
+
+    /// Draws the whole screen, from the map (already drawn to the map pane's size), the
+    /// player's status, and the message log
+    pub fn draw(&self, map: &Frame, status: &Status, log: &MessageLog) -> Frame {
+        let mut frame = Frame::new(self.screen.width, self.screen.height);
+        let mut blit = |rect: Rect, pane: &Frame| {
+            frame.blit(rect.x - self.screen.x, rect.y - self.screen.y, pane);
+        };
+
+        blit(self.map, map);
+        // Without the sidebar, the log's title has the gist of it
+        let title = match self.sidebar {
+            Some(sidebar) => {
+                blit(sidebar, &status.render(sidebar.width, sidebar.height));
+                "Messages".to_string()
+            }
+            None => status.summary(),
+        };
+        blit(
+            self.log,
+            &log.render(self.log.width, self.log.height, &title),
+        );
+
+        frame
+    }
+
+    /// Whether the log has room for more than the latest message
+    pub fn has_full_log(&self) -> bool {
+        self.log.height > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let rect = Rect::new(2, 1, 10, 6);
+
+        assert_eq!(
+            rect.split_right(4),
+            (Rect::new(2, 1, 6, 6), Rect::new(8, 1, 4, 6))
+        );
+        assert_eq!(
+            rect.split_bottom(2),
+            (Rect::new(2, 1, 10, 4), Rect::new(2, 5, 10, 2))
+        );
+        // Can't cut off more than there is
+        assert_eq!(rect.split_right(20).0, Rect::new(2, 1, 0, 6));
+        assert!(rect.split_right(20).0.is_empty());
+    }
+
+    #[test]
+    fn test_navigation_layout() {
+        let layout = NavigationLayout::new(Rect::new(0, 0, 100, 30));
+
+        assert_eq!(layout.screen, Rect::new(0, 0, 100, 30));
+        assert_eq!(layout.map, Rect::new(0, 0, 76, 24));
+        assert_eq!(layout.sidebar, Some(Rect::new(76, 0, 24, 24)));
+        assert_eq!(layout.log, Rect::new(0, 24, 100, 6));
+        assert!(layout.has_full_log());
+    }
+
+    #[test]
+    fn test_navigation_layout_collapses() {
+        // Too narrow for the sidebar
+        let layout = NavigationLayout::new(Rect::new(0, 0, 60, 30));
+        assert_eq!(layout.sidebar, None);
+        assert_eq!(layout.map, Rect::new(0, 0, 60, 24));
+
+        // Too short for the full log
+        let layout = NavigationLayout::new(Rect::new(0, 0, 100, 12));
+        assert_eq!(layout.log, Rect::new(0, 11, 100, 1));
+        assert!(!layout.has_full_log());
+
+        // Nothing at all
+        let layout = NavigationLayout::new(Rect::new(0, 0, 0, 0));
+        assert!(layout.map.is_empty());
+        assert!(layout.log.is_empty());
+    }
+
+    #[test]
+    fn test_draw_without_sidebar() {
+        let layout = NavigationLayout::new(Rect::new(0, 0, 50, 20));
+        let status = Status {
+            name: "Alice".to_string(),
+            stage: "first_steps".to_string(),
+            location: "meadow".to_string(),
+        };
+        let mut map = Frame::new(50, 14);
+        map.print(0, 0, "map");
+
+        let frame = layout.draw(&map, &status, &MessageLog::new());
+
+        assert_eq!(frame.row(0).trim_end(), "map");
+        // The status goes in the log's title instead
+        assert!(frame.row(14).starts_with("─ Alice · meadow ─"));
+    }
+}
@@ -1,7 +1,491 @@
-// TODO: Use this
-struct _Menu<'a, T: std::fmt::Display> {
-    message: &'a str,
-    options: Vec<T>,
-    selected_index: usize,
-    use_simulate_typing: bool,
+//! A list of options to pick from.
+//!
+//! The menu only keeps track of what's selected and draws itself to a `Frame`; reading keys
+//! from the terminal and showing the frame is up to the caller (see
+//! `interactions::methods::menu_select`), so the menu can be tested without a terminal.
+//!
+//! Keys:
+//!  - Up/Down move the selection, wrapping around at either end
+//!  - Home/End jump to the first/last option, PageUp/PageDown by a page
+//!  - 1-9 pick one of the first nine options straight away
+//!  - Typing anything else filters the options; Backspace undoes it
+//!  - Enter picks the selected option, unless it's disabled
+//!  - Esc clears the filter, or cancels the menu if it can be cancelled
+//!
+//! Like the rest of the game's text, the message is in `TBGColors::TEXT_COLOR`, and the
+//! options are in `TBGColors::ACTION_COLOR`.
+use crate::terminal_utils::TBGColors;
+use crate::tui::frame::{Frame, Style};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::Color;
+use std::fmt;
+
+/// How many options are shown at once, unless told otherwise
+pub const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// How a menu was done with
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuChoice<T> {
+    Picked(T),
+    Cancelled, // Only if the menu can be cancelled
+}
+
+struct MenuItem<T> {
+    value: T,
+    label: String,
+    disabled: Option<String>, // Why it can't be picked
+}
+
+pub struct Menu<T> {
+    message: String,
+    items: Vec<MenuItem<T>>,
+    selected: usize, // Index into `items`
+    filter: String,
+    scroll: usize, // How many of the matching options are scrolled past
+    page_size: usize,
+    cancellable: bool,
+    notice: Option<String>, // Shown under the options until the next key press
+}
+
+impl<T: Clone> Menu<T> {
+    pub fn new(message: &str, options: Vec<T>) -> Self
+    where
+        T: fmt::Display,
+    {
+        let items = options
+            .into_iter()
+            .map(|value| MenuItem {
+                label: value.to_string(),
+                value,
+                disabled: None,
+            })
+            .collect();
+
+        Menu {
+            message: message.to_string(),
+            items,
+            selected: 0,
+            filter: String::new(),
+            scroll: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+            cancellable: false,
+            notice: None,
+        }
+    }
+
+    /// Lets the menu be cancelled with Esc
+    pub fn with_cancel(mut self) -> Self {
+        self.cancellable = true;
+        self
+    }
+
+    /// Shows the option at `index`, but doesn't let it be picked. The reason is shown next to
+    /// it.
+    pub fn with_disabled(mut self, index: usize, reason: &str) -> Self {
+        if let Some(item) = self.items.get_mut(index) {
+            item.disabled = Some(reason.to_string());
+        }
+        if index == self.selected {
+            self.selected = self.first_enabled().unwrap_or(0);
+        }
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.set_page_size(page_size);
+        self
+    }
+
+    /// Changes how many options are shown at once, e.g. when the terminal is resized
+    pub fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size.max(1);
+        self.scroll_to_selected();
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// The selected option, if any option matches the filter
+    pub fn selected(&self) -> Option<&T> {
+        self.matches()
+            .contains(&self.selected)
+            .then(|| &self.items[self.selected].value)
+    }
+
+    /// Handles a key press. Returns what the player chose once the menu is done, and `None`
+    /// while it isn't.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<MenuChoice<T>> {
+        self.notice = None;
+        let matches = self.matches();
+        let position = matches.iter().position(|index| *index == self.selected);
+        let last = matches.len().saturating_sub(1);
+
+        match key.code {
+            KeyCode::Up => {
+                if let Some(position) = position {
+                    self.selected = matches[position.checked_sub(1).unwrap_or(last)];
+                }
+            }
+            KeyCode::Down => {
+                if let Some(position) = position {
+                    self.selected = matches[if position == last { 0 } else { position + 1 }];
+                }
+            }
+            KeyCode::Home if !matches.is_empty() => self.selected = matches[0],
+            KeyCode::End if !matches.is_empty() => self.selected = matches[last],
+            KeyCode::PageUp => {
+                if let Some(position) = position {
+                    self.selected = matches[position.saturating_sub(self.page_size)];
+                }
+            }
+            KeyCode::PageDown => {
+                if let Some(position) = position {
+                    self.selected = matches[(position + self.page_size).min(last)];
+                }
+            }
+            KeyCode::Enter if position.is_some() => return self.pick(self.selected),
+            KeyCode::Esc => {
+                if !self.filter.is_empty() {
+                    self.set_filter(String::new());
+                } else if self.cancellable {
+                    return Some(MenuChoice::Cancelled);
+                }
+            }
+            KeyCode::Backspace => {
+                let mut filter = self.filter.clone();
+                filter.pop();
+                self.set_filter(filter);
+            }
+            // Shortcuts, unless a digit is part of what's being typed
+            KeyCode::Char(c @ '1'..='9') if self.filter.is_empty() => {
+                let index = c as usize - '1' as usize;
+                if let Some(index) = matches.get(index) {
+                    self.selected = *index;
+                    return self.pick(*index);
+                }
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let filter = format!("{}{}", self.filter, c);
+                self.set_filter(filter);
+            }
+            _ => {}
+        }
+
+        self.scroll_to_selected();
+        None
+    }
+
+    /// Draws the menu, no wider than `max_width`: the message, a page of options, and
+    /// whatever the player needs to know about them
+    pub fn render(&self, max_width: usize) -> Frame {
+        let text = Some(TBGColors::TEXT_COLOR);
+        let action = Some(TBGColors::ACTION_COLOR);
+        let mut lines: Vec<(String, Style, Option<Color>)> = self
+            .message
+            .lines()
+            .map(|line| (line.to_string(), Style::Normal, text))
+            .collect();
+        lines.push((String::new(), Style::Normal, None));
+
+        let matches = self.matches();
+        let page = self.scroll..(self.scroll + self.page_size).min(matches.len());
+        let scrolls = matches.len() > self.page_size;
+        if scrolls {
+            lines.push((more_line('↑', self.scroll), Style::Dim, None));
+        }
+        for (position, index) in matches.iter().enumerate().take(page.end).skip(page.start) {
+            let item = &self.items[*index];
+            let marker = if *index == self.selected { '>' } else { ' ' };
+            let shortcut = match position {
+                0..=8 if self.filter.is_empty() => format!("{}.", position + 1),
+                _ => "  ".to_string(),
+            };
+            let (label, style) = match &item.disabled {
+                Some(reason) => (format!("{} ({})", item.label, reason), Style::Dim),
+                None if *index == self.selected => (item.label.clone(), Style::Reverse),
+                None => (item.label.clone(), Style::Normal),
+            };
+            lines.push((format!("{} {} {}", marker, shortcut, label), style, action));
+        }
+        if scrolls {
+            lines.push((more_line('↓', matches.len() - page.end), Style::Dim, None));
+        }
+
+        if matches.is_empty() {
+            lines.push(("No matches".to_string(), Style::Dim, None));
+        }
+        if !self.filter.is_empty() {
+            lines.push((format!("Filter: {}", self.filter), Style::Normal, None));
+        }
+        if let Some(notice) = &self.notice {
+            lines.push((notice.clone(), Style::Normal, None));
+        }
+
+        let width = lines
+            .iter()
+            .map(|(line, _, _)| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(max_width);
+        let mut frame = Frame::new(width, lines.len());
+        for (y, (line, style, color)) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                frame.set_colored(x, y, c, *style, *color);
+            }
+        }
+
+        frame
+    }
+
+    /// Draws the menu with only the first `typed` characters of its message, and room left for
+    /// the rest, for typing the message out before the options appear
+    pub fn render_typing(&self, max_width: usize, typed: usize) -> Frame {
+        let full = self.render(max_width);
+        let mut frame = Frame::new(full.get_width(), full.get_height());
+
+        let mut remaining = typed;
+        for (y, line) in self.message.lines().enumerate() {
+            for (x, c) in line.chars().take(remaining).enumerate() {
+                frame.set_colored(x, y, c, Style::Normal, Some(TBGColors::TEXT_COLOR));
+            }
+            remaining = remaining.saturating_sub(line.chars().count());
+        }
+
+        frame
+    }
+
+    /// The indices of the options that match the filter, ignoring case
+    fn matches(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.label.to_lowercase().contains(&filter))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        let matches = self.matches();
+        if !matches.contains(&self.selected) {
+            // The first match that can be picked, or at least the first match
+            self.selected = matches
+                .iter()
+                .copied()
+                .find(|index| self.items[*index].disabled.is_none())
+                .or(matches.first().copied())
+                .unwrap_or(self.selected);
+        }
+        self.scroll = 0;
+    }
+
+    fn pick(&mut self, index: usize) -> Option<MenuChoice<T>> {
+        let item = &self.items[index];
+        match &item.disabled {
+            Some(reason) => {
+                self.notice = Some(format!("{} can't be picked: {}", item.label, reason));
+                None
+            }
+            None => Some(MenuChoice::Picked(item.value.clone())),
+        }
+    }
+
+    fn first_enabled(&self) -> Option<usize> {
+        self.items.iter().position(|item| item.disabled.is_none())
+    }
+
+    /// Scrolls just far enough for the selected option to be on the page
+    fn scroll_to_selected(&mut self) {
+        let matches = self.matches();
+        let Some(position) = matches.iter().position(|index| *index == self.selected) else {
+            return;
+        };
+        if position < self.scroll {
+            self.scroll = position;
+        } else if position >= self.scroll + self.page_size {
+            self.scroll = position + 1 - self.page_size;
+        }
+        self.scroll = self
+            .scroll
+            .min(matches.len().saturating_sub(self.page_size));
+    }
+}
+
+/// The line above or below a scrolled list, saying how many more options there are
+fn more_line(arrow: char, count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!("  {} {} more", arrow, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MenuChoice::{Cancelled, Picked};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn press(menu: &mut Menu<&'static str>, codes: &[KeyCode]) -> Option<MenuChoice<&'static str>> {
+        let mut picked = None;
+        for code in codes {
+            picked = menu.handle_key(key(*code));
+        }
+        picked
+    }
+
+    fn fruit() -> Menu<&'static str> {
+        Menu::new("Pick a fruit:", vec!["Apple", "Banana", "Cherry", "Date"])
+    }
+
+    #[test]
+    fn test_wraps_around() {
+        let mut menu = fruit();
+
+        assert_eq!(
+            press(&mut menu, &[KeyCode::Up, KeyCode::Enter]),
+            Some(Picked("Date"))
+        );
+
+        let mut menu = fruit();
+        let keys = [KeyCode::End, KeyCode::Down, KeyCode::Enter];
+        assert_eq!(press(&mut menu, &keys), Some(Picked("Apple")));
+    }
+
+    #[test]
+    fn test_home_and_end() {
+        let mut menu = fruit();
+
+        press(&mut menu, &[KeyCode::End]);
+        assert_eq!(menu.selected(), Some(&"Date"));
+        press(&mut menu, &[KeyCode::Home]);
+        assert_eq!(menu.selected(), Some(&"Apple"));
+    }
+
+    #[test]
+    fn test_number_shortcuts() {
+        let mut menu = fruit();
+
+        assert_eq!(
+            press(&mut menu, &[KeyCode::Char('3')]),
+            Some(Picked("Cherry"))
+        );
+        // Past the end of the list
+        assert_eq!(press(&mut menu, &[KeyCode::Char('9')]), None);
+    }
+
+    #[test]
+    fn test_type_to_filter() {
+        let mut menu = fruit();
+
+        press(&mut menu, &[KeyCode::Char('A'), KeyCode::Char('n')]);
+        assert_eq!(menu.get_filter(), "An");
+        assert_eq!(menu.selected(), Some(&"Banana"));
+
+        press(&mut menu, &[KeyCode::Char('x')]);
+        assert_eq!(menu.selected(), None);
+        assert_eq!(press(&mut menu, &[KeyCode::Enter]), None);
+
+        press(&mut menu, &[KeyCode::Backspace, KeyCode::Backspace]);
+        assert_eq!(menu.get_filter(), "A");
+        // Apple, Banana, and Date all have an 'a'
+        assert_eq!(
+            press(&mut menu, &[KeyCode::End, KeyCode::Enter]),
+            Some(Picked("Date"))
+        );
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut menu = fruit();
+        assert_eq!(press(&mut menu, &[KeyCode::Esc]), None);
+
+        let mut menu = fruit().with_cancel();
+        // The first Esc only clears the filter
+        press(&mut menu, &[KeyCode::Char('c')]);
+        assert_eq!(press(&mut menu, &[KeyCode::Esc]), None);
+        assert_eq!(menu.get_filter(), "");
+        assert_eq!(press(&mut menu, &[KeyCode::Esc]), Some(Cancelled));
+    }
+
+    #[test]
+    fn test_disabled_items() {
+        let mut menu = fruit().with_disabled(0, "out of season");
+
+        // The selection starts on the first option that can be picked
+        assert_eq!(menu.selected(), Some(&"Banana"));
+        assert_eq!(press(&mut menu, &[KeyCode::Char('1')]), None);
+        assert!(menu
+            .render(80)
+            .row(6)
+            .contains("can't be picked: out of season"));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut menu = fruit().with_disabled(3, "sold out");
+        press(&mut menu, &[KeyCode::Down]);
+
+        let frame = menu.render(80);
+
+        assert_eq!(frame.row(0).trim_end(), "Pick a fruit:");
+        assert_eq!(frame.row(2).trim_end(), "  1. Apple");
+        assert_eq!(frame.row(3).trim_end(), "> 2. Banana");
+        assert_eq!(frame.get_styled(5, 3), Some(('B', Style::Reverse)));
+        assert_eq!(frame.row(5).trim_end(), "  4. Date (sold out)");
+        assert_eq!(frame.get_styled(5, 5), Some(('D', Style::Dim)));
+
+        // Colored like the rest of the game's text
+        assert_eq!(frame.get_color(0, 0), Some(TBGColors::TEXT_COLOR));
+        for y in 2..6 {
+            assert_eq!(frame.get_color(5, y), Some(TBGColors::ACTION_COLOR));
+        }
+
+        // Cut off at the width it's given
+        assert_eq!(menu.render(6).row(0), "Pick a");
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let options: Vec<String> = (1..=20).map(|n| format!("Option {}", n)).collect();
+        let mut menu = Menu::new("Pick one:", options).with_page_size(5);
+
+        let frame = menu.render(80);
+        assert_eq!(frame.get_height(), 9); // Message, blank line, arrows, and a page
+        assert_eq!(frame.row(2).trim_end(), "");
+        assert_eq!(frame.row(3).trim_end(), "> 1. Option 1");
+        assert_eq!(frame.row(8).trim_end(), "  ↓ 15 more");
+
+        for _ in 0..6 {
+            menu.handle_key(key(KeyCode::Down));
+        }
+        let frame = menu.render(80);
+        assert_eq!(frame.row(2).trim_end(), "  ↑ 2 more");
+        assert_eq!(frame.row(7).trim_end(), "> 7. Option 7");
+
+        menu.handle_key(key(KeyCode::End));
+        assert_eq!(menu.render(80).row(8).trim_end(), "");
+        menu.handle_key(key(KeyCode::PageUp));
+        assert_eq!(menu.selected().map(String::as_str), Some("Option 15"));
+    }
+
+    #[test]
+    fn test_render_typing() {
+        let menu = fruit();
+
+        let frame = menu.render_typing(80, 4);
+
+        assert_eq!(frame.get_height(), menu.render(80).get_height());
+        assert_eq!(frame.row(0).trim_end(), "Pick");
+        assert_eq!(frame.row(2).trim_end(), "");
+    }
 }
@@ -0,0 +1,213 @@
+//! A scrolling log of what's happened, e.g. "The door is locked.", newest at the bottom.
+//!
+//! Messages the player hasn't seen yet are drawn normally, and the rest are dimmed. Messages
+//! too long for the pane are wrapped onto more rows.
+use crate::tui::frame::{Frame, Style};
+use crate::tui::wrap::wrap;
+
+/// How many messages are kept; older ones are dropped
+pub const MAX_MESSAGES: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct MessageLog {
+    messages: Vec<String>,
+    read: usize,   // How many of the messages (from the oldest) the player has seen
+    scroll: usize, // How many of the newest messages are scrolled out of view
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        MessageLog::default()
+    }
+
+    /// Adds a message, and scrolls back down to it
+    pub fn push(&mut self, message: &str) {
+        self.messages.push(message.to_string());
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+            self.read = self.read.saturating_sub(1);
+        }
+        self.scroll = 0;
+    }
+
+    pub fn latest(&self) -> Option<&str> {
+        self.messages.last().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Marks every message as seen, e.g. once the player has acted after they were shown
+    pub fn mark_read(&mut self) {
+        self.read = self.messages.len();
+    }
+
+    /// Scrolls back through older messages by a page of `rows`
+    pub fn scroll_up(&mut self, rows: usize) {
+        let max_scroll = self.messages.len().saturating_sub(rows);
+        self.scroll = (self.scroll + rows).min(max_scroll);
+    }
+
+    /// Scrolls towards the newest messages by a page of `rows`
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    /// Draws the log. With a single row, that's just (the start of) the latest message;
+    /// otherwise the first row is a rule with `title` in it, and the rest are messages.
+    /// The oldest message shown may be cut off at the top.
+    pub fn render(&self, width: usize, height: usize, title: &str) -> Frame {
+        let mut frame = Frame::new(width, height);
+        if height == 0 {
+            return frame;
+        }
+        if height == 1 {
+            if let Some(latest) = self.latest() {
+                let line = wrap(latest, width).into_iter().next().unwrap_or_default();
+                self.print_line(&mut frame, 0, self.messages.len() - 1, &line);
+            }
+            return frame;
+        }
+
+        let rows = height - 1;
+        let scroll = self.scroll.min(self.messages.len().saturating_sub(rows));
+        let end = self.messages.len() - scroll;
+
+        // Newest at the bottom, so fill the rows from the bottom up
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        let mut start = end;
+        while start > 0 && lines.len() < rows {
+            start -= 1;
+            let wrapped = wrap(&self.messages[start], width);
+            lines.extend(wrapped.into_iter().rev().map(|line| (start, line)));
+        }
+        lines.truncate(rows);
+        lines.reverse();
+
+        let mut rule = format!("─ {} ", title);
+        if start > 0 {
+            rule.push_str(&format!("─ ↑ {} older ", start));
+        }
+        if scroll > 0 {
+            rule.push_str(&format!("─ ↓ {} newer ", scroll));
+        }
+        for x in 0..width {
+            frame.set(x, 0, '─');
+        }
+        frame.print(0, 0, &rule);
+
+        for (row, (i, line)) in lines.iter().enumerate() {
+            self.print_line(&mut frame, row + 1, *i, line);
+        }
+
+        frame
+    }
+
+    /// Prints a line of message `i`, dimmed if the player has seen it
+    fn print_line(&self, frame: &mut Frame, y: usize, i: usize, line: &str) {
+        let style = if i < self.read {
+            Style::Dim
+        } else {
+            Style::Normal
+        };
+        for (x, c) in line.chars().enumerate() {
+            frame.set_styled(x, y, c, style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(count: usize) -> MessageLog {
+        let mut log = MessageLog::new();
+        for i in 1..=count {
+            log.push(&format!("Message {}", i));
+        }
+        log
+    }
+
+    #[test]
+    fn test_render() {
+        let frame = log(5).render(30, 4, "Messages");
+
+        assert_eq!(frame.row(0), "─ Messages ─ ↑ 2 older ───────");
+        assert_eq!(frame.row(1).trim_end(), "Message 3");
+        assert_eq!(frame.row(3).trim_end(), "Message 5");
+    }
+
+    #[test]
+    fn test_render_single_line() {
+        let mut log = log(2);
+
+        let frame = log.render(12, 1, "Messages");
+        assert_eq!(frame.row(0), "Message 2   ");
+        assert_eq!(frame.get_styled(0, 0), Some(('M', Style::Normal)));
+
+        // Seen messages are dimmed
+        log.mark_read();
+        let frame = log.render(12, 1, "Messages");
+        assert_eq!(frame.get_styled(0, 0), Some(('M', Style::Dim)));
+
+        assert_eq!(
+            MessageLog::new().render(12, 1, "Messages").row(0).trim(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let mut log = log(10);
+
+        log.scroll_up(3);
+        let frame = log.render(40, 4, "Messages");
+        assert_eq!(
+            frame.row(0).trim_end_matches('─'),
+            "─ Messages ─ ↑ 4 older ─ ↓ 3 newer "
+        );
+        assert_eq!(frame.row(3).trim_end(), "Message 7");
+
+        // Can't scroll past the oldest message
+        log.scroll_up(3);
+        log.scroll_up(3);
+        assert_eq!(log.render(40, 4, "Messages").row(1).trim_end(), "Message 1");
+
+        // A new message scrolls back down
+        log.push("Message 11");
+        assert_eq!(
+            log.render(40, 4, "Messages").row(3).trim_end(),
+            "Message 11"
+        );
+    }
+
+    #[test]
+    fn test_long_messages_wrap() {
+        let mut log = log(2);
+        log.push("The door is locked, and the key is nowhere to be found.");
+
+        let frame = log.render(20, 5, "Messages");
+
+        assert_eq!(frame.row(1).trim_end(), "Message 2");
+        assert_eq!(frame.row(2).trim_end(), "The door is locked,");
+        assert_eq!(frame.row(4).trim_end(), "nowhere to be found.");
+        // Only its first row fits on a single line
+        assert_eq!(
+            log.render(20, 1, "Messages").row(0).trim_end(),
+            "The door is locked,"
+        );
+    }
+
+    #[test]
+    fn test_old_messages_are_dropped() {
+        let log = log(MAX_MESSAGES + 5);
+
+        assert_eq!(log.len(), MAX_MESSAGES);
+        assert_eq!(log.latest(), Some("Message 105"));
+    }
+}
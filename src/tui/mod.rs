@@ -10,6 +10,13 @@
 //! TODO: All or almost all TUI components should have a trait. Should have render() function.
 //!
 
+pub mod backend;
 pub mod book;
+pub mod frame;
+pub mod layout;
 pub mod menu;
+pub mod message_log;
+pub mod sidebar;
+pub mod size_guard;
 pub mod window;
+pub mod wrap;
@@ -0,0 +1,75 @@
+//! The status sidebar next to the map: who the player is, how far along the story they are,
+//! and where they are.
+use crate::tui::frame::{Frame, Style};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    pub name: String,
+    pub stage: String,    // e.g. "character_creation"
+    pub location: String, // The name of the map the player is on
+}
+
+/// Ids like "character_creation" as words
+fn readable(id: &str) -> String {
+    id.replace('_', " ")
+}
+
+impl Status {
+    /// A single line of the status, for when there's no room for the sidebar
+    pub fn summary(&self) -> String {
+        format!("{} · {}", self.name, readable(&self.location))
+    }
+
+    /// Draws the sidebar, with a border along its left edge
+    pub fn render(&self, width: usize, height: usize) -> Frame {
+        let mut frame = Frame::new(width, height);
+        for y in 0..height {
+            frame.set(0, y, '│');
+        }
+
+        let sections = [
+            ("Player", self.name.clone()),
+            ("Stage", readable(&self.stage)),
+            ("Location", readable(&self.location)),
+        ];
+        for (i, (label, value)) in sections.iter().enumerate() {
+            let y = 1 + i * 3;
+            for (x, c) in label.chars().enumerate() {
+                frame.set_styled(2 + x, y, c, Style::Dim);
+            }
+            frame.print(4, y + 1, value);
+        }
+
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> Status {
+        Status {
+            name: "Alice".to_string(),
+            stage: "character_creation".to_string(),
+            location: "meadow".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let frame = status().render(24, 9);
+
+        assert_eq!(frame.row(0).trim_end(), "│");
+        assert_eq!(frame.row(1).trim_end(), "│ Player");
+        assert_eq!(frame.row(2).trim_end(), "│   Alice");
+        assert_eq!(frame.row(5).trim_end(), "│   character creation");
+        assert_eq!(frame.row(8).trim_end(), "│   meadow");
+        assert_eq!(frame.get_styled(2, 1), Some(('P', Style::Dim)));
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(status().summary(), "Alice · meadow");
+    }
+}
@@ -0,0 +1,202 @@
+//! Keeps the game from being played in a terminal too small to draw it in.
+//!
+//! The terminal's size is checked on startup, and on every resize event (all of the game's
+//! input goes through `read_event`). While it's too small, whatever was running is paused
+//! behind a screen asking the player to enlarge the terminal. Once it's big enough, the
+//! screen is put back the way it was, and the resize is passed on so it can be redrawn at
+//! the new size.
+use crate::tui::backend::{self, Backend, TerminalBackend};
+use crate::tui::frame::Frame;
+use crossterm::event::{self, Event};
+use crossterm::terminal;
+use std::io;
+use std::time::Duration;
+
+pub const MIN_WIDTH: usize = 60;
+pub const MIN_HEIGHT: usize = 16;
+
+pub fn is_big_enough(width: usize, height: usize) -> bool {
+    width >= MIN_WIDTH && height >= MIN_HEIGHT
+}
+
+/// The screen shown while the terminal is too small
+pub fn too_small_screen(width: usize, height: usize) -> Frame {
+    let lines = [
+        "Please enlarge your terminal".to_string(),
+        String::new(),
+        format!("It's {}x{}, and the game", width, height),
+        format!("needs at least {}x{}.", MIN_WIDTH, MIN_HEIGHT),
+    ];
+
+    let mut frame = Frame::new(width, height);
+    let top = height.saturating_sub(lines.len()) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        let x = width.saturating_sub(line.chars().count()) / 2;
+        frame.print(x, top + i, line);
+    }
+    frame
+}
+
+/// Shows the "too small" screen until the backend is big enough, reading events with
+/// `next_event` in the meantime. Returns whether it had to wait.
+pub fn wait_until_big_enough(
+    backend: &mut impl Backend,
+    mut next_event: impl FnMut() -> io::Result<Event>,
+) -> io::Result<bool> {
+    let (mut width, mut height) = backend.size()?;
+    if is_big_enough(width, height) {
+        return Ok(false);
+    }
+
+    while !is_big_enough(width, height) {
+        backend.invalidate(); // Resizing can scramble what's on the screen
+        backend.draw(&too_small_screen(width, height))?;
+
+        // Anything else (like a key press) is ignored until the game resumes
+        if let Event::Resize(..) = next_event()? {
+            (width, height) = backend.size()?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Pauses the game until the terminal is big enough, then puts the screen back the way it
+/// was. Returns whether it had to pause.
+pub fn ensure_size() -> io::Result<bool> {
+    // If there's no telling how big the terminal is, there's nothing to guard
+    let Ok((width, height)) = terminal::size() else {
+        return Ok(false);
+    };
+    if is_big_enough(width as usize, height as usize) {
+        return Ok(false);
+    }
+
+    // Raw mode, so keys pressed while paused aren't echoed
+    let was_raw = terminal::is_raw_mode_enabled()?;
+    terminal::enable_raw_mode()?;
+
+    // Not `TerminalBackend::stdout`, which would remember this screen as the game's
+    let mut terminal_backend = TerminalBackend::new(io::stdout()).with_hidden_cursor();
+    let paused = wait_until_big_enough(&mut terminal_backend, event::read)?;
+
+    if paused {
+        backend::invalidate_terminal();
+        if let Some(screen) = backend::last_screen() {
+            terminal_backend.invalidate();
+            terminal_backend.draw(&screen)?;
+        }
+    }
+
+    if !was_raw {
+        terminal::disable_raw_mode()?;
+    }
+    Ok(paused)
+}
+
+/// Waits up to `timeout` for an input event. A resize that leaves the terminal too small
+/// pauses here until it's big enough again; the resize is still returned, so the caller can
+/// redraw at the new size.
+pub fn read_event(timeout: Duration) -> Option<Event> {
+    if !event::poll(timeout).unwrap_or(false) {
+        return None;
+    }
+    let event = event::read().ok()?;
+    if let Event::Resize(..) = event {
+        ensure_size().ok();
+    }
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::backend::MemoryBackend;
+    use crossterm::event::{KeyCode, KeyEvent};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_is_big_enough() {
+        assert!(is_big_enough(80, 24));
+        assert!(is_big_enough(MIN_WIDTH, MIN_HEIGHT));
+        assert!(!is_big_enough(MIN_WIDTH - 1, 24));
+        assert!(!is_big_enough(80, MIN_HEIGHT - 1));
+    }
+
+    #[test]
+    fn test_too_small_screen() {
+        let frame = too_small_screen(40, 6);
+
+        assert_eq!(frame.row(1).trim(), "Please enlarge your terminal");
+        assert_eq!(frame.row(3).trim(), "It's 40x6, and the game");
+        assert_eq!(frame.row(4).trim(), "needs at least 60x16.");
+
+        // Even tinier terminals just get what fits
+        assert_eq!(too_small_screen(5, 1).row(0), "Pleas");
+        assert_eq!(too_small_screen(0, 0).get_height(), 0);
+    }
+
+    #[test]
+    fn test_big_enough_terminals_dont_wait() {
+        let mut backend = MemoryBackend::new(80, 24);
+
+        let waited =
+            wait_until_big_enough(&mut backend, || panic!("Shouldn't read events")).unwrap();
+
+        assert!(!waited);
+        assert_eq!(backend.draws(), 0);
+    }
+
+    /// A terminal that the test can resize while the guard is waiting on it
+    struct FakeTerminal {
+        size: Rc<Cell<(usize, usize)>>,
+        screens: Vec<Frame>,
+    }
+
+    impl Backend for FakeTerminal {
+        fn size(&self) -> io::Result<(usize, usize)> {
+            Ok(self.size.get())
+        }
+
+        fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+            self.screens.push(frame.clone());
+            Ok(())
+        }
+
+        fn invalidate(&mut self) {}
+    }
+
+    #[test]
+    fn test_waits_until_big_enough() {
+        let size = Rc::new(Cell::new((40, 10)));
+        let mut terminal = FakeTerminal {
+            size: size.clone(),
+            screens: Vec::new(),
+        };
+        let mut events = vec![
+            Event::Resize(80, 24),
+            Event::Resize(50, 20), // Still too narrow
+            Event::Key(KeyEvent::from(KeyCode::Enter)),
+        ];
+
+        let waited = wait_until_big_enough(&mut terminal, || {
+            let event = events.pop().unwrap();
+            if let Event::Resize(width, height) = event {
+                size.set((width as usize, height as usize));
+            }
+            Ok(event)
+        })
+        .unwrap();
+
+        assert!(waited);
+        assert!(events.is_empty());
+        // Redrawn at each size it was too small at
+        let sizes: Vec<(usize, usize)> = terminal
+            .screens
+            .iter()
+            .map(|screen| (screen.get_width(), screen.get_height()))
+            .collect();
+        assert_eq!(sizes, vec![(40, 10), (40, 10), (50, 20)]);
+    }
+}
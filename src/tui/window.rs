@@ -1,34 +1,46 @@
-use crossterm::{
-    cursor, execute,
-    terminal::{self, Clear, ClearType},
-};
-use regex::Regex;
-use std::io::{self, Write};
-// For calculating visual width of multibyte unicode chars
-// TODO: I'd like to get rid of this dependency if possible
-//       - Could just hardcode a map of multibyte chars I'm using
-use unicode_width::UnicodeWidthStr;
+use crate::tui::backend::{Backend, TerminalBackend};
+use crate::tui::frame::{display_width, Frame};
+use crate::tui::wrap::wrap;
+use std::io;
 
+#[derive(Clone, Copy)]
 pub struct Window<'a> {
     pub content: &'a str,
+    page: usize,
+    wrap: bool,
 }
 
 // FIXME: add a FrameType setting, use instead of hard-coding "NORMAL" borders
 //        - DO NOT want to query this from the GameState every time.
 //        - Want to load this at start of game, and only fetch if settings are updated.
-// FIXME: Can we validate that the content parameter actually fits inside of the terminal?
-//        - If it doesn't fit, how do we handle?
 impl<'a> Window<'a> {
     pub fn new(content: &'a str) -> Self {
-        Window { content }
+        Window {
+            content,
+            page: 0,
+            wrap: true,
+        }
+    }
+
+    /// Shows one page of content that's too tall for the window (or the last page, if
+    /// there aren't that many)
+    pub fn with_page(mut self, page: usize) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Cuts long lines off at the border instead of wrapping them, e.g. for ASCII art
+    pub fn without_wrapping(mut self) -> Self {
+        self.wrap = false;
+        self
     }
 
     /// This is one of the core functions of TBG!
     /// Use-cases:
     ///  - During dialogue/narration mode (TODO: Come up with these modes, naming conventions)
     ///  - *Not* during world/navigation mode (that uses Viewport::render())
-    ///  - The Viewport stuct's render fn and this draw_window fn are very similar
-    ///    - Might need to share some abstractions in the future
+    ///  - Like the Viewport, it draws into a `tui::frame::Frame`, which a backend puts on
+    ///    the screen
     ///
     /// Responsibilities:
     ///  - Take a string slice (usually a snippet of dialogue or narration)
@@ -41,84 +53,104 @@ impl<'a> Window<'a> {
     ///     - The lines may contain ANSI colors, or multi-byte unicode chars--
     ///       the padding calculations need to take into account these
     ///       invisible or non-standard chars
+    ///  - Lines too wide for the frame are word wrapped, and content too tall for it is
+    ///    split into pages, with a "more" indicator on the bottom border
     ///  - Users can choose the border styling in the settings
-    ///  - The draw_window method can be used in tandem with the simulate_typing method
+    ///  - The render method can be used in tandem with the simulate_typing method
     ///
     pub fn render(&self) -> io::Result<()> {
-        let mut stdout = io::stdout();
+        self.render_to(&mut TerminalBackend::stdout())
+    }
 
-        // Get the terminal size
-        let (width, height) = terminal::size()?;
-        let width = width.max(10);
-        let height = height.max(5);
+    /// Renders the window with a backend, which can be kept between renders (e.g. while
+    /// text is typed out), so only what changed is redrawn.
+    ///
+    /// The window leaves the screen's last row free, for a prompt under it.
+    pub fn render_to(&self, backend: &mut impl Backend) -> io::Result<()> {
+        let (width, height) = Window::frame_size(backend)?;
+        let mut frame = Frame::new(width, height);
+        self.draw(&mut frame);
+        backend.draw(&frame)
+    }
+
+    /// The size of the frame that `render_to` draws the window into
+    pub fn frame_size(backend: &impl Backend) -> io::Result<(usize, usize)> {
+        let (width, height) = backend.size()?;
+        Ok((width.max(10), height.max(5) - 1))
+    }
+
+    /// The content's lines, wrapped to fit in a frame of the given size, and split into
+    /// pages of as many as fit in it at once
+    pub fn pages(&self, width: usize, height: usize) -> Vec<Vec<String>> {
+        // A column of space is left between the text and each side
+        let line_width = match self.wrap {
+            true => width.saturating_sub(4).max(1),
+            false => usize::MAX, // Still split up, so styling carries over between lines
+        };
+        wrap(self.content, line_width)
+            .chunks(height.saturating_sub(2).max(1))
+            .map(<[String]>::to_vec)
+            .collect()
+    }
+
+    /// Draws the window over the whole frame
+    pub fn draw(&self, frame: &mut Frame) {
+        let (width, height) = (frame.get_width(), frame.get_height());
+        if width < 2 || height < 2 {
+            return; // No room for even the border
+        }
 
         // Create borders
-        // let top_border = format!("┏{}┓", "━".repeat((width - 2) as usize));
-        // let bottom_border = format!("┗{}┛", "━".repeat((width - 2) as usize));
-        // let empty_line = format!("┃{}┃", " ".repeat((width - 2) as usize));
+        // let top_border = format!("┏{}┓", "━".repeat(width - 2));
+        // let bottom_border = format!("┗{}┛", "━".repeat(width - 2));
+        // let empty_line = format!("┃{}┃", " ".repeat(width - 2));
         // TODO: Maybe use these "fantasy" style borders
         let repeat_count = (width - 2) / 3; // Required because the fantasy border is 3 chars long
         let remainder = (width - 2) % 3; // Required because the fantasy border is 3 chars long
-        let top_border = format!(
-            "╭{}{}╮",
-            "╼◈╾".repeat(repeat_count as usize),
-            "━".repeat(remainder as usize)
-        );
-        let bottom_border = format!(
-            "╰{}{}╯",
-            "╼◈╾".repeat(repeat_count as usize),
-            "━".repeat(remainder as usize)
-        );
-        let empty_line = format!("║{}║", " ".repeat((width - 2) as usize));
-
-        // Regex to remove ANSI escape codes (including color codes and resets)
-        let color_code_re = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
-
-        // Move the cursor to the top-left corner and clear the screen
-        execute!(stdout, cursor::MoveTo(0, 0), Clear(ClearType::All))?;
-        writeln!(stdout, "{}\r", top_border)?;
+        let top_border = format!("╭{}{}╮", "╼◈╾".repeat(repeat_count), "━".repeat(remainder));
+        let bottom_border = format!("╰{}{}╯", "╼◈╾".repeat(repeat_count), "━".repeat(remainder));
 
-        // Split content into lines and calculate padding
-        let content_lines: Vec<&str> = self.content.split('\n').collect();
-        let content_height = content_lines.len();
-        let padding_top = (height as usize - content_height - 2).max(0) / 2;
-        let padding_bottom = (height as usize - content_height - padding_top - 2).max(0);
-
-        // Pad top empty lines
-        for _ in 0..padding_top {
-            writeln!(stdout, "{}\r", empty_line)?;
+        frame.print(0, 0, &top_border);
+        for y in 1..height - 1 {
+            frame.set(0, y, '║');
+            frame.set(width - 1, y, '║');
         }
+        frame.print(0, height - 1, &bottom_border);
 
-        // Pad and print each line of content
-        for line in content_lines {
-            // Remove color codes to calculate the padding based on the actual length
-            let clean_line = color_code_re.replace_all(line, "");
-            // Correct visual width, accounting for multibyte unicode characters!
-            let line_len = UnicodeWidthStr::width(clean_line.as_ref());
-            let extra_padding = width as usize - 2 - line_len;
+        // Pick the page to show, and say which it is if there's more than one
+        let pages = self.pages(width, height);
+        let page = self.page.min(pages.len() - 1);
+        if pages.len() > 1 {
+            let more = page + 1 < pages.len();
+            let mut indicator = match more {
+                true => format!(" ▼ more ({}/{}) ", page + 1, pages.len()),
+                false => format!(" ({}/{}) ", page + 1, pages.len()),
+            };
+            // Narrow windows just get an arrow (if even that fits), so the corners aren't
+            // covered up
+            if indicator.chars().count() > width - 2 {
+                let arrow = " ▼ ";
+                let fits = arrow.chars().count() <= width - 2;
+                indicator = if more && fits { arrow } else { "" }.to_string();
+            }
+            let x = width.saturating_sub(indicator.chars().count()) / 2;
+            frame.print(x, height - 1, &indicator);
+        }
 
-            // Split padding between left and right equally
-            let padding_left = extra_padding / 2;
-            let padding_right = extra_padding - padding_left;
+        // Center the page's lines
+        let content_lines = &pages[page];
+        let padding_top = (height - 2).saturating_sub(content_lines.len()) / 2;
 
-            // Pad the line and print it, with color codes intact
-            let padded_line = format!(
-                "┃{}{}{}┃",
-                " ".repeat(padding_left),
-                line,
-                " ".repeat(padding_right)
-            );
+        for (i, line) in content_lines.iter().enumerate().take(height - 2) {
+            let y = 1 + padding_top + i;
+            frame.set(0, y, '┃');
+            frame.set(width - 1, y, '┃');
 
-            writeln!(stdout, "{}\r", padded_line)?;
+            // Correct visual width, accounting for color codes and multibyte unicode chars!
+            let extra_padding = (width - 2).saturating_sub(display_width(line));
+            let mut inner = Frame::new(width - 2, 1);
+            inner.print_ansi(extra_padding / 2, 0, line);
+            frame.blit(1, y, &inner);
         }
-
-        // Pad bottom empty lines
-        for _ in 0..(padding_bottom.saturating_sub(1)) {
-            writeln!(stdout, "{}\r", empty_line)?;
-        }
-
-        // Print the bottom border
-        write!(stdout, "{}\r", bottom_border)?;
-        stdout.flush()
     }
 }
@@ -0,0 +1,206 @@
+//! Word wrapping for text that may have ANSI escape codes (colors, etc.) and double-width
+//! characters in it.
+//!
+//! Widths are measured the way the text looks on the screen, so escape codes take up no room,
+//! and characters like CJK take up two columns. Styling that's in effect when a line is
+//! broken (or at a `\n`) is started again at the beginning of the next line, so every line
+//! can be drawn on its own.
+use crate::tui::frame::{display_width, escape_code};
+use unicode_width::UnicodeWidthChar;
+
+/// A piece of text: an escape code, or a single character
+enum Piece<'a> {
+    Code(&'a str),
+    Char(char),
+}
+
+fn pieces(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let code = (c == '\x1b').then(|| escape_code(rest)).flatten();
+        let len = match code {
+            Some((code, _)) => {
+                pieces.push(Piece::Code(code));
+                code.len()
+            }
+            None => {
+                pieces.push(Piece::Char(c));
+                c.len_utf8()
+            }
+        };
+        rest = &rest[len..];
+    }
+    pieces
+}
+
+/// Splits a paragraph into runs of spaces and runs of everything else (words)
+fn words(paragraph: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in paragraph.char_indices() {
+        let is_space = c == ' ';
+        if in_space.is_some_and(|in_space| in_space != is_space) {
+            words.push(&paragraph[start..i]);
+            start = i;
+        }
+        in_space = Some(is_space);
+    }
+    if start < paragraph.len() {
+        words.push(&paragraph[start..]);
+    }
+    words
+}
+
+/// Wraps text to lines no wider than `width`, breaking between words where it can. Words
+/// too long for a line are broken up. `\n`s always start a new line.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut wrapper = Wrapper {
+        width,
+        lines: Vec::new(),
+        line: String::new(),
+        line_width: 0,
+        active: String::new(),
+    };
+
+    for paragraph in text.split('\n') {
+        let mut spaces = "";
+        for word in words(paragraph) {
+            if word.starts_with(' ') {
+                spaces = word;
+                continue;
+            }
+
+            let word_width = display_width(word);
+            let spaces_width = display_width(spaces);
+            if wrapper.line_width > 0 && wrapper.line_width + spaces_width + word_width > width {
+                wrapper.break_line();
+            } else {
+                // Includes indentation at the start of a paragraph
+                wrapper.push_str(spaces, spaces_width);
+            }
+            spaces = "";
+            wrapper.push_word(word);
+        }
+        // Trailing spaces are kept (as many as fit), so text that's padded out to line up
+        // stays lined up
+        let room = width.saturating_sub(wrapper.line_width);
+        let trailing = display_width(spaces).min(room);
+        wrapper.push_str(&spaces[..trailing], trailing);
+        wrapper.break_line();
+    }
+
+    wrapper.lines
+}
+
+struct Wrapper {
+    width: usize,
+    lines: Vec<String>,
+    line: String,
+    line_width: usize,
+    active: String, // The styling codes in effect, to start the next line with
+}
+
+impl Wrapper {
+    fn break_line(&mut self) {
+        let line = std::mem::replace(&mut self.line, self.active.clone());
+        self.lines.push(line);
+        self.line_width = 0;
+    }
+
+    fn push_str(&mut self, text: &str, width: usize) {
+        self.line.push_str(text);
+        self.line_width += width;
+    }
+
+    /// Adds a word, breaking it up if it's wider than a whole line
+    fn push_word(&mut self, word: &str) {
+        for piece in pieces(word) {
+            match piece {
+                Piece::Code(code) => {
+                    self.line.push_str(code);
+                    if code.ends_with('m') {
+                        // A reset ends everything before it
+                        if matches!(code, "\x1b[m" | "\x1b[0m") {
+                            self.active.clear();
+                        } else {
+                            self.active.push_str(code);
+                        }
+                    }
+                }
+                Piece::Char(c) => {
+                    let width = c.width().unwrap_or(0);
+                    if self.line_width > 0 && self.line_width + width > self.width {
+                        self.break_line();
+                    }
+                    self.line.push(c);
+                    self.line_width += width;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_words() {
+        assert_eq!(
+            wrap("The quick brown fox jumps over the lazy dog", 15),
+            vec!["The quick brown", "fox jumps over", "the lazy dog"]
+        );
+        // Short enough already
+        assert_eq!(wrap("Hello", 15), vec!["Hello"]);
+    }
+
+    #[test]
+    fn test_wrap_keeps_line_breaks_and_indentation() {
+        assert_eq!(
+            wrap("One\n\n  Two three", 8),
+            vec!["One", "", "  Two", "three"]
+        );
+        // Padding at the end is kept, if it fits
+        assert_eq!(wrap("One  \nTwo   ", 5), vec!["One  ", "Two  "]);
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_words() {
+        assert_eq!(
+            wrap("A supercalifragilistic word", 10),
+            vec!["A", "supercalif", "ragilistic", "word"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_ignores_escape_codes_and_carries_them_over() {
+        let lines = wrap("\x1b[33mThe quick brown fox\x1b[39m jumps", 10);
+
+        // Color codes take up no room
+        assert_eq!(
+            lines,
+            vec![
+                "\x1b[33mThe quick",
+                "\x1b[33mbrown fox\x1b[39m",
+                "\x1b[33m\x1b[39mjumps"
+            ]
+        );
+        for line in &lines {
+            assert!(display_width(line) <= 10);
+        }
+
+        // A reset ends the styling
+        assert_eq!(
+            wrap("\x1b[1mbold\x1b[0m\nplain", 10),
+            vec!["\x1b[1mbold\x1b[0m", "plain"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_counts_double_width_characters() {
+        assert_eq!(wrap("日本語の本", 6), vec!["日本語", "の本"]);
+        assert_eq!(wrap("本 を 読む", 5), vec!["本 を", "読む"]);
+    }
+}
@@ -0,0 +1,135 @@
+//! The world is every map the player can walk between, loaded from the map files in
+//! `MAPS_DIR`. Each map is named after its file, e.g. the "cave" map is `cave.map`.
+//!
+//! Maps are linked by portals: triggers with a `warp <map> <spawn>` event.
+use crate::world::map::Map;
+use crate::world::map_file_utils::{load_map_from_file, MapFileError};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const MAPS_DIR: &str = "src/world/maps/";
+pub const MAP_EXTENSION: &str = "map";
+
+/// The map new players start on
+pub const STARTING_MAP: &str = "meadow";
+
+/// The spawn point players are placed at when they have nowhere better to be
+pub const DEFAULT_SPAWN: &str = "start";
+
+#[derive(Debug)]
+pub enum WorldError {
+    MapFile(MapFileError),
+    /// The map's file doesn't exist
+    UnknownMap(String),
+    /// The map's [map] section names a different map than its file
+    MismatchedName {
+        file_name: String,
+        map_name: String,
+    },
+    UnknownSpawn {
+        map: String,
+        spawn: String,
+    },
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::MapFile(e) => write!(f, "{}", e),
+            WorldError::UnknownMap(name) => write!(f, "There is no map named {}", name),
+            WorldError::MismatchedName {
+                file_name,
+                map_name,
+            } => write!(
+                f,
+                "The map in {}.{} is named {}",
+                file_name, MAP_EXTENSION, map_name
+            ),
+            WorldError::UnknownSpawn { map, spawn } => {
+                write!(f, "The {} map has no spawn point named {}", map, spawn)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+impl From<MapFileError> for WorldError {
+    fn from(e: MapFileError) -> Self {
+        WorldError::MapFile(e)
+    }
+}
+
+/// Loads maps on demand, and keeps them around for when the player comes back
+pub struct World {
+    maps_dir: PathBuf,
+    maps: HashMap<String, Map>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new(MAPS_DIR)
+    }
+}
+
+impl World {
+    pub fn new<P: AsRef<Path>>(maps_dir: P) -> Self {
+        World {
+            maps_dir: maps_dir.as_ref().to_path_buf(),
+            maps: HashMap::new(),
+        }
+    }
+
+    pub fn map_path(&self, name: &str) -> PathBuf {
+        self.maps_dir.join(name).with_extension(MAP_EXTENSION)
+    }
+
+    /// The names of every map in the world, sorted
+    pub fn map_names(&self) -> io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.maps_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(MAP_EXTENSION) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(stem.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn map(&mut self, name: &str) -> Result<&Map, WorldError> {
+        if !self.maps.contains_key(name) {
+            let path = self.map_path(name);
+            if !path.exists() {
+                return Err(WorldError::UnknownMap(name.to_string()));
+            }
+
+            let map = load_map_from_file(&path.to_string_lossy())?;
+            if map.get_name() != name {
+                return Err(WorldError::MismatchedName {
+                    file_name: name.to_string(),
+                    map_name: map.get_name().to_string(),
+                });
+            }
+            self.maps.insert(name.to_string(), map);
+        }
+
+        Ok(&self.maps[name])
+    }
+
+    /// Where a portal (or a new game) puts the player
+    pub fn spawn_point(&mut self, map: &str, spawn: &str) -> Result<(usize, usize), WorldError> {
+        self.map(map)?
+            .get_spawn(spawn)
+            .ok_or_else(|| WorldError::UnknownSpawn {
+                map: map.to_string(),
+                spawn: spawn.to_string(),
+            })
+    }
+}
@@ -0,0 +1,232 @@
+//! Works out which part of a map is on screen, as the player walks around it
+use crate::world::map::Map;
+
+/// The part of the map that's on screen, and where it's drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct View {
+    pub map_x: usize, // The map tile in the view's top left corner
+    pub map_y: usize,
+    pub screen_x: usize, // Where that tile is drawn. Non-zero when the map is centered.
+    pub screen_y: usize,
+    pub width: usize, // How many tiles are visible
+    pub height: usize,
+}
+
+impl View {
+    /// The map tile drawn at (screen_x, screen_y), if any
+    pub fn map_tile(&self, screen_x: usize, screen_y: usize) -> Option<(usize, usize)> {
+        let x = screen_x.checked_sub(self.screen_x)?;
+        let y = screen_y.checked_sub(self.screen_y)?;
+        (x < self.width && y < self.height).then_some((self.map_x + x, self.map_y + y))
+    }
+}
+
+/// Follows the player around the map.
+///
+/// The camera never shows anything past the map's edges: maps bigger than the screen stop
+/// scrolling at their edges, and smaller ones are centered. Within the dead-zone (a box in the
+/// middle of the screen), the player can move without the camera scrolling at all.
+#[derive(Clone, Debug, Default)]
+pub struct Camera {
+    dead_zone: (usize, usize), // Width and height. (0, 0) keeps the player centered.
+    origin: Option<(usize, usize)>, // The map tile in the top left corner, last time
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera::default()
+    }
+
+    pub fn with_dead_zone(mut self, width: usize, height: usize) -> Self {
+        self.dead_zone = (width, height);
+        self
+    }
+
+    /// Forgets where the camera was, so it jumps straight to the player (e.g. after a warp)
+    pub fn reset(&mut self) {
+        self.origin = None;
+    }
+
+    /// Moves the camera (if need be) to keep the player in view, and returns what's visible
+    /// on a screen of `view_width` by `view_height` tiles
+    pub fn follow(
+        &mut self,
+        map: &Map,
+        player: (usize, usize),
+        view_width: usize,
+        view_height: usize,
+    ) -> View {
+        let (map_x, screen_x, width) = follow_axis(
+            self.origin.map(|(x, _)| x),
+            player.0,
+            map.get_width(),
+            view_width,
+            self.dead_zone.0,
+        );
+        let (map_y, screen_y, height) = follow_axis(
+            self.origin.map(|(_, y)| y),
+            player.1,
+            map.get_height(),
+            view_height,
+            self.dead_zone.1,
+        );
+        self.origin = Some((map_x, map_y));
+
+        View {
+            map_x,
+            map_y,
+            screen_x,
+            screen_y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Follows the player along one axis. Returns the first visible map tile, where it's drawn,
+/// and how many tiles are visible.
+fn follow_axis(
+    previous: Option<usize>,
+    player: usize,
+    map_length: usize,
+    view_length: usize,
+    dead_zone: usize,
+) -> (usize, usize, usize) {
+    // The whole map fits, so center it
+    if map_length <= view_length {
+        return (0, (view_length - map_length) / 2, map_length);
+    }
+
+    // Where on screen the player can be without scrolling
+    let last = view_length.saturating_sub(1);
+    let low = view_length / 2 - dead_zone.min(last) / 2;
+    let high = low + dead_zone.min(last - low);
+
+    let origin = match previous {
+        Some(previous) if player < previous + low => player.saturating_sub(low),
+        Some(previous) if player > previous + high => player - high,
+        Some(previous) => previous,
+        None => player.saturating_sub(view_length / 2),
+    };
+
+    (origin.min(map_length - view_length), 0, view_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(camera: &mut Camera, map: &Map, player: (usize, usize)) -> (usize, usize) {
+        let view = camera.follow(map, player, 10, 5);
+        (view.map_x, view.map_y)
+    }
+
+    #[test]
+    fn test_centers_on_the_player() {
+        let map = Map::new(100, 50);
+        let mut camera = Camera::new();
+
+        assert_eq!(view(&mut camera, &map, (50, 25)), (45, 23));
+        assert_eq!(view(&mut camera, &map, (51, 25)), (46, 23));
+    }
+
+    #[test]
+    fn test_stops_at_the_map_edges() {
+        let map = Map::new(100, 50);
+        let mut camera = Camera::new();
+
+        assert_eq!(view(&mut camera, &map, (0, 0)), (0, 0));
+        assert_eq!(view(&mut camera, &map, (3, 1)), (0, 0));
+
+        // The view doesn't shrink at the right and bottom edges, either
+        let view = camera.follow(&map, (99, 49), 10, 5);
+        assert_eq!((view.map_x, view.map_y), (90, 45));
+        assert_eq!((view.width, view.height), (10, 5));
+        assert_eq!(view.map_tile(9, 4), Some((99, 49)));
+    }
+
+    #[test]
+    fn test_centers_small_maps() {
+        let map = Map::new(4, 3);
+        let mut camera = Camera::new();
+
+        let view = camera.follow(&map, (3, 2), 10, 5);
+
+        assert_eq!(
+            view,
+            View {
+                map_x: 0,
+                map_y: 0,
+                screen_x: 3,
+                screen_y: 1,
+                width: 4,
+                height: 3
+            }
+        );
+        assert_eq!(view.map_tile(2, 1), None);
+        assert_eq!(view.map_tile(3, 1), Some((0, 0)));
+        assert_eq!(view.map_tile(7, 1), None);
+    }
+
+    #[test]
+    fn test_centers_each_axis_separately() {
+        // Narrow, but taller than the view
+        let map = Map::new(4, 50);
+        let mut camera = Camera::new();
+
+        let view = camera.follow(&map, (1, 25), 10, 5);
+
+        assert_eq!((view.map_x, view.screen_x, view.width), (0, 3, 4));
+        assert_eq!((view.map_y, view.screen_y, view.height), (23, 0, 5));
+    }
+
+    #[test]
+    fn test_dead_zone() {
+        let map = Map::new(100, 50);
+        let mut camera = Camera::new().with_dead_zone(4, 1);
+
+        // The first frame centers on the player, like any other camera
+        assert_eq!(view(&mut camera, &map, (50, 25)), (45, 23));
+
+        // The dead-zone is screen columns 3 to 7, i.e. map columns 48 to 52
+        assert_eq!(view(&mut camera, &map, (52, 25)), (45, 23));
+        assert_eq!(view(&mut camera, &map, (48, 25)), (45, 23));
+        assert_eq!(view(&mut camera, &map, (53, 25)), (46, 23));
+        assert_eq!(view(&mut camera, &map, (47, 25)), (44, 23));
+
+        // It still stops at the edges
+        assert_eq!(view(&mut camera, &map, (0, 0)), (0, 0));
+
+        camera.reset();
+        assert_eq!(view(&mut camera, &map, (50, 25)), (45, 23));
+    }
+
+    #[test]
+    fn test_dead_zone_stays_in_view() {
+        let map = Map::new(20, 20);
+        // A dead-zone almost as big as the view
+        let mut camera = Camera::new().with_dead_zone(3, 3);
+
+        for x in (0..20).chain((0..20).rev()) {
+            let view = camera.follow(&map, (x, 10), 4, 4);
+            let screen_x = x - view.map_x;
+            assert!(
+                screen_x < 4,
+                "Player at {} drawn off screen, at {}",
+                x,
+                screen_x
+            );
+        }
+    }
+
+    #[test]
+    fn test_tiny_views() {
+        let map = Map::new(100, 50);
+        let mut camera = Camera::new().with_dead_zone(10, 10);
+
+        let view = camera.follow(&map, (50, 25), 1, 0);
+
+        assert_eq!((view.width, view.height), (1, 0));
+        assert_eq!(view.map_x, 50);
+    }
+}
@@ -0,0 +1,523 @@
+//! The map editor: a map being edited, with a cursor, a palette of tiles, and undo history.
+//!
+//! `MapEditorRoutine` drives this from the keyboard. Triggers and NPCs are typed in the same
+//! syntax as the map file's `[triggers]` and `[npcs]` sections, minus their position, which
+//! comes from the cursor (or the selected rectangle).
+use crate::tui::frame::Style;
+use crate::world::map::{tile_to_char, Map, TileType};
+use crate::world::map_file_utils::{load_map_from_file, save_map, MapFileError};
+use crate::world::map_format::{parse_npc_line, parse_trigger_line, MapParseError};
+use crate::world::minimap::points_of_interest;
+use crate::world::navigation::Direction;
+use crate::world::trigger::TriggerArea;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The tiles that can be painted, in the order of their number keys (1 to 9, then 0)
+pub const PALETTE: &[TileType] = &[
+    TileType::Grass,
+    TileType::Wall,
+    TileType::Water,
+    TileType::Empty,
+    TileType::Door,
+    TileType::LockedDoor,
+    TileType::Bridge,
+    TileType::Sign,
+    TileType::Chest,
+    TileType::Bookshelf,
+];
+
+/// How many edits can be undone
+pub const UNDO_LIMIT: usize = 100;
+
+/// How spawn points are drawn in the editor
+pub const SPAWN_MARKER: char = 'S';
+
+pub struct MapEditor {
+    map: Map,
+    path: PathBuf,
+    cursor: (usize, usize),
+    tile: usize,                    // Index into PALETTE
+    anchor: Option<(usize, usize)>, // The first corner of the rectangle being selected
+    undo: Vec<Map>,                 // Copies of the map before each edit, oldest first
+    redo: Vec<Map>,
+    modified: bool, // Since it was last saved
+}
+
+impl MapEditor {
+    /// Edits `map`, which is saved to `path`
+    pub fn new<P: AsRef<Path>>(map: Map, path: P) -> Self {
+        MapEditor {
+            map,
+            path: path.as_ref().to_path_buf(),
+            cursor: (0, 0),
+            tile: 0,
+            anchor: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            modified: false,
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, MapFileError> {
+        let map = load_map_from_file(&path.as_ref().to_string_lossy())?;
+        Ok(MapEditor::new(map, path))
+    }
+
+    pub fn get_map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    pub fn tile(&self) -> TileType {
+        PALETTE[self.tile]
+    }
+
+    pub fn is_selecting(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Moves the cursor one tile, stopping at the map's edges
+    pub fn move_cursor(&mut self, direction: Direction) {
+        let x = self.cursor.0 as isize + direction.dx();
+        let y = self.cursor.1 as isize + direction.dy();
+        if self.map.in_bounds(x, y) {
+            self.cursor = (x as usize, y as usize);
+        }
+    }
+
+    /// Picks a tile from the palette. Numbers past the end of it are ignored.
+    pub fn select_tile(&mut self, index: usize) {
+        if index < PALETTE.len() {
+            self.tile = index;
+        }
+    }
+
+    pub fn next_tile(&mut self) {
+        self.tile = (self.tile + 1) % PALETTE.len();
+    }
+
+    /// Starts selecting a rectangle from the cursor, or stops selecting
+    pub fn toggle_selection(&mut self) {
+        self.anchor = match self.anchor {
+            Some(_) => None,
+            None => Some(self.cursor),
+        };
+    }
+
+    /// The selected rectangle, or just the tile under the cursor
+    pub fn selection(&self) -> TriggerArea {
+        let (x, y) = self.cursor;
+        let (anchor_x, anchor_y) = self.anchor.unwrap_or(self.cursor);
+        TriggerArea {
+            x: x.min(anchor_x),
+            y: y.min(anchor_y),
+            width: x.abs_diff(anchor_x) + 1,
+            height: y.abs_diff(anchor_y) + 1,
+        }
+    }
+
+    /// Paints the tile under the cursor
+    pub fn paint(&mut self) {
+        let (x, y) = self.cursor;
+        if self.map.get_tile(x, y) != self.tile() {
+            self.checkpoint();
+            self.map.set_tile(x, y, self.tile());
+        }
+    }
+
+    /// Paints the whole selection, and stops selecting
+    pub fn fill(&mut self) {
+        let area = self.selection();
+        let tile = self.tile();
+        self.anchor = None;
+
+        let tiles = (area.y..area.y + area.height)
+            .flat_map(|y| (area.x..area.x + area.width).map(move |x| (x, y)));
+        if tiles.clone().all(|(x, y)| self.map.get_tile(x, y) == tile) {
+            return; // Nothing to do, so nothing to undo
+        }
+
+        self.checkpoint();
+        for (x, y) in tiles {
+            self.map.set_tile(x, y, tile);
+        }
+    }
+
+    /// Puts a spawn point under the cursor, moving it if it's already somewhere else
+    pub fn place_spawn(&mut self, name: &str) {
+        self.checkpoint();
+        self.map.add_spawn(name, self.cursor.0, self.cursor.1);
+    }
+
+    /// Adds a trigger covering the selection, and stops selecting. `text` is its id followed
+    /// by the rest of a `[triggers]` line after the area, e.g. `door enter repeat warp cave
+    /// start`. A trigger with the same id is replaced.
+    pub fn place_trigger(&mut self, text: &str) -> Result<(), MapParseError> {
+        let (id, rest) = split_id(text);
+        let area = self.selection();
+        let line = format!(
+            "{} = {},{} {}x{} {}",
+            id, area.x, area.y, area.width, area.height, rest
+        );
+        let trigger = parse_trigger_line(&line, &self.map)?;
+
+        self.checkpoint();
+        self.anchor = None;
+        let triggers = self.map.get_triggers_mut();
+        triggers.retain(|existing| existing.id != trigger.id);
+        triggers.push(trigger);
+        Ok(())
+    }
+
+    /// Puts an NPC under the cursor. `text` is their id followed by the rest of an `[npcs]`
+    /// line without the position, e.g. `guard 1 down dialogue first_quest`. An NPC with the
+    /// same id is replaced.
+    pub fn place_npc(&mut self, text: &str) -> Result<(), MapParseError> {
+        let (id, rest) = split_id(text);
+        let (npc_id, rest) = split_id(rest);
+        let line = format!(
+            "{} = {} {},{} {}",
+            id, npc_id, self.cursor.0, self.cursor.1, rest
+        );
+        let npc = parse_npc_line(&line, &self.map)?;
+
+        self.checkpoint();
+        let npcs = self.map.get_npcs_mut();
+        npcs.retain(|existing| existing.id != npc.id);
+        npcs.push(npc);
+        Ok(())
+    }
+
+    /// Removes the spawn points, triggers, and NPCs under the cursor. False if there were
+    /// none.
+    pub fn erase(&mut self) -> bool {
+        let (x, y) = self.cursor;
+        let spawns: Vec<String> = self
+            .map
+            .get_spawns()
+            .iter()
+            .filter(|(_, position)| **position == (x, y))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let has_trigger = self
+            .map
+            .get_triggers()
+            .iter()
+            .any(|trigger| trigger.area.contains(x, y));
+        let has_npc = self.map.npc_at(x, y).is_some();
+        if spawns.is_empty() && !has_trigger && !has_npc {
+            return false;
+        }
+
+        self.checkpoint();
+        for name in spawns {
+            self.map.remove_spawn(&name);
+        }
+        self.map
+            .get_triggers_mut()
+            .retain(|trigger| !trigger.area.contains(x, y));
+        self.map
+            .get_npcs_mut()
+            .retain(|npc| (npc.x, npc.y) != (x, y));
+        true
+    }
+
+    /// Undoes the last edit. False if there's nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(map) => {
+                self.redo.push(std::mem::replace(&mut self.map, map));
+                self.modified = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redoes the last undone edit. False if there's nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(map) => {
+                self.undo.push(std::mem::replace(&mut self.map, map));
+                self.modified = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes the map to its file, in the sectioned format
+    pub fn save(&mut self) -> io::Result<()> {
+        save_map(&self.map, &self.path.to_string_lossy())?;
+        self.modified = false;
+        Ok(())
+    }
+
+    /// How the tile at (x, y) is drawn: NPCs, spawn points, and points of interest over the
+    /// terrain, with the cursor and the selection in reverse
+    pub fn cell(&self, x: usize, y: usize) -> (char, Style) {
+        let c = if let Some(npc) = self.map.npc_at(x, y) {
+            npc.sprite
+        } else if self.map.get_spawns().values().any(|spawn| *spawn == (x, y)) {
+            SPAWN_MARKER
+        } else if let Some((_, _, marker)) = points_of_interest(&self.map, &[])
+            .into_iter()
+            .filter(|(px, py, _)| (*px, *py) == (x, y))
+            .min_by_key(|(_, _, marker)| *marker)
+        {
+            marker.symbol()
+        } else {
+            tile_to_char(self.map.get_tile(x, y), None)
+        };
+
+        let selected = self.anchor.is_some() && self.selection().contains(x, y);
+        if selected || (x, y) == self.cursor {
+            (c, Style::Reverse)
+        } else {
+            (c, Style::Normal)
+        }
+    }
+
+    /// Saves a copy of the map to undo back to, before an edit
+    fn checkpoint(&mut self) {
+        self.undo.push(self.map.clone());
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.modified = true;
+    }
+}
+
+/// Splits the first word off of the text
+fn split_id(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    match text.split_once(char::is_whitespace) {
+        Some((id, rest)) => (id, rest.trim_start()),
+        None => (text, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::trigger::TriggerEvent;
+
+    // A 5x4 field of grass
+    fn editor() -> MapEditor {
+        let mut map = Map::new(5, 4).with_name("test");
+        for y in 0..4 {
+            for x in 0..5 {
+                map.set_tile(x, y, TileType::Grass);
+            }
+        }
+        MapEditor::new(map, "test.map")
+    }
+
+    #[test]
+    fn test_cursor_stays_on_the_map() {
+        let mut editor = editor();
+
+        editor.move_cursor(Direction::Up);
+        editor.move_cursor(Direction::Right);
+
+        assert_eq!(editor.cursor(), (1, 0));
+    }
+
+    #[test]
+    fn test_paint_and_undo() {
+        let mut editor = editor();
+        editor.select_tile(1);
+
+        editor.paint();
+        assert_eq!(editor.get_map().get_tile(0, 0), TileType::Wall);
+        assert!(editor.is_modified());
+
+        assert!(editor.undo());
+        assert_eq!(editor.get_map().get_tile(0, 0), TileType::Grass);
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert_eq!(editor.get_map().get_tile(0, 0), TileType::Wall);
+        assert!(!editor.redo());
+
+        // Painting the same tile again isn't an edit
+        editor.paint();
+        assert!(editor.undo());
+        assert!(!editor.undo());
+    }
+
+    #[test]
+    fn test_new_edits_clear_redo() {
+        let mut editor = editor();
+        editor.select_tile(1);
+        editor.paint();
+        editor.undo();
+
+        editor.select_tile(2);
+        editor.paint();
+
+        assert!(!editor.redo());
+        assert_eq!(editor.get_map().get_tile(0, 0), TileType::Water);
+    }
+
+    #[test]
+    fn test_undo_limit() {
+        let mut editor = editor();
+        for i in 0..UNDO_LIMIT + 10 {
+            editor.next_tile();
+            editor.move_cursor(if i % 2 == 0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            });
+            editor.paint();
+        }
+
+        let mut undone = 0;
+        while editor.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+    }
+
+    #[test]
+    fn test_rectangle_fill() {
+        let mut editor = editor();
+        editor.select_tile(2);
+        editor.move_cursor(Direction::Right);
+        editor.toggle_selection();
+        editor.move_cursor(Direction::Right);
+        editor.move_cursor(Direction::Right);
+        editor.move_cursor(Direction::Down);
+
+        assert_eq!(
+            editor.selection(),
+            TriggerArea {
+                x: 1,
+                y: 0,
+                width: 3,
+                height: 2
+            }
+        );
+        editor.fill();
+
+        assert!(!editor.is_selecting());
+        let water = |x, y| editor.get_map().get_tile(x, y) == TileType::Water;
+        assert!(water(1, 0) && water(3, 1));
+        assert!(!water(0, 0) && !water(4, 1) && !water(1, 2));
+
+        // The whole fill is one edit
+        editor.undo();
+        assert_eq!(editor.get_map().get_tile(3, 1), TileType::Grass);
+    }
+
+    #[test]
+    fn test_place_spawn_trigger_and_npc() {
+        let mut editor = editor();
+        editor.move_cursor(Direction::Down);
+
+        editor.place_spawn("start");
+        editor.toggle_selection();
+        editor.move_cursor(Direction::Right);
+        editor
+            .place_trigger("door enter repeat warp cave start")
+            .unwrap();
+        editor
+            .place_npc("guard 1 down dialogue first_quest")
+            .unwrap();
+
+        let map = editor.get_map();
+        assert_eq!(map.get_spawn("start"), Some((0, 1)));
+        let trigger = &map.get_triggers()[0];
+        assert_eq!((trigger.area.width, trigger.area.height), (2, 1));
+        assert_eq!(
+            trigger.event,
+            TriggerEvent::Warp {
+                map: "cave".to_string(),
+                spawn: "start".to_string()
+            }
+        );
+        let npc = map.npc_at(1, 1).unwrap();
+        assert_eq!((npc.id.as_str(), npc.npc_id), ("guard", 1));
+        assert_eq!(npc.dialogue.as_deref(), Some("first_quest"));
+
+        // Placing them again replaces them
+        editor.place_npc("guard 2 up").unwrap();
+        assert_eq!(editor.get_map().get_npcs().len(), 1);
+    }
+
+    #[test]
+    fn test_bad_triggers_and_npcs_are_rejected() {
+        let mut editor = editor();
+
+        assert!(editor
+            .place_trigger("door enter sometimes book_builder")
+            .is_err());
+        assert!(editor.place_npc("guard 1 sideways").is_err());
+
+        assert!(editor.get_map().get_triggers().is_empty());
+        assert!(!editor.undo()); // Nothing happened
+    }
+
+    #[test]
+    fn test_erase() {
+        let mut editor = editor();
+        editor.place_spawn("start");
+        editor
+            .place_trigger("sign inspect repeat book_builder")
+            .unwrap();
+        editor.move_cursor(Direction::Right);
+        editor.place_npc("guard 1 down").unwrap();
+
+        assert!(editor.erase());
+        assert!(!editor.erase());
+        editor.move_cursor(Direction::Left);
+        assert!(editor.erase());
+
+        let map = editor.get_map();
+        assert!(map.get_spawns().is_empty());
+        assert!(map.get_triggers().is_empty());
+        assert!(map.get_npcs().is_empty());
+    }
+
+    #[test]
+    fn test_cells() {
+        let mut editor = editor();
+        editor.place_spawn("start");
+        editor.move_cursor(Direction::Right);
+
+        assert_eq!(editor.cell(0, 0), (SPAWN_MARKER, Style::Normal));
+        assert_eq!(editor.cell(1, 0), ('.', Style::Reverse));
+        assert_eq!(editor.cell(2, 0), ('.', Style::Normal));
+    }
+
+    #[test]
+    fn test_save() {
+        let path = std::env::temp_dir().join(format!("editor_{}.map", uuid::Uuid::new_v4()));
+        let mut editor = MapEditor::new(editor().get_map().clone(), &path);
+        editor.select_tile(2);
+        editor.paint();
+        editor.place_spawn("start");
+
+        editor.save().unwrap();
+        assert!(!editor.is_modified());
+
+        let saved = MapEditor::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.get_map().get_tile(0, 0), TileType::Water);
+        assert_eq!(saved.get_map().get_spawn("start"), Some((0, 0)));
+    }
+}
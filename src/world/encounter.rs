@@ -0,0 +1,166 @@
+//! Random encounters: tables of opponents that might jump out at the player as they walk
+//! over certain tiles, or through certain parts of a map.
+use crate::world::map::{Map, TileType};
+use crate::world::random::{tick_rng, ENCOUNTER_STREAM};
+use crate::world::trigger::TriggerArea;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+/// Which tiles an encounter table covers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncounterArea {
+    Tile(TileType), // Every tile of this type on the map
+    Region(TriggerArea),
+}
+
+/// Someone who might be encountered, and how likely they are compared to the rest of the table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opponent {
+    pub npc_id: i32, // The NPC in the non_player_characters table
+    pub weight: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncounterTable {
+    pub id: String, // Unique within its map
+    pub area: EncounterArea,
+    pub rate: u32, // Chance of an encounter per step, in percent
+    pub opponents: Vec<Opponent>,
+}
+
+impl EncounterTable {
+    pub fn new(id: &str, area: EncounterArea, rate: u32) -> Self {
+        EncounterTable {
+            id: id.to_string(),
+            area,
+            rate,
+            opponents: Vec::new(),
+        }
+    }
+
+    pub fn with_opponent(mut self, npc_id: i32, weight: u32) -> Self {
+        self.opponents.push(Opponent { npc_id, weight });
+        self
+    }
+
+    pub fn covers(&self, map: &Map, x: usize, y: usize) -> bool {
+        match &self.area {
+            EncounterArea::Tile(tile) => map.get_tile(x, y) == *tile,
+            EncounterArea::Region(area) => area.contains(x, y),
+        }
+    }
+
+    /// Rolls for an encounter, returning the id of the NPC encountered, if any
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<i32> {
+        if rng.gen_range(0..100) >= self.rate {
+            return None;
+        }
+
+        let weights = WeightedIndex::new(self.opponents.iter().map(|o| o.weight)).ok()?;
+        Some(self.opponents[weights.sample(rng)].npc_id)
+    }
+}
+
+/// Rolls for an encounter on the player's step onto (x, y), using the first of the map's
+/// encounter tables that covers it.
+///
+/// The roll only depends on the player's seed and the tick, so the same save gets the same
+/// encounters when it walks the same way.
+pub fn roll_encounter(map: &Map, x: usize, y: usize, seed: u64, tick: u64) -> Option<i32> {
+    let table = map.encounter_table_at(x, y)?;
+    table.roll(&mut tick_rng(seed, tick, ENCOUNTER_STREAM))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4x1 strip: grass, grass, empty, empty
+    fn test_map() -> Map {
+        let mut map = Map::new(4, 1);
+        map.set_tile(0, 0, TileType::Grass);
+        map.set_tile(1, 0, TileType::Grass);
+        map
+    }
+
+    #[test]
+    fn test_covers() {
+        let map = test_map();
+
+        let grass = EncounterTable::new("grass", EncounterArea::Tile(TileType::Grass), 10);
+        assert!(grass.covers(&map, 1, 0));
+        assert!(!grass.covers(&map, 2, 0));
+
+        let region = EncounterTable::new(
+            "region",
+            EncounterArea::Region(TriggerArea {
+                x: 1,
+                y: 0,
+                width: 2,
+                height: 1,
+            }),
+            10,
+        );
+        assert!(!region.covers(&map, 0, 0));
+        assert!(region.covers(&map, 2, 0));
+    }
+
+    #[test]
+    fn test_roll_rate() {
+        let never = EncounterTable::new("never", EncounterArea::Tile(TileType::Grass), 0)
+            .with_opponent(2, 1);
+        let always = EncounterTable::new("always", EncounterArea::Tile(TileType::Grass), 100)
+            .with_opponent(2, 1);
+        let nobody = EncounterTable::new("nobody", EncounterArea::Tile(TileType::Grass), 100);
+
+        for tick in 0..100 {
+            let mut rng = tick_rng(42, tick, ENCOUNTER_STREAM);
+            assert_eq!(never.roll(&mut rng), None);
+            assert_eq!(always.roll(&mut rng), Some(2));
+            assert_eq!(nobody.roll(&mut rng), None);
+        }
+    }
+
+    #[test]
+    fn test_roll_weights() {
+        let table = EncounterTable::new("field", EncounterArea::Tile(TileType::Grass), 100)
+            .with_opponent(2, 3)
+            .with_opponent(3, 1)
+            .with_opponent(4, 0);
+
+        let rolls: Vec<i32> = (0..400)
+            .map(|tick| {
+                table
+                    .roll(&mut tick_rng(42, tick, ENCOUNTER_STREAM))
+                    .unwrap()
+            })
+            .collect();
+        let twos = rolls.iter().filter(|id| **id == 2).count();
+
+        assert!(!rolls.contains(&4));
+        assert!((250..350).contains(&twos), "{} of 400 were 2", twos);
+    }
+
+    #[test]
+    fn test_roll_encounter_is_reproducible() {
+        let mut map = test_map();
+        map.add_encounter_table(
+            EncounterTable::new("grass", EncounterArea::Tile(TileType::Grass), 30)
+                .with_opponent(2, 1)
+                .with_opponent(3, 1),
+        );
+
+        let walk = |seed: u64| -> Vec<Option<i32>> {
+            (0..50)
+                .map(|tick| roll_encounter(&map, 0, 0, seed, tick))
+                .collect()
+        };
+
+        assert_eq!(walk(42), walk(42));
+        assert_ne!(walk(42), walk(43));
+        assert!(walk(42).iter().any(|roll| roll.is_some()));
+
+        // Nothing lives on empty tiles
+        assert!((0..50).all(|tick| roll_encounter(&map, 2, 0, 42, tick).is_none()));
+    }
+}
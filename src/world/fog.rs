@@ -0,0 +1,272 @@
+//! Fog of war: the player only sees what's in their line of sight, and remembers what
+//! they've seen before
+use crate::world::map::Map;
+
+/// How far the player can see, in tiles
+pub const SIGHT_RADIUS: usize = 8;
+
+/// Which tiles of a map the player has ever seen
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explored {
+    width: usize,
+    height: usize,
+    tiles: Vec<bool>, // Row by row
+}
+
+impl Explored {
+    /// Nothing explored yet
+    pub fn new(width: usize, height: usize) -> Self {
+        Explored {
+            width,
+            height,
+            tiles: vec![false; width * height],
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.tiles[y * self.width + x]
+    }
+
+    pub fn explore(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = true;
+        }
+    }
+
+    /// The tiles as a bitset (row by row, lowest bit first), in hex, for storing in the save
+    pub fn to_hex(&self) -> String {
+        self.tiles
+            .chunks(8)
+            .map(|chunk| {
+                let byte = chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, explored)| byte | ((*explored as u8) << i));
+                format!("{:02x}", byte)
+            })
+            .collect()
+    }
+
+    /// The reverse of `to_hex`. `None` if the hex doesn't fit a map of this size.
+    pub fn from_hex(width: usize, height: usize, hex: &str) -> Option<Self> {
+        let count = width * height;
+        if !hex.is_ascii() || hex.len() != count.div_ceil(8) * 2 {
+            return None;
+        }
+
+        let mut tiles = Vec::with_capacity(count);
+        for i in (0..hex.len()).step_by(2) {
+            let byte = u8::from_str_radix(&hex[i..i + 2], 16).ok()?;
+            for bit in 0..8 {
+                tiles.push(byte & (1 << bit) != 0);
+            }
+        }
+        tiles.truncate(count);
+
+        Some(Explored {
+            width,
+            height,
+            tiles,
+        })
+    }
+}
+
+/// How a tile is drawn, given what the player can see and remember
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileVisibility {
+    Visible,
+    Remembered, // Seen before, but not in sight now. Drawn dimmed, without anyone on it.
+    Unseen,     // Drawn blank
+}
+
+/// What the player can see of a map right now, and what they've explored of it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fog {
+    visible: Vec<Vec<bool>>,
+    explored: Explored,
+}
+
+impl Fog {
+    pub fn new(explored: Explored) -> Self {
+        let (width, height) = explored.size();
+        Fog {
+            visible: vec![vec![false; width]; height],
+            explored,
+        }
+    }
+
+    pub fn explored(&self) -> &Explored {
+        &self.explored
+    }
+
+    /// Works out what the player can see from where they're standing, and remembers it
+    pub fn update(&mut self, map: &Map, player: (usize, usize)) {
+        self.visible = line_of_sight(map, player, SIGHT_RADIUS);
+        for (y, row) in self.visible.iter().enumerate() {
+            for (x, visible) in row.iter().enumerate() {
+                if *visible {
+                    self.explored.explore(x, y);
+                }
+            }
+        }
+    }
+
+    pub fn visibility(&self, x: usize, y: usize) -> TileVisibility {
+        if self.visible.get(y).and_then(|row| row.get(x)) == Some(&true) {
+            TileVisibility::Visible
+        } else if self.explored.is_explored(x, y) {
+            TileVisibility::Remembered
+        } else {
+            TileVisibility::Unseen
+        }
+    }
+}
+
+/// The tiles within `radius` of `origin` that can be seen from it. Indexed by [y][x].
+///
+/// A tile is visible if a straight line to it doesn't pass through anything that blocks
+/// sight. The tiles that do block sight (walls) are visible themselves, so rooms have edges.
+pub fn line_of_sight(map: &Map, origin: (usize, usize), radius: usize) -> Vec<Vec<bool>> {
+    let (width, height) = (map.get_width(), map.get_height());
+    let mut visible = vec![vec![false; width]; height];
+    if origin.0 >= width || origin.1 >= height {
+        return visible;
+    }
+
+    let min_x = origin.0.saturating_sub(radius);
+    let min_y = origin.1.saturating_sub(radius);
+    let max_x = (origin.0 + radius).min(width - 1);
+    let max_y = (origin.1 + radius).min(height - 1);
+
+    for (y, row) in visible.iter_mut().enumerate().take(max_y + 1).skip(min_y) {
+        for (x, cell) in row.iter_mut().enumerate().take(max_x + 1).skip(min_x) {
+            let dx = x.abs_diff(origin.0);
+            let dy = y.abs_diff(origin.1);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+
+            // Everything between the origin and the tile has to be see-through
+            let line = line_between(origin, (x, y));
+            *cell = line
+                .iter()
+                .skip(1)
+                .take(line.len().saturating_sub(2))
+                .all(|(x, y)| !map.get_tile(*x, *y).blocks_sight());
+        }
+    }
+
+    visible
+}
+
+/// The tiles on a straight line from `from` to `to`, both included (Bresenham's line)
+fn line_between(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (to_x, to_y) = (to.0 as isize, to.1 as isize);
+    let dx = (to_x - x).abs();
+    let dy = -(to_y - y).abs();
+    let step_x = if x < to_x { 1 } else { -1 };
+    let step_y = if y < to_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut line = vec![(x as usize, y as usize)];
+    while (x, y) != (to_x, to_y) {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        line.push((x as usize, y as usize));
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::map::TileType;
+
+    // A 9x5 field of grass, with a wall from (4, 0) down to (4, 3)
+    fn test_map() -> Map {
+        let mut map = Map::new(9, 5);
+        for y in 0..5 {
+            for x in 0..9 {
+                map.set_tile(x, y, TileType::Grass);
+            }
+        }
+        for y in 0..4 {
+            map.set_tile(4, y, TileType::Wall);
+        }
+        map
+    }
+
+    #[test]
+    fn test_line_between() {
+        assert_eq!(
+            line_between((0, 0), (3, 0)),
+            vec![(0, 0), (1, 0), (2, 0), (3, 0)]
+        );
+        assert_eq!(line_between((2, 2), (0, 0)), vec![(2, 2), (1, 1), (0, 0)]);
+        assert_eq!(line_between((1, 1), (1, 1)), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_walls_block_sight() {
+        let map = test_map();
+
+        let visible = line_of_sight(&map, (2, 1), 8);
+
+        assert!(visible[1][2]);
+        assert!(visible[1][4]); // The wall itself
+        assert!(!visible[1][6]); // Behind the wall
+        assert!(visible[4][4]); // Past the bottom of it
+    }
+
+    #[test]
+    fn test_sight_radius() {
+        let map = test_map();
+
+        let visible = line_of_sight(&map, (0, 4), 2);
+
+        assert!(visible[4][2]);
+        assert!(!visible[4][3]);
+        assert!(!visible[2][2]); // Diagonally, 2 and 2 is further than 2
+    }
+
+    #[test]
+    fn test_fog_remembers_what_was_seen() {
+        let map = test_map();
+        let mut fog = Fog::new(Explored::new(9, 5));
+
+        fog.update(&map, (0, 4));
+        assert_eq!(fog.visibility(0, 0), TileVisibility::Visible);
+        assert_eq!(fog.visibility(8, 0), TileVisibility::Unseen);
+
+        fog.update(&map, (8, 4));
+        assert_eq!(fog.visibility(0, 0), TileVisibility::Remembered);
+        assert_eq!(fog.visibility(8, 0), TileVisibility::Visible);
+    }
+
+    #[test]
+    fn test_explored_hex_round_trip() {
+        let mut explored = Explored::new(5, 3);
+        explored.explore(0, 0);
+        explored.explore(4, 2);
+
+        let hex = explored.to_hex();
+
+        assert_eq!(hex, "0140"); // Bits 0 and 14
+        assert_eq!(Explored::from_hex(5, 3, &hex), Some(explored));
+        assert_eq!(Explored::from_hex(6, 3, &hex), None);
+        assert_eq!(Explored::from_hex(5, 3, "zz40"), None);
+    }
+}
@@ -0,0 +1,397 @@
+//! Interactive tiles: doors, signs, chests, and bookshelves. What's written on a sign, kept in
+//! a chest, or needed to unlock a door comes from the map's `[contents]` section.
+//!
+//! Working out what inspecting a tile does is kept apart from doing it, so the rules can be
+//! tested without a database. Anything that can only happen once (taking what's in a chest,
+//! unlocking a door) is recorded under the content's id, like a trigger that fires once.
+use crate::world::map::{Map, TileType};
+
+/// Something on an interactive tile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContentKind {
+    Text(String),                      // What a sign says, or what's in a bookshelf's books
+    Item { item: String, count: u32 }, // Taken from a chest or bookshelf, once
+    Page(String),                      // Likewise, a loose page for the player's book
+    Key(String),                       // The item that unlocks a locked door
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileContent {
+    pub id: String, // Unique within its map, along with the map's trigger ids
+    pub x: usize,
+    pub y: usize,
+    pub kind: ContentKind,
+}
+
+impl TileContent {
+    pub fn new(id: &str, x: usize, y: usize, kind: ContentKind) -> Self {
+        TileContent {
+            id: id.to_string(),
+            x,
+            y,
+            kind,
+        }
+    }
+
+    /// Whether this is something the player can take
+    pub fn is_loot(&self) -> bool {
+        matches!(self.kind, ContentKind::Item { .. } | ContentKind::Page(_))
+    }
+}
+
+/// What happens when the player inspects a tile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Interaction {
+    Read(String),
+    OpenDoor,
+    CloseDoor,
+    Locked,
+    Unlock {
+        id: String, // Of the key content, to record the door as unlocked
+        key: String,
+    },
+    Take {
+        ids: Vec<String>, // Of the contents taken, so they're only taken once
+        items: Vec<(String, u32)>,
+        pages: Vec<String>,
+    },
+}
+
+impl Interaction {
+    /// What the player is told
+    pub fn message(&self) -> String {
+        match self {
+            Interaction::Read(text) => text.clone(),
+            Interaction::OpenDoor => "The door creaks open.".to_string(),
+            Interaction::CloseDoor => "You close the door.".to_string(),
+            Interaction::Locked => TileType::LockedDoor.bump_message(),
+            Interaction::Unlock { key, .. } => format!("You unlock the door with the {}.", key),
+            Interaction::Take { items, pages, .. } => {
+                let found: Vec<String> = items
+                    .iter()
+                    .map(|(item, count)| match count {
+                        1 => item.clone(),
+                        _ => format!("{} x {}", count, item),
+                    })
+                    .chain(pages.iter().map(|page| format!("a page: {}", page)))
+                    .collect();
+                if found.is_empty() {
+                    "There's nothing inside.".to_string()
+                } else {
+                    format!("You find {}.", found.join(", "))
+                }
+            }
+        }
+    }
+
+    /// The tile that the inspected one turns into, if it changes
+    pub fn changes_tile(&self, tile: TileType) -> Option<TileType> {
+        match (self, tile) {
+            (Interaction::OpenDoor, _) => Some(TileType::OpenDoor),
+            (Interaction::CloseDoor, _) => Some(TileType::Door),
+            (Interaction::Unlock { .. }, _) => Some(TileType::OpenDoor),
+            (Interaction::Take { .. }, TileType::Chest) => Some(TileType::OpenChest),
+            _ => None,
+        }
+    }
+
+    /// The content ids to record, so this doesn't happen again
+    pub fn used_ids(&self) -> Vec<String> {
+        match self {
+            Interaction::Unlock { id, .. } => vec![id.clone()],
+            Interaction::Take { ids, .. } => ids.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Works out what inspecting the tile at (x, y) does, or `None` if it isn't interactive.
+///
+/// `has_item` tells whether the player is carrying an item, and `is_used` whether a content id
+/// has already been recorded for them.
+pub fn inspect(
+    map: &Map,
+    x: usize,
+    y: usize,
+    has_item: impl Fn(&str) -> bool,
+    is_used: impl Fn(&str) -> bool,
+) -> Option<Interaction> {
+    let text = map
+        .contents_at(x, y)
+        .find_map(|content| match &content.kind {
+            ContentKind::Text(text) => Some(text.clone()),
+            _ => None,
+        });
+
+    match map.get_tile(x, y) {
+        TileType::Door => Some(Interaction::OpenDoor),
+        TileType::OpenDoor => Some(Interaction::CloseDoor),
+        TileType::LockedDoor => {
+            let key = map
+                .contents_at(x, y)
+                .find_map(|content| match &content.kind {
+                    ContentKind::Key(key) if has_item(key) => Some((content.id.clone(), key)),
+                    _ => None,
+                });
+            Some(match key {
+                Some((id, key)) => Interaction::Unlock {
+                    id,
+                    key: key.clone(),
+                },
+                None => Interaction::Locked,
+            })
+        }
+        TileType::Sign => Some(Interaction::Read(
+            text.unwrap_or_else(|| "The sign is blank.".to_string()),
+        )),
+        TileType::Chest => Some(take(map, x, y, is_used)),
+        TileType::OpenChest => Some(Interaction::Read("The chest is empty.".to_string())),
+        TileType::Bookshelf => match take(map, x, y, is_used) {
+            Interaction::Take { ids, .. } if ids.is_empty() => {
+                Some(Interaction::Read(text.unwrap_or_else(|| {
+                    "Nothing but dusty old books.".to_string()
+                })))
+            }
+            taken => Some(taken),
+        },
+        _ => None,
+    }
+}
+
+/// Everything on the tile that hasn't been taken yet
+fn take(map: &Map, x: usize, y: usize, is_used: impl Fn(&str) -> bool) -> Interaction {
+    let mut ids = Vec::new();
+    let mut items = Vec::new();
+    let mut pages = Vec::new();
+    for content in map.contents_at(x, y) {
+        if !content.is_loot() || is_used(&content.id) {
+            continue;
+        }
+        match &content.kind {
+            ContentKind::Item { item, count } => items.push((item.clone(), *count)),
+            ContentKind::Page(page) => pages.push(page.clone()),
+            _ => unreachable!("Only loot is taken"),
+        }
+        ids.push(content.id.clone());
+    }
+
+    Interaction::Take { ids, items, pages }
+}
+
+/// Puts the map back the way the player left it: chests they've emptied are open, doors
+/// they've unlocked are unlocked, and doors they left open (at `opened_doors`) are open.
+/// Called whenever a map is loaded.
+pub fn restore(map: &mut Map, is_used: impl Fn(&str) -> bool, opened_doors: &[(usize, usize)]) {
+    let mut changes = Vec::new();
+    for content in map.get_contents() {
+        let (x, y) = (content.x, content.y);
+        let change = match (map.get_tile(x, y), &content.kind) {
+            (TileType::LockedDoor, ContentKind::Key(_)) if is_used(&content.id) => {
+                Some(TileType::Door)
+            }
+            (TileType::Chest, _)
+                if map
+                    .contents_at(x, y)
+                    .filter(|content| content.is_loot())
+                    .all(|content| is_used(&content.id)) =>
+            {
+                Some(TileType::OpenChest)
+            }
+            _ => None,
+        };
+        if let Some(tile) = change {
+            changes.push((x, y, tile));
+        }
+    }
+
+    for (x, y, tile) in changes {
+        map.replace_tile(x, y, tile);
+    }
+
+    // Once they're unlocked, locked doors can be open too
+    for &(x, y) in opened_doors {
+        if map.get_tile(x, y) == TileType::Door {
+            map.replace_tile(x, y, TileType::OpenDoor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A row of interactive tiles: door, locked door, sign, chest, bookshelf
+    fn test_map() -> Map {
+        let mut map = Map::new(5, 1);
+        let tiles = [
+            TileType::Door,
+            TileType::LockedDoor,
+            TileType::Sign,
+            TileType::Chest,
+            TileType::Bookshelf,
+        ];
+        for (x, tile) in tiles.into_iter().enumerate() {
+            map.set_tile(x, 0, tile);
+        }
+        map.add_content(TileContent::new(
+            "cellar",
+            1,
+            0,
+            ContentKind::Key("iron key".to_string()),
+        ));
+        map.add_content(TileContent::new(
+            "welcome",
+            2,
+            0,
+            ContentKind::Text("Welcome!".to_string()),
+        ));
+        map.add_content(TileContent::new(
+            "gold",
+            3,
+            0,
+            ContentKind::Item {
+                item: "gold coin".to_string(),
+                count: 3,
+            },
+        ));
+        map.add_content(TileContent::new(
+            "torn_page",
+            3,
+            0,
+            ContentKind::Page("A torn page".to_string()),
+        ));
+        map.add_content(TileContent::new(
+            "history",
+            4,
+            0,
+            ContentKind::Text("Tales of old.".to_string()),
+        ));
+        map.add_content(TileContent::new(
+            "lost_page",
+            4,
+            0,
+            ContentKind::Page("A lost page".to_string()),
+        ));
+        map
+    }
+
+    fn inspect_with(map: &Map, x: usize, items: &[&str], used: &[&str]) -> Option<Interaction> {
+        inspect(
+            map,
+            x,
+            0,
+            |item| items.contains(&item),
+            |id| used.contains(&id),
+        )
+    }
+
+    #[test]
+    fn test_doors() {
+        let mut map = test_map();
+
+        assert_eq!(inspect_with(&map, 0, &[], &[]), Some(Interaction::OpenDoor));
+        map.replace_tile(0, 0, TileType::OpenDoor);
+        assert_eq!(
+            inspect_with(&map, 0, &[], &[]),
+            Some(Interaction::CloseDoor)
+        );
+
+        assert_eq!(inspect_with(&map, 1, &[], &[]), Some(Interaction::Locked));
+        let unlock = inspect_with(&map, 1, &["iron key"], &[]).unwrap();
+        assert_eq!(
+            unlock,
+            Interaction::Unlock {
+                id: "cellar".to_string(),
+                key: "iron key".to_string()
+            }
+        );
+        assert_eq!(
+            unlock.changes_tile(TileType::LockedDoor),
+            Some(TileType::OpenDoor)
+        );
+        assert_eq!(unlock.used_ids(), vec!["cellar"]);
+    }
+
+    #[test]
+    fn test_signs() {
+        let mut map = test_map();
+
+        let read = inspect_with(&map, 2, &[], &[]).unwrap();
+        assert_eq!(read.message(), "Welcome!");
+        assert_eq!(read.changes_tile(TileType::Sign), None);
+
+        map.get_contents_mut().clear();
+        assert_eq!(
+            inspect_with(&map, 2, &[], &[]).unwrap().message(),
+            "The sign is blank."
+        );
+    }
+
+    #[test]
+    fn test_chests_are_only_emptied_once() {
+        let map = test_map();
+
+        let take = inspect_with(&map, 3, &[], &[]).unwrap();
+        assert_eq!(
+            take.message(),
+            "You find 3 x gold coin, a page: A torn page."
+        );
+        assert_eq!(take.used_ids(), vec!["gold", "torn_page"]);
+        assert_eq!(
+            take.changes_tile(TileType::Chest),
+            Some(TileType::OpenChest)
+        );
+
+        // Only what's left
+        let take = inspect_with(&map, 3, &[], &["gold"]).unwrap();
+        assert_eq!(take.used_ids(), vec!["torn_page"]);
+
+        let take = inspect_with(&map, 3, &[], &["gold", "torn_page"]).unwrap();
+        assert_eq!(take.message(), "There's nothing inside.");
+    }
+
+    #[test]
+    fn test_bookshelves() {
+        let map = test_map();
+
+        let take = inspect_with(&map, 4, &[], &[]).unwrap();
+        assert_eq!(take.message(), "You find a page: A lost page.");
+        assert_eq!(take.changes_tile(TileType::Bookshelf), None);
+
+        let read = inspect_with(&map, 4, &[], &["lost_page"]).unwrap();
+        assert_eq!(read, Interaction::Read("Tales of old.".to_string()));
+    }
+
+    #[test]
+    fn test_other_tiles_arent_interactive() {
+        let mut map = test_map();
+        map.set_tile(0, 0, TileType::Grass);
+
+        assert_eq!(inspect_with(&map, 0, &[], &[]), None);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut map = test_map();
+
+        restore(&mut map, |id| ["cellar", "gold"].contains(&id), &[]);
+        assert_eq!(map.get_tile(1, 0), TileType::Door);
+        assert_eq!(map.get_tile(3, 0), TileType::Chest); // The page is still in it
+
+        restore(&mut map, |id| ["gold", "torn_page"].contains(&id), &[]);
+        assert_eq!(map.get_tile(3, 0), TileType::OpenChest);
+    }
+
+    #[test]
+    fn test_restore_opened_doors() {
+        let mut map = test_map();
+        restore(&mut map, |_| false, &[(0, 0), (1, 0)]);
+        assert_eq!(map.get_tile(0, 0), TileType::OpenDoor);
+        // Still locked, so it can't have been left open
+        assert_eq!(map.get_tile(1, 0), TileType::LockedDoor);
+
+        let mut map = test_map();
+        restore(&mut map, |id| id == "cellar", &[(1, 0)]);
+        assert_eq!(map.get_tile(1, 0), TileType::OpenDoor);
+    }
+}
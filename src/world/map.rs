@@ -1,6 +1,12 @@
+use crate::world::encounter::EncounterTable;
+use crate::world::interaction::TileContent;
 use crate::world::navigation::Direction;
+use crate::world::npc::NpcPlacement;
+use crate::world::trigger::{Trigger, TriggerActivation};
+use std::collections::BTreeMap;
+use std::fmt;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TileType {
     Player,      // 0 - The player's body position
     PlayerFocus, // 1 - The player's "focus" position
@@ -8,6 +14,126 @@ pub enum TileType {
     Wall,        // 3 - Wall or obstacle
     Water,       // 4 - Water (if applicable)
     Grass,       // 5 - Grass or floor
+    Door,        // 6 - A closed door. Inspect it to open it.
+    OpenDoor,    // 7
+    LockedDoor,  // 8 - Opens with the key named in the map's [contents]
+    Bridge,      // 9 - Lets anyone cross water
+    Sign,        // 10 - Shows its text when inspected
+    Chest,       // 11 - Holds items or pages, which can be taken once
+    OpenChest,   // 12
+    Bookshelf,   // 13 - Shows lore, and might have a page or two to take
+}
+
+/// Something the player can learn that lets them cross otherwise impassable tiles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ability {
+    Swim,
+}
+
+impl fmt::Display for Ability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ability_str = match self {
+            Ability::Swim => "swim",
+        };
+        write!(f, "{}", ability_str)
+    }
+}
+
+/// Whether a tile can be walked on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Passability {
+    Walkable,
+    Blocked,
+    RequiresAbility(Ability),
+}
+
+impl TileType {
+    pub const ALL: [TileType; 14] = [
+        TileType::Player,
+        TileType::PlayerFocus,
+        TileType::Empty,
+        TileType::Wall,
+        TileType::Water,
+        TileType::Grass,
+        TileType::Door,
+        TileType::OpenDoor,
+        TileType::LockedDoor,
+        TileType::Bridge,
+        TileType::Sign,
+        TileType::Chest,
+        TileType::OpenChest,
+        TileType::Bookshelf,
+    ];
+
+    /// The tile's name in map files, e.g. in the legend
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileType::Player => "player",
+            TileType::PlayerFocus => "player_focus",
+            TileType::Empty => "empty",
+            TileType::Wall => "wall",
+            TileType::Water => "water",
+            TileType::Grass => "grass",
+            TileType::Door => "door",
+            TileType::OpenDoor => "open_door",
+            TileType::LockedDoor => "locked_door",
+            TileType::Bridge => "bridge",
+            TileType::Sign => "sign",
+            TileType::Chest => "chest",
+            TileType::OpenChest => "open_chest",
+            TileType::Bookshelf => "bookshelf",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TileType> {
+        TileType::ALL.into_iter().find(|tile| tile.name() == name)
+    }
+
+    pub fn passability(&self) -> Passability {
+        match self {
+            TileType::Player | TileType::PlayerFocus => Passability::Walkable,
+            TileType::Empty => Passability::Walkable,
+            TileType::Wall => Passability::Blocked,
+            TileType::Water => Passability::RequiresAbility(Ability::Swim),
+            TileType::Grass | TileType::OpenDoor | TileType::Bridge => Passability::Walkable,
+            TileType::Door | TileType::LockedDoor => Passability::Blocked,
+            TileType::Sign | TileType::Chest | TileType::OpenChest | TileType::Bookshelf => {
+                Passability::Blocked
+            }
+        }
+    }
+
+    /// Whether the tile can be walked on by someone with the given abilities
+    pub fn is_passable(&self, abilities: &[Ability]) -> bool {
+        match self.passability() {
+            Passability::Walkable => true,
+            Passability::Blocked => false,
+            Passability::RequiresAbility(ability) => abilities.contains(&ability),
+        }
+    }
+
+    /// Whether the player can see past this tile
+    pub fn blocks_sight(&self) -> bool {
+        matches!(
+            self,
+            TileType::Wall | TileType::Door | TileType::LockedDoor | TileType::Bookshelf
+        )
+    }
+
+    /// What the player is told when they walk into the tile, and can't pass
+    pub fn bump_message(&self) -> String {
+        match self.passability() {
+            Passability::RequiresAbility(ability) => {
+                format!("You need to be able to {} to go that way.", ability)
+            }
+            _ => match self {
+                TileType::Wall => "You bump into a wall.".to_string(),
+                TileType::Door => "The door is closed.".to_string(),
+                TileType::LockedDoor => "The door is locked.".to_string(),
+                _ => "You can't go that way.".to_string(),
+            },
+        }
+    }
 }
 
 pub fn tile_to_char(tile: TileType, player_direction: Option<Direction>) -> char {
@@ -24,6 +150,14 @@ pub fn tile_to_char(tile: TileType, player_direction: Option<Direction>) -> char
         TileType::Wall => '#',  // Wall
         TileType::Water => '~', // Water
         TileType::Grass => '.', // Grass or floor
+        TileType::Door => '+',
+        TileType::OpenDoor => '\'',
+        TileType::LockedDoor => '╫',
+        TileType::Bridge => '═',
+        TileType::Sign => '¶',
+        TileType::Chest => '■',
+        TileType::OpenChest => '□',
+        TileType::Bookshelf => '≡',
     }
 }
 
@@ -36,29 +170,68 @@ impl From<u8> for TileType {
             3 => TileType::Wall,
             4 => TileType::Water,
             5 => TileType::Grass,
+            6 => TileType::Door,
+            7 => TileType::OpenDoor,
+            8 => TileType::LockedDoor,
+            9 => TileType::Bridge,
+            10 => TileType::Sign,
+            11 => TileType::Chest,
+            12 => TileType::OpenChest,
+            13 => TileType::Bookshelf,
             _ => TileType::Empty, // Default fallback
         }
     }
 }
 
+/// The layers of a map, from the bottom up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Ground,  // What's underfoot. Every tile of the map has one.
+    Objects, // Things standing on the ground, like walls or rocks. Block movement like any tile.
+    Overlay, // Drawn over the player, like treetops or roofs. Never blocks movement.
+}
+
+impl Layer {
+    pub const ALL: [Layer; 3] = [Layer::Ground, Layer::Objects, Layer::Overlay];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layer::Ground => "ground",
+            Layer::Objects => "objects",
+            Layer::Overlay => "overlay",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Layer> {
+        Layer::ALL.into_iter().find(|layer| layer.name() == name)
+    }
+}
+
 /// A map is a 2d grid
+#[derive(Clone, Debug, PartialEq)]
 pub struct Map {
+    name: String,
+    metadata: BTreeMap<String, String>, // Anything else from the map file's [map] section
     width: usize,
     height: usize,
-    tiles: Vec<Vec<TileType>>, // 2D grid of tiles
+    tiles: Vec<Vec<TileType>>, // 2D grid of tiles (the ground layer)
+    objects: Vec<Vec<Option<TileType>>>, // Same size as `tiles`
+    overlay: Vec<Vec<Option<TileType>>>, // Same size as `tiles`
+    spawns: BTreeMap<String, (usize, usize)>,
+    triggers: Vec<Trigger>,
+    npcs: Vec<NpcPlacement>,
+    encounter_tables: Vec<EncounterTable>,
+    contents: Vec<TileContent>, // What's on signs, in chests, and so on
 }
 
+const UNNAMED_MAP: &str = "unnamed";
+
 impl Map {
     // Creates empty map
     // TODO: Remove this? Sort of useless. Or keep and raise error to
     //       raise usage to developer.
     pub fn new(width: usize, height: usize) -> Self {
-        let tiles = vec![vec![TileType::Empty; width]; height];
-        Map {
-            width,
-            height,
-            tiles,
-        }
+        Map::new_with_tiles(vec![vec![TileType::Empty; width]; height])
     }
 
     // Creates a map with existing tile data
@@ -66,12 +239,129 @@ impl Map {
         let height = tiles.len();
         let width = if height > 0 { tiles[0].len() } else { 0 };
         Map {
+            name: UNNAMED_MAP.to_string(),
+            metadata: BTreeMap::new(),
             width,
             height,
             tiles,
+            objects: vec![vec![None; width]; height],
+            overlay: vec![vec![None; width]; height],
+            spawns: BTreeMap::new(),
+            triggers: Vec::new(),
+            npcs: Vec::new(),
+            encounter_tables: Vec::new(),
+            contents: Vec::new(),
         }
     }
 
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        self.metadata.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn add_spawn(&mut self, name: &str, x: usize, y: usize) {
+        self.spawns.insert(name.to_string(), (x, y));
+    }
+
+    pub fn get_spawn(&self, name: &str) -> Option<(usize, usize)> {
+        self.spawns.get(name).copied()
+    }
+
+    pub fn get_spawns(&self) -> &BTreeMap<String, (usize, usize)> {
+        &self.spawns
+    }
+
+    pub fn remove_spawn(&mut self, name: &str) {
+        self.spawns.remove(name);
+    }
+
+    pub fn add_npc(&mut self, npc: NpcPlacement) {
+        self.npcs.push(npc);
+    }
+
+    pub fn get_npcs(&self) -> &Vec<NpcPlacement> {
+        &self.npcs
+    }
+
+    pub fn get_npcs_mut(&mut self) -> &mut Vec<NpcPlacement> {
+        &mut self.npcs
+    }
+
+    /// The NPC standing at (x, y), if any
+    pub fn npc_at(&self, x: usize, y: usize) -> Option<&NpcPlacement> {
+        self.npcs.iter().find(|npc| npc.x == x && npc.y == y)
+    }
+
+    pub fn add_trigger(&mut self, trigger: Trigger) {
+        self.triggers.push(trigger);
+    }
+
+    pub fn get_triggers(&self) -> &Vec<Trigger> {
+        &self.triggers
+    }
+
+    pub fn get_triggers_mut(&mut self) -> &mut Vec<Trigger> {
+        &mut self.triggers
+    }
+
+    /// The triggers set off by activating the tile at (x, y) in the given way
+    pub fn triggers_at(
+        &self,
+        x: usize,
+        y: usize,
+        activation: TriggerActivation,
+    ) -> impl Iterator<Item = &Trigger> {
+        self.triggers
+            .iter()
+            .filter(move |trigger| trigger.is_activated_by(x, y, activation))
+    }
+
+    pub fn add_encounter_table(&mut self, table: EncounterTable) {
+        self.encounter_tables.push(table);
+    }
+
+    pub fn get_encounter_tables(&self) -> &Vec<EncounterTable> {
+        &self.encounter_tables
+    }
+
+    /// The encounter table for the tile at (x, y), i.e. the first one listed that covers it
+    pub fn encounter_table_at(&self, x: usize, y: usize) -> Option<&EncounterTable> {
+        self.encounter_tables
+            .iter()
+            .find(|table| table.covers(self, x, y))
+    }
+
+    pub fn add_content(&mut self, content: TileContent) {
+        self.contents.push(content);
+    }
+
+    pub fn get_contents(&self) -> &Vec<TileContent> {
+        &self.contents
+    }
+
+    pub fn get_contents_mut(&mut self) -> &mut Vec<TileContent> {
+        &mut self.contents
+    }
+
+    /// Everything the map's `[contents]` puts on the tile at (x, y), in order
+    pub fn contents_at(&self, x: usize, y: usize) -> impl Iterator<Item = &TileContent> {
+        self.contents
+            .iter()
+            .filter(move |content| content.x == x && content.y == y)
+    }
+
     pub fn get_width(&self) -> usize {
         self.width
     }
@@ -91,29 +381,132 @@ impl Map {
         }
     }
 
+    /// Sets (or, with `None`, clears) a tile on one of the layers above the ground
+    ///
+    /// Clearing the ground isn't possible, so `None` on `Layer::Ground` sets it to `Empty`.
+    pub fn set_layer_tile(&mut self, layer: Layer, x: usize, y: usize, tile: Option<TileType>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        match layer {
+            Layer::Ground => self.tiles[y][x] = tile.unwrap_or(TileType::Empty),
+            Layer::Objects => self.objects[y][x] = tile,
+            Layer::Overlay => self.overlay[y][x] = tile,
+        }
+    }
+
+    /// Swaps the tile the player sees at (x, y) for another, e.g. a door for an open one. This
+    /// is the object there if there is one, otherwise the ground.
+    pub fn replace_tile(&mut self, x: usize, y: usize, tile: TileType) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        match self.objects[y][x] {
+            Some(_) => self.objects[y][x] = Some(tile),
+            None => self.tiles[y][x] = tile,
+        }
+    }
+
+    pub fn get_layer_tile(&self, layer: Layer, x: usize, y: usize) -> Option<TileType> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        match layer {
+            Layer::Ground => Some(self.tiles[y][x]),
+            Layer::Objects => self.objects[y][x],
+            Layer::Overlay => self.overlay[y][x],
+        }
+    }
+
+    /// Whether anything has been placed on the layer
+    pub fn has_layer(&self, layer: Layer) -> bool {
+        match layer {
+            Layer::Ground => true,
+            Layer::Objects => self.objects.iter().flatten().any(Option::is_some),
+            Layer::Overlay => self.overlay.iter().flatten().any(Option::is_some),
+        }
+    }
+
+    pub fn in_bounds(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    /// Finds the passable tile closest to (x, y), e.g. to place someone who is stuck in a wall.
+    /// Ties are broken top to bottom, then left to right.
+    pub fn nearest_passable(
+        &self,
+        x: usize,
+        y: usize,
+        abilities: &[Ability],
+    ) -> Option<(usize, usize)> {
+        let mut nearest: Option<(usize, (usize, usize))> = None;
+        for tile_y in 0..self.height {
+            for tile_x in 0..self.width {
+                if !self.get_tile(tile_x, tile_y).is_passable(abilities) {
+                    continue;
+                }
+                let distance = x.abs_diff(tile_x) + y.abs_diff(tile_y);
+                if nearest.is_none_or(|(nearest_distance, _)| distance < nearest_distance) {
+                    nearest = Some((distance, (tile_x, tile_y)));
+                }
+            }
+        }
+        nearest.map(|(_, position)| position)
+    }
+
     // Function to get a tile at a given position
+    //
+    // This is the tile the player stands on, or bumps into: the object there, otherwise the ground.
     pub fn get_tile(&self, x: usize, y: usize) -> TileType {
         if x < self.width && y < self.height {
-            self.tiles[y][x]
+            self.objects[y][x].unwrap_or(self.tiles[y][x])
         } else {
             TileType::Empty // Return Empty if out of bounds
         }
     }
 
+    /// How the tile at (x, y) is drawn, with the player at (player_x, player_y).
+    ///
+    /// The overlay is drawn over everything, then the player, NPCs, the player's focus,
+    /// and finally the tile itself.
+    pub fn char_at(
+        &self,
+        x: usize,
+        y: usize,
+        player_x: usize,
+        player_y: usize,
+        player_direction: Direction,
+    ) -> char {
+        let tile = if let Some(overlay) = self.overlay[y][x] {
+            overlay
+        } else if x == player_x && y == player_y {
+            TileType::Player
+        } else if let Some(npc) = self.npc_at(x, y) {
+            return npc.sprite;
+        } else if x as isize == player_x as isize + player_direction.dx()
+            && y as isize == player_y as isize + player_direction.dy()
+        {
+            TileType::PlayerFocus
+        } else {
+            self.get_tile(x, y)
+        };
+
+        tile_to_char(tile, Some(player_direction))
+    }
+
+    /// How the tile at (x, y) is drawn from memory: the terrain, without anyone on it
+    pub fn terrain_char_at(&self, x: usize, y: usize) -> char {
+        let tile = self.overlay[y][x].unwrap_or_else(|| self.get_tile(x, y));
+        tile_to_char(tile, None)
+    }
+
     pub fn print(&self, player_x: usize, player_y: usize, player_direction: Direction) {
-        for (y, row) in self.tiles.iter().enumerate() {
-            for (x, &tile) in row.iter().enumerate() {
-                let tile = if x == player_x && y == player_y {
-                    TileType::Player
-                } else if x as isize == player_x as isize + player_direction.dx()
-                    && y as isize == player_y as isize + player_direction.dy()
-                {
-                    TileType::PlayerFocus
-                } else {
-                    tile
-                };
-
-                print!("{}", tile_to_char(tile, Some(player_direction)));
+        for y in 0..self.height {
+            for x in 0..self.width {
+                print!(
+                    "{}",
+                    self.char_at(x, y, player_x, player_y, player_direction)
+                );
             }
             println!(); // New line after each row
         }
@@ -122,11 +515,170 @@ impl Map {
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::world::trigger::{TriggerArea, TriggerEvent, TriggerFrequency};
 
     #[test]
     fn test_create_map() {}
 
+    #[test]
+    fn test_tile_passability() {
+        assert_eq!(TileType::Grass.passability(), Passability::Walkable);
+        assert_eq!(TileType::Empty.passability(), Passability::Walkable);
+        assert_eq!(TileType::Wall.passability(), Passability::Blocked);
+        assert_eq!(
+            TileType::Water.passability(),
+            Passability::RequiresAbility(Ability::Swim)
+        );
+    }
+
+    #[test]
+    fn test_is_passable_with_abilities() {
+        assert!(TileType::Grass.is_passable(&[]));
+        assert!(!TileType::Wall.is_passable(&[Ability::Swim]));
+        assert!(!TileType::Water.is_passable(&[]));
+        assert!(TileType::Water.is_passable(&[Ability::Swim]));
+    }
+
+    #[test]
+    fn test_nearest_passable() {
+        let mut map = Map::new(3, 3);
+        for x in 0..3 {
+            map.set_tile(x, 0, TileType::Wall);
+            map.set_tile(0, x, TileType::Wall);
+        }
+
+        assert_eq!(map.nearest_passable(0, 0, &[]), Some((1, 1)));
+        assert_eq!(map.nearest_passable(2, 2, &[]), Some((2, 2)));
+        assert_eq!(Map::new(0, 0).nearest_passable(0, 0, &[]), None);
+    }
+
+    #[test]
+    fn test_triggers_at() {
+        let mut map = Map::new(5, 5).with_name("test");
+        map.add_trigger(Trigger::new(
+            "entrance",
+            TriggerArea {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 2,
+            },
+            TriggerActivation::Enter,
+            TriggerFrequency::Repeat,
+            TriggerEvent::OpenBookBuilder,
+        ));
+        map.add_trigger(Trigger::new(
+            "sign",
+            TriggerArea::tile(1, 1),
+            TriggerActivation::Inspect,
+            TriggerFrequency::Once,
+            TriggerEvent::StartDialogue {
+                root_name: "first_quest".to_string(),
+            },
+        ));
+
+        let entered: Vec<&str> = map
+            .triggers_at(2, 2, TriggerActivation::Enter)
+            .map(|trigger| trigger.id.as_str())
+            .collect();
+        let inspected: Vec<&str> = map
+            .triggers_at(1, 1, TriggerActivation::Inspect)
+            .map(|trigger| trigger.id.as_str())
+            .collect();
+
+        assert_eq!(map.get_name(), "test");
+        assert_eq!(entered, vec!["entrance"]);
+        assert_eq!(inspected, vec!["sign"]);
+        assert_eq!(map.triggers_at(3, 3, TriggerActivation::Enter).count(), 0);
+    }
+
+    #[test]
+    fn test_objects_layer_blocks_ground() {
+        let mut map = Map::new(2, 1);
+        map.set_tile(0, 0, TileType::Grass);
+        map.set_layer_tile(Layer::Objects, 0, 0, Some(TileType::Wall));
+        map.set_layer_tile(Layer::Overlay, 1, 0, Some(TileType::Wall));
+
+        assert_eq!(map.get_tile(0, 0), TileType::Wall);
+        // The overlay never affects what the player walks on
+        assert_eq!(map.get_tile(1, 0), TileType::Empty);
+        assert!(map.has_layer(Layer::Objects));
+
+        map.set_layer_tile(Layer::Objects, 0, 0, None);
+
+        assert_eq!(map.get_tile(0, 0), TileType::Grass);
+        assert!(!map.has_layer(Layer::Objects));
+    }
+
+    #[test]
+    fn test_interactive_tiles() {
+        assert!(TileType::OpenDoor.is_passable(&[]));
+        assert!(TileType::Bridge.is_passable(&[]));
+        for tile in [
+            TileType::Door,
+            TileType::LockedDoor,
+            TileType::Sign,
+            TileType::Chest,
+            TileType::Bookshelf,
+        ] {
+            assert!(!tile.is_passable(&[Ability::Swim]));
+        }
+        assert!(TileType::Door.blocks_sight());
+        assert!(!TileType::OpenDoor.blocks_sight());
+        assert_eq!(TileType::LockedDoor.bump_message(), "The door is locked.");
+    }
+
+    #[test]
+    fn test_replace_tile() {
+        let mut map = Map::new(2, 1);
+        map.set_tile(0, 0, TileType::Door);
+        map.set_tile(1, 0, TileType::Grass);
+        map.set_layer_tile(Layer::Objects, 1, 0, Some(TileType::Chest));
+
+        map.replace_tile(0, 0, TileType::OpenDoor);
+        map.replace_tile(1, 0, TileType::OpenChest);
+
+        assert_eq!(map.get_tile(0, 0), TileType::OpenDoor);
+        // The chest was an object, so the ground under it is left alone
+        assert_eq!(
+            map.get_layer_tile(Layer::Objects, 1, 0),
+            Some(TileType::OpenChest)
+        );
+        assert_eq!(
+            map.get_layer_tile(Layer::Ground, 1, 0),
+            Some(TileType::Grass)
+        );
+    }
+
+    #[test]
+    fn test_tile_names() {
+        for tile in TileType::ALL {
+            assert_eq!(TileType::from_name(tile.name()), Some(tile));
+        }
+        assert_eq!(TileType::from_name("lava"), None);
+    }
+
+    #[test]
+    fn test_npc_at() {
+        let mut map = Map::new(3, 3);
+        map.add_npc(NpcPlacement::new("narrator", 1, 2, 1, Direction::Left));
+
+        assert_eq!(map.npc_at(2, 1).unwrap().id, "narrator");
+        assert!(map.npc_at(1, 1).is_none());
+    }
+
+    #[test]
+    fn test_in_bounds() {
+        let map = Map::new(3, 2);
+
+        assert!(map.in_bounds(0, 0));
+        assert!(map.in_bounds(2, 1));
+        assert!(!map.in_bounds(-1, 0));
+        assert!(!map.in_bounds(3, 0));
+        assert!(!map.in_bounds(0, 2));
+    }
+
     // TODO: Add tests! For everything!
     // - Tiles
     //  - tile_to_char
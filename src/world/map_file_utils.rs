@@ -1,44 +1,55 @@
 use crate::world::map::{Map, TileType};
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
-
-// Load a Map from a .map (CSV-style) file
-pub fn load_map_from_file(file_path: &str) -> Map {
-    let file = File::open(file_path).expect("Failed to open map file");
-    let reader = BufReader::new(file);
-
-    let mut tiles = Vec::new();
-
-    for line in reader.lines() {
-        let line = line.expect("Failed to read line");
-        let row: Vec<TileType> = line
-            .split(',')
-            .map(|num| match num.parse::<u8>() {
-                Ok(n) => TileType::from(n),
-                Err(_) => TileType::Empty, // Default fallback
-            })
-            .collect();
-
-        tiles.push(row);
-    }
+use crate::world::map_format::{self, MapParseError};
+use crate::world::trigger::{
+    Trigger, TriggerActivation, TriggerArea, TriggerEvent, TriggerFrequency,
+};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub const DEMO_MAP_NAME: &str = "demo";
+
+#[derive(Debug)]
+pub enum MapFileError {
+    Io(io::Error),
+    Parse(PathBuf, MapParseError),
+}
 
-    Map::new_with_tiles(tiles)
+impl fmt::Display for MapFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapFileError::Io(e) => write!(f, "Failed to read map file: {}", e),
+            MapFileError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
 }
 
-// Save a Map to a .map (CSV-style) file
-pub fn save_map_to_file(map: &Map, file_path: &str) {
-    let file = File::create(file_path).expect("Failed to create map file");
-    let mut writer = BufWriter::new(file);
-
-    for row in map.get_tiles_ref() {
-        let line: Vec<String> = row
-            .iter()
-            .map(|tile| (tile.clone() as u8).to_string())
-            .collect();
-        writeln!(writer, "{}", line.join(",")).expect("Failed to write to file");
+impl std::error::Error for MapFileError {}
+
+impl From<io::Error> for MapFileError {
+    fn from(e: io::Error) -> Self {
+        MapFileError::Io(e)
     }
 }
 
+// Load a Map from a map file, either the sectioned format or an older CSV-style .map file
+pub fn load_map_from_file(file_path: &str) -> Result<Map, MapFileError> {
+    let source = fs::read_to_string(file_path)?;
+    map_format::parse_map_source(&source)
+        .map_err(|e| MapFileError::Parse(PathBuf::from(file_path), e))
+}
+
+// Save a Map to a .map (CSV-style) file. Only keeps the ground layer.
+pub fn save_map_to_file(map: &Map, file_path: &str) -> io::Result<()> {
+    fs::write(file_path, map_format::write_csv_map(map))
+}
+
+// Save a Map, with all of its layers, spawns, triggers, etc., in the sectioned format
+pub fn save_map(map: &Map, file_path: &str) -> io::Result<()> {
+    fs::write(file_path, map_format::write_map(map))
+}
+
 pub fn generate_small_demo_map() -> Map {
     let mut map = Map::new(10, 10);
 
@@ -94,18 +105,60 @@ pub fn generate_demo_map(width: usize, height: usize) -> Map {
         tiles[path_start_y + 3][x] = TileType::Wall;
     }
 
-    Map::new_with_tiles(tiles)
+    let mut map = Map::new_with_tiles(tiles).with_name(DEMO_MAP_NAME);
+
+    // Just inside the top left corner. Leads the player into the book tutorial.
+    map.add_trigger(Trigger::new(
+        "book_tutorial",
+        TriggerArea::tile(2, 3),
+        TriggerActivation::Enter,
+        TriggerFrequency::Once,
+        TriggerEvent::OpenBookBuilder,
+    ));
+
+    map
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+
+    fn temp_map_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("{}_{}.map", name, uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned()
+    }
 
     #[test]
-    fn test_load_map_from_file() {}
+    fn test_load_map_from_file() {
+        let path = temp_map_path("load");
+        fs::write(&path, "3,3\n3,5\n").unwrap();
+
+        let map = load_map_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(map.get_tile(1, 1), TileType::Grass);
+        assert!(matches!(
+            load_map_from_file(&path),
+            Err(MapFileError::Io(_))
+        ));
+    }
 
     #[test]
-    fn test_save_map_to_file() {}
+    fn test_save_map_to_file() {
+        let path = temp_map_path("save");
+        let map = generate_small_demo_map();
+
+        save_map_to_file(&map, &path).unwrap();
+        let loaded = load_map_from_file(&path).unwrap();
+        save_map(&generate_demo_map(30, 20), &path).unwrap();
+        let demo = load_map_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.get_tiles_ref(), map.get_tiles_ref());
+        assert_eq!(demo, generate_demo_map(30, 20));
+    }
 
     // TODO: Add tests! For everything!
     // - generate_small_demo_map
@@ -0,0 +1,1517 @@
+//! The map file format.
+//!
+//! A map file is made of sections. Blank lines and lines starting with ';' are ignored,
+//! except inside a layer, where every line up to the next blank line is a row of tiles.
+//! Elsewhere, a ';' after a space starts a comment that runs to the end of the line.
+//!
+//! ```text
+//! [map]
+//! name = demo
+//! music = overworld        ; Anything besides the name is kept as metadata
+//!
+//! [legend]
+//! '#' = wall
+//! '.' = grass
+//! '~' = water
+//! '-' = none               ; Nothing on this tile (only allowed above the ground layer)
+//!
+//! [layer ground]
+//! #####
+//! #..~#
+//! #####
+//!
+//! [layer overlay]
+//! -----
+//! -#---
+//! -----
+//!
+//! [spawns]
+//! start = 1,1
+//!
+//! [triggers]
+//! ; id = x,y [width x height] enter|inspect once|repeat event [args...]
+//! tutorial = 2,1 enter once book_builder
+//! pond = 3,1 1x1 inspect repeat dialogue first_quest
+//!
+//! [npcs]
+//! ; id = npc_id x,y facing [dialogue root_name] [sprite character] [behavior]
+//! narrator = 1 1,1 down dialogue first_quest sprite N
+//!
+//! [encounters]
+//! ; id = tile|x,y [WxH] rate% npc_id[:weight] [npc_id[:weight]...]
+//! reeds = 3,1 25% 2:3 3
+//!
+//! [contents]
+//! ; id = x,y text|item [count]|page|key <words...>
+//! welcome = 1,1 text Welcome to the demo!
+//! gold = 2,1 item 3 gold coin
+//! ```
+//!
+//! Trigger events are `dialogue <root_name>`, `battle <npc_id>`, `book_builder`,
+//! `warp <map> <spawn>`, and `advance_stage <epic> <stage>`.
+//!
+//! NPCs stand still unless given a behavior: `patrol <x,y> [x,y...]`, `wander <x,y> [WxH]`,
+//! `follow`, or `approach <range>`.
+//!
+//! Contents belong to the interactive tile they're on: the text of a sign or bookshelf, the
+//! items and pages in a chest or bookshelf, or the key a locked door needs. A tile can have
+//! several.
+//!
+//! Encounter tables cover every tile of a type, or a region. Each step onto a covered tile has
+//! `rate` percent chance of an encounter, with one of the opponents picked by weight (1 if
+//! left out). Where tables overlap, the first one listed wins.
+//!
+//! Older maps are a bare CSV of tile codes (see `TileType`'s `From<u8>`), one row per line.
+//! Both are read by `parse_map_source`, which tells them apart by the first section header.
+use crate::world::encounter::{EncounterArea, EncounterTable};
+use crate::world::interaction::{ContentKind, TileContent};
+use crate::world::map::{Layer, Map, TileType};
+use crate::world::navigation::Direction;
+use crate::world::npc::{NpcBehavior, NpcPlacement, DEFAULT_NPC_SPRITE};
+use crate::world::trigger::{
+    Trigger, TriggerActivation, TriggerArea, TriggerEvent, TriggerFrequency,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// What a legend entry stands for on layers above the ground
+const NO_TILE: &str = "none";
+
+/// A problem with a map file, and where it is. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl MapParseError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        MapParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for MapParseError {}
+
+type ParseResult<T> = Result<T, MapParseError>;
+
+/// Reads a map in either format: sectioned, or an older CSV map
+pub fn parse_map_source(source: &str) -> ParseResult<Map> {
+    if is_sectioned(source) {
+        parse_map(source)
+    } else {
+        parse_csv_map(source)
+    }
+}
+
+/// Whether the source is in the sectioned format, i.e. its first meaningful line is a header
+fn is_sectioned(source: &str) -> bool {
+    source
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(';'))
+        .is_some_and(|line| line.starts_with('['))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    None,
+    Map,
+    Legend,
+    Layer(Layer),
+    Spawns,
+    Triggers,
+    Npcs,
+    Encounters,
+    Contents,
+}
+
+/// A line of a layer, before the legend is applied
+struct LayerRow<'a> {
+    line: usize,
+    text: &'a str,
+}
+
+/// A line of an entry section (triggers, npcs, ...), read once the map's size is known
+struct Entry<'a> {
+    line: usize,
+    key: &'a str,
+    value: &'a str,
+    value_column: usize,
+}
+
+pub fn parse_map(source: &str) -> ParseResult<Map> {
+    let mut section = Section::None;
+    let mut name: Option<String> = None;
+    let mut metadata: Vec<(String, String)> = Vec::new();
+    let mut legend: HashMap<char, (Option<TileType>, usize)> = HashMap::new(); // .., line
+    let mut layers: Vec<(Layer, usize, Vec<LayerRow>)> = Vec::new(); // .., header line, ..
+    let mut spawns: Vec<Entry> = Vec::new();
+    let mut triggers: Vec<Entry> = Vec::new();
+    let mut npcs: Vec<Entry> = Vec::new();
+    let mut encounters: Vec<Entry> = Vec::new();
+    let mut contents: Vec<Entry> = Vec::new();
+
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+
+        if let Section::Layer(_) = section {
+            if text.trim().is_empty() {
+                section = Section::None;
+            } else if !text.starts_with('[') {
+                layers.last_mut().unwrap().2.push(LayerRow { line, text });
+                continue;
+            }
+        }
+
+        let trimmed = strip_comment(text).trim_end();
+        if trimmed.trim_start().is_empty() || trimmed.trim_start().starts_with(';') {
+            continue;
+        }
+
+        if trimmed.starts_with('[') {
+            section = parse_header(trimmed, line)?;
+            if let Section::Layer(layer) = section {
+                if layers.iter().any(|(existing, _, _)| *existing == layer) {
+                    return Err(MapParseError::new(
+                        line,
+                        1,
+                        format!("the {} layer is defined more than once", layer.name()),
+                    ));
+                }
+                layers.push((layer, line, Vec::new()));
+            }
+            continue;
+        }
+
+        match section {
+            Section::None => {
+                return Err(MapParseError::new(
+                    line,
+                    1,
+                    "expected a section header, like [map]",
+                ))
+            }
+            Section::Legend => {
+                let (symbol, tile) = parse_legend_entry(trimmed, line)?;
+                if let Some((_, first_line)) = legend.get(&symbol) {
+                    return Err(MapParseError::new(
+                        line,
+                        1,
+                        format!(
+                            "'{}' is already in the legend, on line {}",
+                            symbol, first_line
+                        ),
+                    ));
+                }
+                legend.insert(symbol, (tile, line));
+            }
+            Section::Layer(_) => unreachable!("Layer rows are handled above"),
+            Section::Map
+            | Section::Spawns
+            | Section::Triggers
+            | Section::Npcs
+            | Section::Encounters
+            | Section::Contents => {
+                let entry = parse_entry(trimmed, line)?;
+                match section {
+                    Section::Map if entry.key == "name" => name = Some(entry.value.to_string()),
+                    Section::Map => metadata.push((entry.key.to_string(), entry.value.to_string())),
+                    Section::Spawns => spawns.push(entry),
+                    Section::Triggers => triggers.push(entry),
+                    Section::Npcs => npcs.push(entry),
+                    Section::Encounters => encounters.push(entry),
+                    _ => contents.push(entry),
+                }
+            }
+        }
+    }
+
+    let name =
+        name.ok_or_else(|| MapParseError::new(1, 1, "the map has no name in its [map] section"))?;
+
+    // Ground first, since it sets the size of the map
+    let ground_index = layers
+        .iter()
+        .position(|(layer, _, _)| *layer == Layer::Ground)
+        .ok_or_else(|| MapParseError::new(1, 1, "the map has no [layer ground] section"))?;
+    let (_, ground_line, ground_rows) = layers.remove(ground_index);
+    let ground = decode_layer(Layer::Ground, ground_line, &ground_rows, &legend, None)?;
+    let tiles = ground
+        .into_iter()
+        .map(|row| row.into_iter().map(Option::unwrap).collect())
+        .collect();
+
+    let mut map = Map::new_with_tiles(tiles).with_name(&name);
+    for (key, value) in metadata {
+        map.set_metadata(&key, &value);
+    }
+
+    let size = (map.get_width(), map.get_height());
+    for (layer, header_line, rows) in layers {
+        let decoded = decode_layer(layer, header_line, &rows, &legend, Some(size))?;
+        for (y, row) in decoded.into_iter().enumerate() {
+            for (x, tile) in row.into_iter().enumerate() {
+                map.set_layer_tile(layer, x, y, tile);
+            }
+        }
+    }
+
+    for entry in spawns {
+        let mut tokens = Tokens::new(&entry);
+        let (x, y) = tokens.position(&map)?;
+        tokens.finish()?;
+        map.add_spawn(entry.key, x, y);
+    }
+
+    for entry in triggers {
+        let trigger = parse_trigger(&entry, &map)?;
+        map.add_trigger(trigger);
+    }
+
+    for entry in npcs {
+        let npc = parse_npc(&entry, &map)?;
+        map.add_npc(npc);
+    }
+
+    for entry in encounters {
+        let table = parse_encounter_table(&entry, &map)?;
+        map.add_encounter_table(table);
+    }
+
+    for entry in contents {
+        let content = parse_content(&entry, &map)?;
+        map.add_content(content);
+    }
+
+    Ok(map)
+}
+
+fn parse_header(text: &str, line: usize) -> ParseResult<Section> {
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(|| MapParseError::new(line, text.chars().count(), "expected ']'"))?;
+
+    match inner.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["map"] => Ok(Section::Map),
+        ["legend"] => Ok(Section::Legend),
+        ["spawns"] => Ok(Section::Spawns),
+        ["triggers"] => Ok(Section::Triggers),
+        ["npcs"] => Ok(Section::Npcs),
+        ["encounters"] => Ok(Section::Encounters),
+        ["contents"] => Ok(Section::Contents),
+        ["layer", layer_name] => {
+            Layer::from_name(layer_name)
+                .map(Section::Layer)
+                .ok_or_else(|| {
+                    MapParseError::new(
+                        line,
+                        text.find(layer_name).unwrap() + 1,
+                        format!(
+                            "unknown layer '{}' (expected ground, objects, or overlay)",
+                            layer_name
+                        ),
+                    )
+                })
+        }
+        _ => Err(MapParseError::new(
+            line,
+            2,
+            format!("unknown section '{}'", inner),
+        )),
+    }
+}
+
+/// Cuts a trailing `; comment` off a line. The ';' has to follow whitespace, so a quoted
+/// legend character like `';'` isn't mistaken for one.
+fn strip_comment(text: &str) -> &str {
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        if c == ';' && previous.is_some_and(char::is_whitespace) {
+            return &text[..i];
+        }
+        previous = Some(c);
+    }
+    text
+}
+
+/// Reads a `key = value` line
+fn parse_entry(text: &str, line: usize) -> ParseResult<Entry<'_>> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| MapParseError::new(line, 1, "expected 'key = value'"))?;
+
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(MapParseError::new(line, 1, "missing a name before '='"));
+    }
+
+    // Columns count characters, not bytes
+    let value_start = text.find('=').unwrap() + 1;
+    let leading = value.len() - value.trim_start().len();
+    let value_column = text[..value_start + leading].chars().count() + 1;
+
+    Ok(Entry {
+        line,
+        key,
+        value: value.trim(),
+        value_column,
+    })
+}
+
+/// Reads a `'c' = tile` (or `c = tile`) legend line
+fn parse_legend_entry(text: &str, line: usize) -> ParseResult<(char, Option<TileType>)> {
+    let mut chars = text.chars();
+    let first = chars.next().unwrap();
+
+    let (symbol, rest) = if first == '\'' {
+        let symbol = chars
+            .next()
+            .ok_or_else(|| MapParseError::new(line, 2, "expected a character"))?;
+        if chars.next() != Some('\'') {
+            return Err(MapParseError::new(line, 3, "expected a closing quote"));
+        }
+        (symbol, chars.as_str())
+    } else {
+        (first, chars.as_str())
+    };
+
+    if symbol == '[' {
+        return Err(MapParseError::new(
+            line,
+            1,
+            "'[' can't be used in the legend, since it starts a section",
+        ));
+    }
+
+    let rest_column = text.chars().count() - rest.chars().count() + 1;
+    let tile_name = rest
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or_else(|| MapParseError::new(line, rest_column, "expected '=' after the character"))?
+        .trim();
+
+    if tile_name == NO_TILE {
+        return Ok((symbol, None));
+    }
+
+    match TileType::from_name(tile_name) {
+        Some(tile) => Ok((symbol, Some(tile))),
+        None => Err(MapParseError::new(
+            line,
+            text.find(tile_name)
+                .map_or(rest_column, |i| text[..i].chars().count() + 1),
+            format!("unknown tile '{}'", tile_name),
+        )),
+    }
+}
+
+/// Applies the legend to a layer's rows
+fn decode_layer(
+    layer: Layer,
+    header_line: usize,
+    rows: &[LayerRow],
+    legend: &HashMap<char, (Option<TileType>, usize)>,
+    size: Option<(usize, usize)>, // Width and height of the ground, for the layers above it
+) -> ParseResult<Vec<Vec<Option<TileType>>>> {
+    if rows.is_empty() {
+        return Err(MapParseError::new(
+            header_line,
+            1,
+            format!("the {} layer has no rows", layer.name()),
+        ));
+    }
+
+    let width = size.map_or_else(|| rows[0].text.chars().count(), |(width, _)| width);
+    if let Some((_, height)) = size {
+        if rows.len() != height {
+            return Err(MapParseError::new(
+                header_line,
+                1,
+                format!(
+                    "the {} layer has {} rows, but the ground has {}",
+                    layer.name(),
+                    rows.len(),
+                    height
+                ),
+            ));
+        }
+    }
+
+    let mut decoded = Vec::with_capacity(rows.len());
+    for row in rows {
+        let row_width = row.text.chars().count();
+        if row_width != width {
+            return Err(MapParseError::new(
+                row.line,
+                row_width.min(width) + 1,
+                format!("expected {} tiles in this row, found {}", width, row_width),
+            ));
+        }
+
+        let mut tiles = Vec::with_capacity(width);
+        for (column, symbol) in row.text.chars().enumerate() {
+            let tile = match legend.get(&symbol) {
+                Some((tile, _)) => *tile,
+                None => {
+                    return Err(MapParseError::new(
+                        row.line,
+                        column + 1,
+                        format!("'{}' isn't in the legend", symbol),
+                    ))
+                }
+            };
+            if tile.is_none() && layer == Layer::Ground {
+                return Err(MapParseError::new(
+                    row.line,
+                    column + 1,
+                    format!(
+                        "'{}' means no tile, but every ground tile needs one",
+                        symbol
+                    ),
+                ));
+            }
+            tiles.push(tile);
+        }
+        decoded.push(tiles);
+    }
+
+    Ok(decoded)
+}
+
+/// Walks the whitespace separated values of an entry, keeping track of their columns
+struct Tokens<'a> {
+    line: usize,
+    tokens: Vec<(usize, &'a str)>,
+    next: usize,
+    end_column: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(entry: &Entry<'a>) -> Self {
+        let mut tokens = Vec::new();
+        let mut start: Option<usize> = None;
+        let value = entry.value;
+        for (i, c) in value.char_indices() {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some(i),
+                (true, Some(s)) => {
+                    tokens.push((s, &value[s..i]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            tokens.push((s, &value[s..]));
+        }
+
+        let to_column = |byte: usize| entry.value_column + value[..byte].chars().count();
+        Tokens {
+            line: entry.line,
+            tokens: tokens
+                .into_iter()
+                .map(|(byte, token)| (to_column(byte), token))
+                .collect(),
+            next: 0,
+            end_column: to_column(value.len()),
+        }
+    }
+
+    fn error(&self, column: usize, message: impl Into<String>) -> MapParseError {
+        MapParseError::new(self.line, column, message)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.next).map(|(_, token)| *token)
+    }
+
+    fn next(&mut self, expected: &str) -> ParseResult<(usize, &'a str)> {
+        let token = self
+            .tokens
+            .get(self.next)
+            .copied()
+            .ok_or_else(|| self.error(self.end_column, format!("expected {}", expected)))?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, expected: &str) -> ParseResult<T> {
+        let (column, token) = self.next(expected)?;
+        token
+            .parse()
+            .map_err(|_| self.error(column, format!("expected {}, found '{}'", expected, token)))
+    }
+
+    /// An `x,y` position, which has to be on the map
+    fn position(&mut self, map: &Map) -> ParseResult<(usize, usize)> {
+        let (column, token) = self.next("a position, like 2,3")?;
+        let (x, y) = token
+            .split_once(',')
+            .and_then(|(x, y)| Some((x.parse::<usize>().ok()?, y.parse::<usize>().ok()?)))
+            .ok_or_else(|| {
+                self.error(
+                    column,
+                    format!("expected a position, like 2,3, found '{}'", token),
+                )
+            })?;
+
+        if !map.in_bounds(x as isize, y as isize) {
+            return Err(self.error(
+                column,
+                format!(
+                    "{},{} is outside of the {}x{} map",
+                    x,
+                    y,
+                    map.get_width(),
+                    map.get_height()
+                ),
+            ));
+        }
+
+        Ok((x, y))
+    }
+
+    /// An `x,y` position, optionally followed by a `WxH` size, which has to fit on the map.
+    /// `what` names the thing the area belongs to, for errors.
+    fn area(&mut self, map: &Map, what: &str) -> ParseResult<TriggerArea> {
+        let (x, y) = self.position(map)?;
+        let mut area = TriggerArea::tile(x, y);
+
+        // The size is optional (and isn't to be confused with an encounter rate)
+        if self.peek().is_some_and(|token| {
+            token.starts_with(|c: char| c.is_ascii_digit()) && !token.ends_with('%')
+        }) {
+            let (column, token) = self.next("a size")?;
+            let (width, height) = token
+                .split_once('x')
+                .and_then(|(w, h)| Some((w.parse::<usize>().ok()?, h.parse::<usize>().ok()?)))
+                .filter(|(w, h)| *w > 0 && *h > 0)
+                .ok_or_else(|| {
+                    self.error(
+                        column,
+                        format!("expected a size, like 2x3, found '{}'", token),
+                    )
+                })?;
+            if x + width > map.get_width() || y + height > map.get_height() {
+                return Err(self.error(
+                    column,
+                    format!("the {} extends past the edge of the map", what),
+                ));
+            }
+            area.width = width;
+            area.height = height;
+        }
+
+        Ok(area)
+    }
+
+    /// Every token that's left, as words separated by single spaces
+    fn rest(&mut self, expected: &str) -> ParseResult<String> {
+        self.next(expected)?;
+        let words: Vec<&str> = self.tokens[self.next - 1..]
+            .iter()
+            .map(|(_, token)| *token)
+            .collect();
+        self.next = self.tokens.len();
+        Ok(words.join(" "))
+    }
+
+    fn finish(&self) -> ParseResult<()> {
+        match self.tokens.get(self.next) {
+            Some((column, token)) => Err(self.error(*column, format!("unexpected '{}'", token))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads a single `[triggers]` line, e.g. one typed into the map editor
+pub fn parse_trigger_line(text: &str, map: &Map) -> ParseResult<Trigger> {
+    parse_trigger(&parse_entry(text, 1)?, map)
+}
+
+/// Reads a single `[npcs]` line, e.g. one typed into the map editor
+pub fn parse_npc_line(text: &str, map: &Map) -> ParseResult<NpcPlacement> {
+    parse_npc(&parse_entry(text, 1)?, map)
+}
+
+fn parse_trigger(entry: &Entry, map: &Map) -> ParseResult<Trigger> {
+    let mut tokens = Tokens::new(entry);
+
+    let area = tokens.area(map, "trigger")?;
+
+    let (column, token) = tokens.next("enter or inspect")?;
+    let activation = match token {
+        "enter" => TriggerActivation::Enter,
+        "inspect" => TriggerActivation::Inspect,
+        _ => {
+            return Err(tokens.error(
+                column,
+                format!("expected enter or inspect, found '{}'", token),
+            ))
+        }
+    };
+
+    let (column, token) = tokens.next("once or repeat")?;
+    let frequency = match token {
+        "once" => TriggerFrequency::Once,
+        "repeat" => TriggerFrequency::Repeat,
+        _ => {
+            return Err(tokens.error(
+                column,
+                format!("expected once or repeat, found '{}'", token),
+            ))
+        }
+    };
+
+    let (column, token) = tokens.next("an event")?;
+    let event = match token {
+        "dialogue" => TriggerEvent::StartDialogue {
+            root_name: tokens.next("a dialogue root name")?.1.to_string(),
+        },
+        "battle" => TriggerEvent::StartBattle {
+            npc_id: tokens.number("an NPC id")?,
+        },
+        "book_builder" => TriggerEvent::OpenBookBuilder,
+        "warp" => TriggerEvent::Warp {
+            map: tokens.next("a map name")?.1.to_string(),
+            spawn: tokens.next("a spawn point")?.1.to_string(),
+        },
+        "advance_stage" => TriggerEvent::AdvanceStage {
+            epic: tokens.next("an epic")?.1.to_string(),
+            stage: tokens.next("a stage")?.1.to_string(),
+        },
+        _ => {
+            return Err(tokens.error(
+                column,
+                format!(
+                    "unknown event '{}' (expected dialogue, battle, book_builder, warp, or advance_stage)",
+                    token
+                ),
+            ))
+        }
+    };
+    tokens.finish()?;
+
+    Ok(Trigger::new(entry.key, area, activation, frequency, event))
+}
+
+fn parse_npc(entry: &Entry, map: &Map) -> ParseResult<NpcPlacement> {
+    let mut tokens = Tokens::new(entry);
+
+    let npc_id = tokens.number("an NPC id")?;
+    let (x, y) = tokens.position(map)?;
+
+    let (column, token) = tokens.next("a direction")?;
+    let facing = direction_from_name(token).ok_or_else(|| {
+        tokens.error(
+            column,
+            format!("expected up, down, left, or right, found '{}'", token),
+        )
+    })?;
+
+    let mut npc = NpcPlacement::new(entry.key, npc_id, x, y, facing);
+
+    // Then any of `dialogue <root_name>`, `sprite <character>`, and a behavior, in any order
+    while tokens.peek().is_some() {
+        let (column, token) = tokens.next("dialogue, sprite, or a behavior")?;
+        match token {
+            "dialogue" => npc = npc.with_dialogue(tokens.next("a dialogue root name")?.1),
+            "patrol" => {
+                let mut route = vec![tokens.position(map)?];
+                while tokens.peek().is_some_and(|token| token.contains(',')) {
+                    route.push(tokens.position(map)?);
+                }
+                npc = npc.with_behavior(NpcBehavior::patrol(route));
+            }
+            "wander" => {
+                let area = tokens.area(map, "wander area")?;
+                npc = npc.with_behavior(NpcBehavior::Wander { area });
+            }
+            "follow" => npc = npc.with_behavior(NpcBehavior::Follow),
+            "approach" => {
+                let range = tokens.number("a range, in tiles")?;
+                npc = npc.with_behavior(NpcBehavior::Approach { range });
+            }
+            "sprite" => {
+                let (column, sprite) = tokens.next("a sprite character")?;
+                let mut chars = sprite.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => npc = npc.with_sprite(c),
+                    _ => {
+                        return Err(tokens.error(
+                            column,
+                            format!("expected a single character, found '{}'", sprite),
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(tokens.error(
+                    column,
+                    format!(
+                    "expected dialogue, sprite, patrol, wander, follow, or approach, found '{}'",
+                    token
+                ),
+                ))
+            }
+        }
+    }
+    tokens.finish()?;
+
+    Ok(npc)
+}
+
+fn parse_encounter_table(entry: &Entry, map: &Map) -> ParseResult<EncounterTable> {
+    let mut tokens = Tokens::new(entry);
+
+    // Either a tile type, or a region of the map
+    let area = match tokens.peek().and_then(TileType::from_name) {
+        Some(tile) => {
+            tokens.next("a tile")?;
+            EncounterArea::Tile(tile)
+        }
+        None => EncounterArea::Region(tokens.area(map, "encounter region")?),
+    };
+
+    let (column, token) = tokens.next("an encounter rate, like 10%")?;
+    let rate = token
+        .strip_suffix('%')
+        .and_then(|rate| rate.parse::<u32>().ok())
+        .filter(|rate| *rate <= 100)
+        .ok_or_else(|| {
+            tokens.error(
+                column,
+                format!(
+                    "expected an encounter rate from 0% to 100%, found '{}'",
+                    token
+                ),
+            )
+        })?;
+
+    let mut table = EncounterTable::new(entry.key, area, rate);
+
+    // Then the opponents, as `npc_id` or `npc_id:weight`
+    loop {
+        let (column, token) = tokens.next("an NPC id")?;
+        let (npc_id, weight) = match token.split_once(':') {
+            Some((npc_id, weight)) => (npc_id, weight),
+            None => (token, "1"),
+        };
+        let (npc_id, weight) = npc_id
+            .parse::<i32>()
+            .ok()
+            .zip(weight.parse::<u32>().ok().filter(|weight| *weight > 0))
+            .ok_or_else(|| {
+                tokens.error(
+                    column,
+                    format!("expected an NPC id, like 2 or 2:3, found '{}'", token),
+                )
+            })?;
+        table = table.with_opponent(npc_id, weight);
+
+        if tokens.peek().is_none() {
+            break;
+        }
+    }
+
+    Ok(table)
+}
+
+fn parse_content(entry: &Entry, map: &Map) -> ParseResult<TileContent> {
+    let mut tokens = Tokens::new(entry);
+
+    let (x, y) = tokens.position(map)?;
+
+    let (column, token) = tokens.next("text, item, page, or key")?;
+    let kind = match token {
+        "text" => ContentKind::Text(tokens.rest("some text")?),
+        "item" => {
+            // The count is optional
+            let count = match tokens.peek().and_then(|token| token.parse::<u32>().ok()) {
+                Some(count) => {
+                    let (column, _) = tokens.next("a count")?;
+                    if count == 0 {
+                        return Err(tokens.error(column, "expected a count of at least 1"));
+                    }
+                    count
+                }
+                None => 1,
+            };
+            ContentKind::Item {
+                item: tokens.rest("an item name")?,
+                count,
+            }
+        }
+        "page" => ContentKind::Page(tokens.rest("a page title")?),
+        "key" => ContentKind::Key(tokens.rest("an item name")?),
+        _ => {
+            return Err(tokens.error(
+                column,
+                format!("expected text, item, page, or key, found '{}'", token),
+            ))
+        }
+    };
+
+    Ok(TileContent::new(entry.key, x, y, kind))
+}
+
+fn direction_from_name(name: &str) -> Option<Direction> {
+    match name {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// The character each tile is written as
+fn tile_symbol(tile: Option<TileType>) -> char {
+    match tile {
+        None => '-',
+        Some(TileType::Player) => 'P',
+        Some(TileType::PlayerFocus) => 'F',
+        Some(TileType::Empty) => '.',
+        Some(TileType::Wall) => '#',
+        Some(TileType::Water) => '~',
+        Some(TileType::Grass) => ',',
+        Some(TileType::Door) => '+',
+        Some(TileType::OpenDoor) => '/',
+        Some(TileType::LockedDoor) => 'L',
+        Some(TileType::Bridge) => '=',
+        Some(TileType::Sign) => '?',
+        Some(TileType::Chest) => '$',
+        Some(TileType::OpenChest) => '_',
+        Some(TileType::Bookshelf) => 'B',
+    }
+}
+
+/// Writes a map in the sectioned format. Only the tiles the map uses go in the legend.
+pub fn write_map(map: &Map) -> String {
+    let mut out = String::new();
+
+    out.push_str("[map]\n");
+    out.push_str(&format!("name = {}\n", map.get_name()));
+    for (key, value) in map.get_metadata() {
+        out.push_str(&format!("{} = {}\n", key, value));
+    }
+
+    let layers: Vec<Layer> = Layer::ALL
+        .into_iter()
+        .filter(|layer| map.has_layer(*layer))
+        .collect();
+
+    let mut used: Vec<Option<TileType>> = Vec::new();
+    for layer in &layers {
+        for y in 0..map.get_height() {
+            for x in 0..map.get_width() {
+                let tile = map.get_layer_tile(*layer, x, y);
+                if !used.contains(&tile) {
+                    used.push(tile);
+                }
+            }
+        }
+    }
+    used.sort_by_key(|tile| tile_symbol(*tile));
+
+    out.push_str("\n[legend]\n");
+    for tile in used {
+        let name = tile.map_or(NO_TILE, |tile| tile.name());
+        out.push_str(&format!("'{}' = {}\n", tile_symbol(tile), name));
+    }
+
+    for layer in layers {
+        out.push_str(&format!("\n[layer {}]\n", layer.name()));
+        for y in 0..map.get_height() {
+            let row: String = (0..map.get_width())
+                .map(|x| tile_symbol(map.get_layer_tile(layer, x, y)))
+                .collect();
+            out.push_str(&row);
+            out.push('\n');
+        }
+    }
+
+    if !map.get_spawns().is_empty() {
+        out.push_str("\n[spawns]\n");
+        for (name, (x, y)) in map.get_spawns() {
+            out.push_str(&format!("{} = {},{}\n", name, x, y));
+        }
+    }
+
+    if !map.get_triggers().is_empty() {
+        out.push_str("\n[triggers]\n");
+        for trigger in map.get_triggers() {
+            out.push_str(&format!("{}\n", write_trigger(trigger)));
+        }
+    }
+
+    if !map.get_npcs().is_empty() {
+        out.push_str("\n[npcs]\n");
+        for npc in map.get_npcs() {
+            let mut line = format!(
+                "{} = {} {},{} {}",
+                npc.id,
+                npc.npc_id,
+                npc.x,
+                npc.y,
+                direction_name(npc.facing)
+            );
+            if let Some(root_name) = &npc.dialogue {
+                line.push_str(&format!(" dialogue {}", root_name));
+            }
+            if npc.sprite != DEFAULT_NPC_SPRITE {
+                line.push_str(&format!(" sprite {}", npc.sprite));
+            }
+            if let Some(behavior) = write_behavior(&npc.behavior) {
+                line.push(' ');
+                line.push_str(&behavior);
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+
+    if !map.get_encounter_tables().is_empty() {
+        out.push_str("\n[encounters]\n");
+        for table in map.get_encounter_tables() {
+            out.push_str(&write_encounter_table(table));
+            out.push('\n');
+        }
+    }
+
+    if !map.get_contents().is_empty() {
+        out.push_str("\n[contents]\n");
+        for content in map.get_contents() {
+            out.push_str(&write_content(content));
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+fn write_encounter_table(table: &EncounterTable) -> String {
+    let area = match &table.area {
+        EncounterArea::Tile(tile) => tile.name().to_string(),
+        EncounterArea::Region(area) => {
+            format!("{},{} {}x{}", area.x, area.y, area.width, area.height)
+        }
+    };
+    let opponents: Vec<String> = table
+        .opponents
+        .iter()
+        .map(|opponent| format!("{}:{}", opponent.npc_id, opponent.weight))
+        .collect();
+
+    format!(
+        "{} = {} {}% {}",
+        table.id,
+        area,
+        table.rate,
+        opponents.join(" ")
+    )
+}
+
+fn write_content(content: &TileContent) -> String {
+    let kind = match &content.kind {
+        ContentKind::Text(text) => format!("text {}", text),
+        ContentKind::Item { item, count: 1 } => format!("item {}", item),
+        ContentKind::Item { item, count } => format!("item {} {}", count, item),
+        ContentKind::Page(page) => format!("page {}", page),
+        ContentKind::Key(key) => format!("key {}", key),
+    };
+
+    format!("{} = {},{} {}", content.id, content.x, content.y, kind)
+}
+
+fn write_behavior(behavior: &NpcBehavior) -> Option<String> {
+    match behavior {
+        NpcBehavior::Stationary => None,
+        NpcBehavior::Patrol { route, .. } => Some(format!(
+            "patrol {}",
+            route
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )),
+        NpcBehavior::Wander { area } => Some(format!(
+            "wander {},{} {}x{}",
+            area.x, area.y, area.width, area.height
+        )),
+        NpcBehavior::Follow => Some("follow".to_string()),
+        NpcBehavior::Approach { range } => Some(format!("approach {}", range)),
+    }
+}
+
+fn write_trigger(trigger: &Trigger) -> String {
+    let area = &trigger.area;
+    let activation = match trigger.activation {
+        TriggerActivation::Enter => "enter",
+        TriggerActivation::Inspect => "inspect",
+    };
+    let frequency = match trigger.frequency {
+        TriggerFrequency::Once => "once",
+        TriggerFrequency::Repeat => "repeat",
+    };
+    let event = match &trigger.event {
+        TriggerEvent::StartDialogue { root_name } => format!("dialogue {}", root_name),
+        TriggerEvent::StartBattle { npc_id } => format!("battle {}", npc_id),
+        TriggerEvent::OpenBookBuilder => "book_builder".to_string(),
+        TriggerEvent::Warp { map, spawn } => format!("warp {} {}", map, spawn),
+        TriggerEvent::AdvanceStage { epic, stage } => format!("advance_stage {} {}", epic, stage),
+    };
+
+    format!(
+        "{} = {},{} {}x{} {} {} {}",
+        trigger.id, area.x, area.y, area.width, area.height, activation, frequency, event
+    )
+}
+
+/// Reads an older CSV map. Unlike the original loader, unknown tile codes are an error.
+pub fn parse_csv_map(source: &str) -> ParseResult<Map> {
+    let mut tiles: Vec<Vec<TileType>> = Vec::new();
+
+    // Trailing blank lines are fine, blank rows in the middle of the map aren't
+    let rows: Vec<&str> = source.trim_end().lines().collect();
+    for (index, text) in rows.into_iter().enumerate() {
+        let line = index + 1;
+        let mut row = Vec::new();
+        let mut column = 1;
+        for value in text.split(',') {
+            let tile = value
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|n| *n <= TileType::Bookshelf as u8) // The last tile code
+                .map(TileType::from)
+                .ok_or_else(|| {
+                    MapParseError::new(line, column, format!("unknown tile code '{}'", value))
+                })?;
+            row.push(tile);
+            column += value.chars().count() + 1;
+        }
+
+        if let Some(first) = tiles.first() {
+            if row.len() != first.len() {
+                return Err(MapParseError::new(
+                    line,
+                    1,
+                    format!(
+                        "expected {} tiles in this row, found {}",
+                        first.len(),
+                        row.len()
+                    ),
+                ));
+            }
+        }
+        tiles.push(row);
+    }
+
+    Ok(Map::new_with_tiles(tiles))
+}
+
+/// Writes the ground layer of a map as CSV. Everything else is lost.
+pub fn write_csv_map(map: &Map) -> String {
+    let mut out = String::new();
+    for row in map.get_tiles_ref() {
+        let line: Vec<String> = row.iter().map(|tile| (*tile as u8).to_string()).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example map in the module doc
+    fn doc_example() -> String {
+        let source = include_str!("map_format.rs");
+        source
+            .lines()
+            .skip_while(|line| *line != "//! ```text")
+            .skip(1)
+            .take_while(|line| *line != "//! ```")
+            .map(|line| {
+                line.trim_start_matches("//!")
+                    .strip_prefix(' ')
+                    .unwrap_or("")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    const SAMPLE: &str = "\
+; A small test map
+[map]
+name = pond
+music = calm
+
+[legend]
+'#' = wall
+'.' = grass
+'~' = water
+'-' = none
+
+[layer ground]
+#####
+#..~#
+#####
+
+[layer overlay]
+-----
+-#---
+-----
+
+[spawns]
+start = 1,1
+
+[triggers]
+tutorial = 2,1 enter once book_builder
+pond = 3,1 1x1 inspect repeat dialogue first_quest
+back = 1,1 enter repeat warp pond start
+
+[npcs]
+narrator = 1 2,1 down dialogue first_quest sprite N
+
+[encounters]
+reeds = 3,1 25% 2:3 3
+field = grass 10% 2
+
+[contents]
+welcome = 1,1 text Welcome to   the pond!
+gold = 2,1 item 3 gold coin
+key = 2,1 key rusty key
+";
+
+    fn parse_error(source: &str) -> MapParseError {
+        parse_map(source).expect_err("Expected the map to be rejected")
+    }
+
+    #[test]
+    fn test_parse_map() {
+        let map = parse_map(SAMPLE).unwrap();
+
+        assert_eq!(map.get_name(), "pond");
+        assert_eq!(map.get_metadata().get("music").unwrap(), "calm");
+        assert_eq!((map.get_width(), map.get_height()), (5, 3));
+        assert_eq!(map.get_tile(3, 1), TileType::Water);
+        assert_eq!(
+            map.get_layer_tile(Layer::Overlay, 1, 1),
+            Some(TileType::Wall)
+        );
+        assert_eq!(map.get_layer_tile(Layer::Overlay, 2, 1), None);
+        assert_eq!(map.get_spawn("start"), Some((1, 1)));
+        assert_eq!(map.get_triggers().len(), 3);
+        assert_eq!(map.get_triggers()[1].activation, TriggerActivation::Inspect);
+        assert_eq!(
+            map.get_triggers()[2].event,
+            TriggerEvent::Warp {
+                map: "pond".to_string(),
+                spawn: "start".to_string()
+            }
+        );
+        assert_eq!(
+            map.get_npcs()[0],
+            NpcPlacement::new("narrator", 1, 2, 1, Direction::Down)
+                .with_dialogue("first_quest")
+                .with_sprite('N')
+        );
+    }
+
+    #[test]
+    fn test_parse_doc_example() {
+        let map = parse_map_source(&doc_example()).unwrap();
+
+        assert_eq!(map.get_name(), "demo");
+        // Comments aren't part of the values
+        assert_eq!(map.get_metadata().get("music").unwrap(), "overworld");
+        assert_eq!(map.get_layer_tile(Layer::Overlay, 0, 0), None);
+    }
+
+    #[test]
+    fn test_inline_comments() {
+        let source = SAMPLE
+            .replace("name = pond", "name = pond ; The pond")
+            .replace(
+                "'#' = wall",
+                "'#' = wall\t; Walls\n';' = grass ; A quoted ';'",
+            )
+            .replace("-#---", "-#;--");
+
+        let map = parse_map(&source).unwrap();
+
+        assert_eq!(map.get_name(), "pond");
+        // Inside a layer, ';' is just a tile
+        assert_eq!(
+            map.get_layer_tile(Layer::Overlay, 2, 1),
+            Some(TileType::Grass)
+        );
+        assert_eq!(
+            map.get_layer_tile(Layer::Overlay, 1, 1),
+            Some(TileType::Wall)
+        );
+    }
+
+    #[test]
+    fn test_parse_encounter_tables() {
+        let map = parse_map(SAMPLE).unwrap();
+
+        assert_eq!(
+            map.get_encounter_tables(),
+            &vec![
+                EncounterTable::new("reeds", EncounterArea::Region(TriggerArea::tile(3, 1)), 25)
+                    .with_opponent(2, 3)
+                    .with_opponent(3, 1),
+                EncounterTable::new("field", EncounterArea::Tile(TileType::Grass), 10)
+                    .with_opponent(2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_contents() {
+        let map = parse_map(SAMPLE).unwrap();
+
+        assert_eq!(
+            map.get_contents(),
+            &vec![
+                TileContent::new(
+                    "welcome",
+                    1,
+                    1,
+                    ContentKind::Text("Welcome to the pond!".to_string())
+                ),
+                TileContent::new(
+                    "gold",
+                    2,
+                    1,
+                    ContentKind::Item {
+                        item: "gold coin".to_string(),
+                        count: 3
+                    }
+                ),
+                TileContent::new("key", 2, 1, ContentKind::Key("rusty key".to_string())),
+            ]
+        );
+
+        let map = parse_map(&SAMPLE.replace("item 3 gold", "item gold")).unwrap();
+        assert_eq!(
+            map.get_contents()[1].kind,
+            ContentKind::Item {
+                item: "gold coin".to_string(),
+                count: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_bad_contents() {
+        let error = parse_error(&SAMPLE.replace("1,1 text", "1,1 scroll"));
+        assert!(error.message.contains("found 'scroll'"));
+
+        let error = parse_error(&SAMPLE.replace("item 3 gold coin", "item 3"));
+        assert!(error.message.contains("expected an item name"));
+
+        let error = parse_error(&SAMPLE.replace("item 3", "item 0"));
+        assert!(error.message.contains("at least 1"));
+    }
+
+    #[test]
+    fn test_bad_encounter_table() {
+        let error = parse_error(&SAMPLE.replace("25%", "25"));
+        assert!(error.message.contains("found '25'"));
+
+        let error = parse_error(&SAMPLE.replace("25%", "101%"));
+        assert!(error.message.contains("from 0% to 100%"));
+
+        let error = parse_error(&SAMPLE.replace("2:3", "2:0"));
+        assert!(error.message.contains("found '2:0'"));
+
+        let error = parse_error(&SAMPLE.replace("10% 2", "10%"));
+        assert!(error.message.contains("expected an NPC id"));
+
+        let error = parse_error(&SAMPLE.replace("field = grass", "field = lava"));
+        assert!(error.message.contains("expected a position"));
+    }
+
+    #[test]
+    fn test_write_map_round_trip() {
+        let map = parse_map(SAMPLE).unwrap();
+
+        let written = write_map(&map);
+
+        assert_eq!(parse_map(&written).unwrap(), map);
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = "3,3,3\n3,5,4\n3,3,3\n";
+
+        let map = parse_csv_map(csv).unwrap();
+
+        assert_eq!(map.get_tile(1, 1), TileType::Grass);
+        assert_eq!(write_csv_map(&map), csv);
+
+        // Converting an old map to the new format keeps every tile
+        let converted = parse_map(&write_map(&map.clone().with_name("old"))).unwrap();
+        assert_eq!(converted.get_tiles_ref(), map.get_tiles_ref());
+        assert_eq!(write_csv_map(&converted), csv);
+    }
+
+    #[test]
+    fn test_parse_map_source_detects_format() {
+        assert_eq!(parse_map_source(SAMPLE).unwrap().get_name(), "pond");
+        assert_eq!(parse_map_source("2,3\n").unwrap().get_width(), 2);
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        assert_eq!(
+            parse_csv_map("3,3\n3,99\n").unwrap_err(),
+            MapParseError::new(2, 3, "unknown tile code '99'")
+        );
+        assert_eq!(parse_csv_map("3,3\n3,x\n").unwrap_err().column, 3);
+        assert_eq!(parse_csv_map("3,3\n3\n").unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_unknown_legend_character() {
+        let source = SAMPLE.replace("#..~#", "#.?~#");
+
+        let error = parse_error(&source);
+
+        assert_eq!((error.line, error.column), (14, 3));
+        assert!(error.message.contains("'?'"));
+    }
+
+    #[test]
+    fn test_unknown_tile_in_legend() {
+        let error = parse_error(&SAMPLE.replace("'~' = water", "'~' = lava"));
+
+        assert_eq!((error.line, error.column), (9, 7));
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let error = parse_error(&SAMPLE.replace("#..~#", "#..~"));
+
+        assert_eq!((error.line, error.column), (14, 5));
+    }
+
+    #[test]
+    fn test_no_tile_on_the_ground() {
+        let error = parse_error(&SAMPLE.replace("#..~#", "#.-~#"));
+
+        assert_eq!((error.line, error.column), (14, 3));
+    }
+
+    #[test]
+    fn test_spawn_outside_map() {
+        let error = parse_error(&SAMPLE.replace("start = 1,1", "start = 9,1"));
+
+        assert_eq!((error.line, error.column), (23, 9));
+    }
+
+    #[test]
+    fn test_bad_trigger() {
+        let error =
+            parse_error(&SAMPLE.replace("enter once book_builder", "enter twice book_builder"));
+        assert_eq!((error.line, error.column), (26, 22));
+
+        let error = parse_error(&SAMPLE.replace("book_builder", "explode"));
+        assert!(error.message.contains("unknown event 'explode'"));
+
+        let error = parse_error(&SAMPLE.replace("3,1 1x1", "3,1 3x1"));
+        assert!(error.message.contains("past the edge"));
+    }
+
+    #[test]
+    fn test_bad_npc() {
+        let error = parse_error(&SAMPLE.replace("sprite N", "sprite NN"));
+        assert_eq!((error.line, error.column), (31, 51));
+
+        let error = parse_error(&SAMPLE.replace("2,1 down", "2,1 sideways"));
+        assert!(error.message.contains("found 'sideways'"));
+    }
+
+    #[test]
+    fn test_npc_behaviors() {
+        let behaviors = [
+            ("patrol 1,1 2,1", NpcBehavior::patrol(vec![(1, 1), (2, 1)])),
+            (
+                "wander 1,1 2x1",
+                NpcBehavior::Wander {
+                    area: TriggerArea {
+                        x: 1,
+                        y: 1,
+                        width: 2,
+                        height: 1,
+                    },
+                },
+            ),
+            ("follow", NpcBehavior::Follow),
+            ("approach 3", NpcBehavior::Approach { range: 3 }),
+        ];
+
+        for (text, behavior) in behaviors {
+            let map =
+                parse_map(&SAMPLE.replace("sprite N", &format!("{} sprite N", text))).unwrap();
+            assert_eq!(map.get_npcs()[0].behavior, behavior);
+            assert_eq!(parse_map(&write_map(&map)).unwrap(), map);
+        }
+
+        let error = parse_error(&SAMPLE.replace("sprite N", "patrol 1,1 9,1"));
+        assert!(error.message.contains("outside of the 5x3 map"));
+
+        let error = parse_error(&SAMPLE.replace("sprite N", "wander 1,1 5x1"));
+        assert_eq!(
+            error.message,
+            "the wander area extends past the edge of the map"
+        );
+
+        let error = parse_error(&SAMPLE.replace("sprite N", "approach"));
+        assert!(error.message.contains("expected a range"));
+    }
+
+    #[test]
+    fn test_missing_sections() {
+        assert!(
+            parse_error("[map]\n[legend]\n'.' = grass\n[layer ground]\n.\n")
+                .message
+                .contains("no name")
+        );
+        assert!(parse_error("[map]\nname = empty\n")
+            .message
+            .contains("no [layer ground]"));
+        assert_eq!(parse_error("name = nowhere\n").line, 1);
+        assert!(parse_error("[maps]\n").message.contains("unknown section"));
+    }
+}
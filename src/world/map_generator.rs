@@ -0,0 +1,475 @@
+//! Procedurally generated maps.
+//!
+//! Everything about a generated map comes from its seed, so an area can be regenerated from
+//! (say) the player's seed whenever it's needed, instead of being stored.
+use crate::world::encounter::{EncounterArea, EncounterTable, Opponent};
+use crate::world::map::{Map, TileType};
+use crate::world::pathfinding::distances_from;
+use crate::world::trigger::{
+    Trigger, TriggerActivation, TriggerArea, TriggerEvent, TriggerFrequency,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Generated maps are never smaller than this, so there's room for a room or two
+pub const MIN_WIDTH: usize = 12;
+pub const MIN_HEIGHT: usize = 8;
+
+/// Where the player arrives on a generated map
+pub const START_SPAWN: &str = "start";
+/// The trigger (on the tile furthest from the start) that leads out of a generated map
+pub const EXIT_TRIGGER: &str = "exit";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapStyle {
+    Dungeon, // Rooms joined by corridors. Each room past the first has its own encounters.
+    Forest,  // A cellular automata cave of grass, walled in by trees. Encounters in the grass.
+    Town,    // Buildings with streets between them. Towns are safe, so no encounters.
+}
+
+pub struct MapGenerator {
+    style: MapStyle,
+    width: usize,
+    height: usize,
+    seed: u64,
+    exit: Option<(String, String)>, // Map and spawn the exit warps to
+    encounter_rate: u32,
+    opponents: Vec<Opponent>,
+}
+
+impl MapGenerator {
+    pub fn new(style: MapStyle, width: usize, height: usize, seed: u64) -> Self {
+        MapGenerator {
+            style,
+            width: width.max(MIN_WIDTH),
+            height: height.max(MIN_HEIGHT),
+            seed,
+            exit: None,
+            encounter_rate: 0,
+            opponents: Vec::new(),
+        }
+    }
+
+    /// Adds an exit, which warps the player to a spawn point on another map
+    pub fn with_exit(mut self, map: &str, spawn: &str) -> Self {
+        self.exit = Some((map.to_string(), spawn.to_string()));
+        self
+    }
+
+    /// Adds encounter zones, where they make sense for the style
+    pub fn with_encounters(mut self, rate: u32, opponents: Vec<Opponent>) -> Self {
+        self.encounter_rate = rate;
+        self.opponents = opponents;
+        self
+    }
+
+    /// Generates the map. The same generator (and seed) always generates the same map.
+    ///
+    /// Every walkable tile can be reached from the start spawn, including the exit.
+    pub fn generate(&self, name: &str) -> Map {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let (mut map, rooms) = match self.style {
+            MapStyle::Dungeon => self.dungeon(&mut rng),
+            MapStyle::Forest => (self.forest(&mut rng), Vec::new()),
+            MapStyle::Town => (self.town(&mut rng), Vec::new()),
+        };
+        map = map.with_name(name);
+
+        let start = match rooms.first() {
+            Some(room) => center(room),
+            None => leftmost_passable(&map),
+        };
+        let exit = keep_reachable(&mut map, start);
+
+        map.add_spawn(START_SPAWN, start.0, start.1);
+        if let Some((exit_map, exit_spawn)) = &self.exit {
+            map.add_trigger(Trigger::new(
+                EXIT_TRIGGER,
+                TriggerArea::tile(exit.0, exit.1),
+                TriggerActivation::Enter,
+                TriggerFrequency::Repeat,
+                TriggerEvent::Warp {
+                    map: exit_map.clone(),
+                    spawn: exit_spawn.clone(),
+                },
+            ));
+        }
+
+        if !self.opponents.is_empty() {
+            self.add_encounters(&mut map, &rooms);
+        }
+
+        map
+    }
+
+    fn add_encounters(&self, map: &mut Map, rooms: &[TriggerArea]) {
+        let table = |id: &str, area: EncounterArea| {
+            let mut table = EncounterTable::new(id, area, self.encounter_rate);
+            table.opponents = self.opponents.clone();
+            table
+        };
+
+        match self.style {
+            // The first room is where the player starts, so it's left quiet
+            MapStyle::Dungeon => {
+                for (i, room) in rooms.iter().enumerate().skip(1) {
+                    map.add_encounter_table(table(
+                        &format!("room_{}", i),
+                        EncounterArea::Region(*room),
+                    ));
+                }
+            }
+            MapStyle::Forest => {
+                map.add_encounter_table(table("undergrowth", EncounterArea::Tile(TileType::Grass)))
+            }
+            MapStyle::Town => {}
+        }
+    }
+
+    /// Rooms, carved out of solid wall, each joined to the one before by an L shaped corridor
+    fn dungeon(&self, rng: &mut StdRng) -> (Map, Vec<TriggerArea>) {
+        let mut map = filled_map(self.width, self.height, TileType::Wall);
+        let mut rooms: Vec<TriggerArea> = Vec::new();
+
+        for _ in 0..self.width * self.height / 20 {
+            let width = rng.gen_range(3..=(self.width / 3).clamp(3, 10));
+            let height = rng.gen_range(3..=(self.height / 3).clamp(3, 6));
+            let room = TriggerArea {
+                x: rng.gen_range(1..self.width - width),
+                y: rng.gen_range(1..self.height - height),
+                width,
+                height,
+            };
+
+            // Leave at least a wall between rooms
+            if rooms.iter().any(|other| overlaps(other, &room, 1)) {
+                continue;
+            }
+
+            fill_area(&mut map, &room, TileType::Empty);
+            if let Some(previous) = rooms.last() {
+                carve_corridor(&mut map, center(previous), center(&room), rng.gen_bool(0.5));
+            }
+            rooms.push(room);
+        }
+
+        (map, rooms)
+    }
+
+    /// Random trees, smoothed out into clearings by a few rounds of cellular automata
+    fn forest(&self, rng: &mut StdRng) -> Map {
+        let (width, height) = (self.width, self.height);
+        let mut walls: Vec<Vec<bool>> = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| is_edge(x, y, width, height) || rng.gen_bool(0.45))
+                    .collect()
+            })
+            .collect();
+
+        for _ in 0..4 {
+            walls = (0..height)
+                .map(|y| {
+                    (0..width)
+                        .map(|x| is_edge(x, y, width, height) || wall_neighbours(&walls, x, y) >= 5)
+                        .collect()
+                })
+                .collect();
+        }
+
+        Map::new_with_tiles(
+            walls
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|wall| {
+                            if wall {
+                                TileType::Wall
+                            } else {
+                                TileType::Grass
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// A walled field of grass, with buildings dotted around it
+    fn town(&self, rng: &mut StdRng) -> Map {
+        let mut map = filled_map(self.width, self.height, TileType::Grass);
+        fill_border(&mut map, TileType::Wall);
+
+        let mut buildings: Vec<TriggerArea> = Vec::new();
+        for _ in 0..self.width * self.height / 25 {
+            let width = rng.gen_range(2..=(self.width / 4).clamp(2, 6));
+            let height = rng.gen_range(2..=(self.height / 4).clamp(2, 4));
+            // Keep a street between the buildings and the town wall
+            if self.width < width + 4 || self.height < height + 4 {
+                continue;
+            }
+            let building = TriggerArea {
+                x: rng.gen_range(2..=self.width - width - 2),
+                y: rng.gen_range(2..=self.height - height - 2),
+                width,
+                height,
+            };
+
+            // ...and between each building
+            if buildings.iter().any(|other| overlaps(other, &building, 1)) {
+                continue;
+            }
+
+            fill_area(&mut map, &building, TileType::Wall);
+            buildings.push(building);
+        }
+
+        map
+    }
+}
+
+fn center(area: &TriggerArea) -> (usize, usize) {
+    (area.x + area.width / 2, area.y + area.height / 2)
+}
+
+fn filled_map(width: usize, height: usize, tile: TileType) -> Map {
+    Map::new_with_tiles(vec![vec![tile; width]; height])
+}
+
+fn fill_area(map: &mut Map, area: &TriggerArea, tile: TileType) {
+    for y in area.y..area.y + area.height {
+        for x in area.x..area.x + area.width {
+            map.set_tile(x, y, tile);
+        }
+    }
+}
+
+fn fill_border(map: &mut Map, tile: TileType) {
+    let (width, height) = (map.get_width(), map.get_height());
+    for y in 0..height {
+        for x in 0..width {
+            if is_edge(x, y, width, height) {
+                map.set_tile(x, y, tile);
+            }
+        }
+    }
+}
+
+fn is_edge(x: usize, y: usize, width: usize, height: usize) -> bool {
+    x == 0 || y == 0 || x == width - 1 || y == height - 1
+}
+
+/// Whether two areas overlap, or come within `margin` tiles of each other
+fn overlaps(a: &TriggerArea, b: &TriggerArea, margin: usize) -> bool {
+    a.x < b.x + b.width + margin
+        && b.x < a.x + a.width + margin
+        && a.y < b.y + b.height + margin
+        && b.y < a.y + a.height + margin
+}
+
+fn carve_corridor(map: &mut Map, from: (usize, usize), to: (usize, usize), horizontal_first: bool) {
+    let corner = if horizontal_first {
+        (to.0, from.1)
+    } else {
+        (from.0, to.1)
+    };
+    for (a, b) in [(from, corner), (corner, to)] {
+        for y in a.1.min(b.1)..=a.1.max(b.1) {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                map.set_tile(x, y, TileType::Empty);
+            }
+        }
+    }
+}
+
+/// How many of the 8 tiles around (x, y) are walls. Off the map counts as wall.
+fn wall_neighbours(walls: &[Vec<bool>], x: usize, y: usize) -> usize {
+    let mut count = 0;
+    for dy in -1..=1isize {
+        for dx in -1..=1isize {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let wall = walls
+                .get((y as isize + dy) as usize)
+                .and_then(|row| row.get((x as isize + dx) as usize))
+                .copied()
+                .unwrap_or(true);
+            if wall {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// The walkable tile nearest the left edge (then the top), in the largest open region
+fn leftmost_passable(map: &Map) -> (usize, usize) {
+    let mut best: Option<((usize, usize), usize)> = None; // Tile, size of its region
+    let mut seen = vec![vec![false; map.get_width()]; map.get_height()];
+
+    for x in 0..map.get_width() {
+        for y in 0..map.get_height() {
+            if seen[y][x] || !map.get_tile(x, y).is_passable(&[]) {
+                continue;
+            }
+
+            let distances = distances_from(map, (x, y), &[]);
+            let mut size = 0;
+            for (row, seen_row) in distances.iter().zip(seen.iter_mut()) {
+                for (distance, seen) in row.iter().zip(seen_row.iter_mut()) {
+                    if distance.is_some() {
+                        *seen = true;
+                        size += 1;
+                    }
+                }
+            }
+
+            if best.is_none_or(|(_, best_size)| size > best_size) {
+                best = Some(((x, y), size));
+            }
+        }
+    }
+
+    match best {
+        Some((tile, _)) => tile,
+        // Solid wall. Make some room.
+        None => (1, 1),
+    }
+}
+
+/// Walls off everything that can't be reached from `start`, and returns the tile furthest
+/// from it (ties go to the last one found, scanning row by row)
+fn keep_reachable(map: &mut Map, start: (usize, usize)) -> (usize, usize) {
+    map.set_tile(
+        start.0,
+        start.1,
+        walkable_tile(map.get_tile(start.0, start.1)),
+    );
+    let distances = distances_from(map, start, &[]);
+
+    let mut furthest = (start, 0);
+    for (y, row) in distances.iter().enumerate() {
+        for (x, distance) in row.iter().enumerate() {
+            match distance {
+                Some(distance) if *distance >= furthest.1 => furthest = ((x, y), *distance),
+                Some(_) => {}
+                None => {
+                    if map.get_tile(x, y).is_passable(&[]) {
+                        map.set_tile(x, y, TileType::Wall);
+                    }
+                }
+            }
+        }
+    }
+
+    furthest.0
+}
+
+fn walkable_tile(tile: TileType) -> TileType {
+    if tile.is_passable(&[]) {
+        tile
+    } else {
+        TileType::Empty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::map_format;
+    use crate::world::pathfinding::find_path;
+
+    const STYLES: [MapStyle; 3] = [MapStyle::Dungeon, MapStyle::Forest, MapStyle::Town];
+
+    fn generator(style: MapStyle, seed: u64) -> MapGenerator {
+        MapGenerator::new(style, 40, 20, seed)
+            .with_exit("meadow", "start")
+            .with_encounters(
+                10,
+                vec![Opponent {
+                    npc_id: 2,
+                    weight: 1,
+                }],
+            )
+    }
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        for style in STYLES {
+            let map = generator(style, 42).generate("generated");
+
+            assert_eq!(map, generator(style, 42).generate("generated"));
+            assert_ne!(map, generator(style, 43).generate("generated"));
+        }
+    }
+
+    #[test]
+    fn test_everywhere_is_reachable_from_the_start() {
+        for style in STYLES {
+            for seed in 0..20 {
+                let map = generator(style, seed).generate("generated");
+                assert_eq!((map.get_width(), map.get_height()), (40, 20));
+
+                let start = map.get_spawn(START_SPAWN).unwrap();
+                assert!(map.get_tile(start.0, start.1).is_passable(&[]));
+
+                let distances = distances_from(&map, start, &[]);
+                for y in 0..map.get_height() {
+                    for x in 0..map.get_width() {
+                        assert_eq!(
+                            map.get_tile(x, y).is_passable(&[]),
+                            distances[y][x].is_some(),
+                            "{:?} (seed {}) has an unreachable tile at {},{}",
+                            style,
+                            seed,
+                            x,
+                            y
+                        );
+                    }
+                }
+
+                let exit = &map.get_triggers()[0];
+                assert_eq!(exit.id, EXIT_TRIGGER);
+                assert!(find_path(&map, start, (exit.area.x, exit.area.y), &[], &[]).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_encounter_zones() {
+        let dungeon = generator(MapStyle::Dungeon, 42).generate("dungeon");
+        assert!(!dungeon.get_encounter_tables().is_empty());
+        // Not in the room the player starts in
+        let start = dungeon.get_spawn(START_SPAWN).unwrap();
+        assert!(dungeon.encounter_table_at(start.0, start.1).is_none());
+
+        let forest = generator(MapStyle::Forest, 42).generate("forest");
+        assert_eq!(
+            forest.get_encounter_tables()[0].area,
+            EncounterArea::Tile(TileType::Grass)
+        );
+
+        let town = generator(MapStyle::Town, 42).generate("town");
+        assert!(town.get_encounter_tables().is_empty());
+    }
+
+    #[test]
+    fn test_tiny_maps_are_made_bigger() {
+        let map = MapGenerator::new(MapStyle::Dungeon, 1, 1, 42).generate("tiny");
+        assert_eq!((map.get_width(), map.get_height()), (MIN_WIDTH, MIN_HEIGHT));
+        assert!(map.get_triggers().is_empty()); // No exit was asked for
+    }
+
+    #[test]
+    fn test_generated_maps_can_be_saved() {
+        for style in STYLES {
+            let map = generator(style, 42).generate("generated");
+            assert_eq!(
+                map_format::parse_map(&map_format::write_map(&map)).unwrap(),
+                map
+            );
+        }
+    }
+}
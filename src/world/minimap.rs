@@ -0,0 +1,372 @@
+//! Shrunk-down views of a whole map: the minimap in the corner of the navigation view, and
+//! the full-screen world map. Both are built by downsampling the map, so that each cell on
+//! screen stands for a block of tiles.
+use crate::tui::frame::Frame;
+use crate::world::fog::{Fog, TileVisibility};
+use crate::world::map::{tile_to_char, Map, TileType};
+use crate::world::navigation::Direction;
+use crate::world::trigger::{TriggerEvent, TriggerFrequency};
+
+/// How many cells the minimap has, not counting its border
+pub const MINIMAP_SIZE: (usize, usize) = (24, 8);
+
+/// Something worth pointing out on a map
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Marker {
+    // In order of importance, for when several end up in the same cell
+    Quest,  // A trigger that moves the story along
+    Portal, // A trigger that warps the player somewhere
+    Npc,
+}
+
+impl Marker {
+    pub fn symbol(&self) -> char {
+        match self {
+            Marker::Quest => '!',
+            Marker::Portal => 'O',
+            Marker::Npc => '&',
+        }
+    }
+}
+
+/// The points of interest on a map. Triggers are marked at the middle of their area, unless
+/// they only fire once and already have (their ids are in `fired`).
+pub fn points_of_interest(map: &Map, fired: &[String]) -> Vec<(usize, usize, Marker)> {
+    let triggers = map.get_triggers().iter().filter_map(|trigger| {
+        if trigger.frequency == TriggerFrequency::Once && fired.contains(&trigger.id) {
+            return None;
+        }
+        let marker = match trigger.event {
+            TriggerEvent::Warp { .. } => Marker::Portal,
+            TriggerEvent::StartDialogue { .. }
+            | TriggerEvent::StartBattle { .. }
+            | TriggerEvent::AdvanceStage { .. } => Marker::Quest,
+            TriggerEvent::OpenBookBuilder => return None,
+        };
+        let area = trigger.area;
+        Some((area.x + area.width / 2, area.y + area.height / 2, marker))
+    });
+    let npcs = map.get_npcs().iter().map(|npc| (npc.x, npc.y, Marker::Npc));
+
+    triggers.chain(npcs).collect()
+}
+
+/// Shrinks the map down to fit in `max_width` by `max_height` cells.
+///
+/// Each cell shows, in order of preference: the player (as an arrow, facing their way), the
+/// most important point of interest in its block of tiles, or the most common tile in the
+/// block. With fog of war, only what the player has explored is shown, and NPCs only while
+/// they're in sight.
+pub fn downsample(
+    map: &Map,
+    fog: Option<&Fog>,
+    fired: &[String],
+    player: (usize, usize),
+    direction: Direction,
+    max_width: usize,
+    max_height: usize,
+) -> Frame {
+    let (map_width, map_height) = (map.get_width(), map.get_height());
+    if max_width == 0 || max_height == 0 {
+        return Frame::new(0, 0);
+    }
+
+    // How many tiles each cell covers, along each axis
+    let scale_x = map_width.div_ceil(max_width).max(1);
+    let scale_y = map_height.div_ceil(max_height).max(1);
+    let mut frame = Frame::new(map_width.div_ceil(scale_x), map_height.div_ceil(scale_y));
+
+    let visibility =
+        |x: usize, y: usize| fog.map_or(TileVisibility::Visible, |fog| fog.visibility(x, y));
+
+    for cell_y in 0..frame.get_height() {
+        for cell_x in 0..frame.get_width() {
+            let xs = cell_x * scale_x..((cell_x + 1) * scale_x).min(map_width);
+            let ys = cell_y * scale_y..((cell_y + 1) * scale_y).min(map_height);
+
+            // How often each tile type comes up in the block
+            let mut counts: Vec<(TileType, usize)> = Vec::new();
+            for y in ys.clone() {
+                for x in xs.clone() {
+                    if visibility(x, y) == TileVisibility::Unseen {
+                        continue;
+                    }
+                    let tile = map.get_tile(x, y);
+                    match counts.iter_mut().find(|(counted, _)| *counted == tile) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((tile, 1)),
+                    }
+                }
+            }
+
+            // Empty space only wins if there's nothing else in the block
+            let common = counts
+                .iter()
+                .filter(|(tile, _)| *tile != TileType::Empty)
+                .max_by_key(|(_, count)| *count)
+                .or(counts.first());
+            if let Some((tile, _)) = common {
+                frame.set(cell_x, cell_y, tile_to_char(*tile, None));
+            }
+        }
+    }
+
+    let mut markers: Vec<(usize, usize, Marker)> = points_of_interest(map, fired)
+        .into_iter()
+        .filter(|(x, y, marker)| match visibility(*x, *y) {
+            TileVisibility::Visible => true,
+            TileVisibility::Remembered => *marker != Marker::Npc, // They've probably moved
+            TileVisibility::Unseen => false,
+        })
+        .collect();
+    // Most important last, so it's drawn over the rest
+    markers.sort_by_key(|(_, _, marker)| std::cmp::Reverse(*marker));
+    for (x, y, marker) in markers {
+        frame.set(x / scale_x, y / scale_y, marker.symbol());
+    }
+
+    frame.set(
+        player.0 / scale_x,
+        player.1 / scale_y,
+        tile_to_char(TileType::PlayerFocus, Some(direction)),
+    );
+
+    frame
+}
+
+/// The minimap, with a border around it
+pub fn minimap(
+    map: &Map,
+    fog: Option<&Fog>,
+    fired: &[String],
+    player: (usize, usize),
+    direction: Direction,
+) -> Frame {
+    let inner = downsample(
+        map,
+        fog,
+        fired,
+        player,
+        direction,
+        MINIMAP_SIZE.0,
+        MINIMAP_SIZE.1,
+    );
+    bordered(&inner)
+}
+
+/// The full-screen world map: the map as big as it fits, with its name above it and a
+/// legend below it
+pub fn world_map(
+    map: &Map,
+    fog: Option<&Fog>,
+    fired: &[String],
+    player: (usize, usize),
+    direction: Direction,
+    width: usize,
+    height: usize,
+) -> Frame {
+    let mut frame = Frame::new(width, height);
+    if height < 3 {
+        return frame; // No room for anything but the map
+    }
+
+    let title = format!("World map: {}", map.get_name());
+    frame.print(width.saturating_sub(title.chars().count()) / 2, 0, &title);
+
+    let inner = downsample(map, fog, fired, player, direction, width, height - 2);
+    frame.blit(
+        (width - inner.get_width()) / 2,
+        1 + (height - 2 - inner.get_height()) / 2,
+        &inner,
+    );
+
+    let legend = format!(
+        "{} you  {} NPC  {} portal  {} quest  (any key to close)",
+        tile_to_char(TileType::PlayerFocus, Some(direction)),
+        Marker::Npc.symbol(),
+        Marker::Portal.symbol(),
+        Marker::Quest.symbol()
+    );
+    frame.print(0, height - 1, &legend);
+
+    frame
+}
+
+/// A copy of the frame, inside a box
+fn bordered(inner: &Frame) -> Frame {
+    let (width, height) = (inner.get_width() + 2, inner.get_height() + 2);
+    let mut frame = Frame::new(width, height);
+
+    for x in 1..width - 1 {
+        frame.set(x, 0, '─');
+        frame.set(x, height - 1, '─');
+    }
+    for y in 1..height - 1 {
+        frame.set(0, y, '│');
+        frame.set(width - 1, y, '│');
+    }
+    frame.set(0, 0, '┌');
+    frame.set(width - 1, 0, '┐');
+    frame.set(0, height - 1, '└');
+    frame.set(width - 1, height - 1, '┘');
+    frame.blit(1, 1, inner);
+
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::fog::Explored;
+    use crate::world::npc::NpcPlacement;
+    use crate::world::trigger::{Trigger, TriggerActivation, TriggerArea, TriggerFrequency};
+
+    // An 8x4 map: grass on the left half, water on the right, and a wall at (3, 1)
+    fn test_map() -> Map {
+        let mut map = Map::new(8, 4).with_name("test");
+        for y in 0..4 {
+            for x in 0..8 {
+                let tile = if x < 4 {
+                    TileType::Grass
+                } else {
+                    TileType::Water
+                };
+                map.set_tile(x, y, tile);
+            }
+        }
+        map.set_tile(3, 1, TileType::Wall);
+        map
+    }
+
+    #[test]
+    fn test_downsample_without_scaling() {
+        let map = test_map();
+
+        let frame = downsample(&map, None, &[], (0, 0), Direction::Right, 10, 10);
+
+        assert_eq!((frame.get_width(), frame.get_height()), (8, 4));
+        assert_eq!(frame.row(0), "▶...~~~~");
+        assert_eq!(frame.row(1), "...#~~~~");
+    }
+
+    #[test]
+    fn test_downsample_picks_the_most_common_tile() {
+        let map = test_map();
+
+        // 2x2 tiles per cell
+        let frame = downsample(&map, None, &[], (7, 3), Direction::Up, 4, 2);
+
+        assert_eq!((frame.get_width(), frame.get_height()), (4, 2));
+        assert_eq!(frame.row(0), "..~~"); // The wall is outnumbered
+        assert_eq!(frame.row(1), "..~▲");
+    }
+
+    #[test]
+    fn test_downsample_marks_points_of_interest() {
+        let mut map = test_map();
+        map.add_npc(NpcPlacement::new("guard", 1, 0, 0, Direction::Down));
+        map.add_trigger(Trigger::new(
+            "door",
+            TriggerArea {
+                x: 4,
+                y: 0,
+                width: 4,
+                height: 2,
+            },
+            TriggerActivation::Enter,
+            TriggerFrequency::Repeat,
+            TriggerEvent::Warp {
+                map: "cave".to_string(),
+                spawn: "entrance".to_string(),
+            },
+        ));
+        // Shares a cell with the guard, and is more important
+        map.add_trigger(Trigger::new(
+            "quest",
+            TriggerArea::tile(1, 1),
+            TriggerActivation::Inspect,
+            TriggerFrequency::Once,
+            TriggerEvent::AdvanceStage {
+                epic: "first".to_string(),
+                stage: "second".to_string(),
+            },
+        ));
+
+        let frame = downsample(&map, None, &[], (2, 3), Direction::Down, 4, 2);
+
+        assert_eq!(frame.row(0), "!.~O"); // The portal's middle is (6, 1)
+        assert_eq!(frame.row(1), ".▼~~");
+    }
+
+    #[test]
+    fn test_fired_triggers_arent_points_of_interest() {
+        let mut map = test_map();
+        for (id, frequency) in [
+            ("quest", TriggerFrequency::Once),
+            ("portal", TriggerFrequency::Repeat),
+        ] {
+            map.add_trigger(Trigger::new(
+                id,
+                TriggerArea::tile(1, 1),
+                TriggerActivation::Inspect,
+                frequency,
+                TriggerEvent::Warp {
+                    map: "cave".to_string(),
+                    spawn: "entrance".to_string(),
+                },
+            ));
+        }
+        let fired = vec!["quest".to_string(), "portal".to_string()];
+
+        // Only the trigger that fires every time is still worth pointing out
+        assert_eq!(points_of_interest(&map, &[]).len(), 2);
+        assert_eq!(
+            points_of_interest(&map, &fired),
+            vec![(1, 1, Marker::Portal)]
+        );
+    }
+
+    #[test]
+    fn test_downsample_hides_unexplored_tiles() {
+        let mut map = test_map();
+        map.add_npc(NpcPlacement::new("guard", 1, 1, 3, Direction::Down));
+        let mut explored = Explored::new(8, 4);
+        explored.explore(1, 3);
+        explored.explore(6, 0);
+        let fog = Fog::new(explored);
+
+        let frame = downsample(&map, Some(&fog), &[], (0, 0), Direction::Left, 10, 10);
+
+        // The guard is remembered, but not seen, so isn't shown
+        assert_eq!(frame.row(0), "◀     ~ ");
+        assert_eq!(frame.row(3), " .      ");
+    }
+
+    #[test]
+    fn test_minimap_has_a_border() {
+        let map = test_map();
+
+        let frame = minimap(&map, None, &[], (0, 0), Direction::Right);
+
+        assert_eq!(frame.row(0), "┌────────┐");
+        assert_eq!(frame.row(1), "│▶...~~~~│");
+        assert_eq!(frame.row(5), "└────────┘");
+    }
+
+    #[test]
+    fn test_world_map() {
+        let map = test_map();
+
+        let frame = world_map(&map, None, &[], (0, 0), Direction::Right, 20, 8);
+
+        assert_eq!(frame.row(0), "  World map: test   ");
+        assert_eq!(frame.row(2), "      ▶...~~~~      ");
+        assert!(frame.row(7).starts_with("▶ you  & NPC"));
+
+        // Too small for anything
+        assert_eq!(
+            world_map(&map, None, &[], (0, 0), Direction::Right, 20, 2).row(0),
+            " ".repeat(20)
+        );
+    }
+}
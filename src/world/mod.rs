@@ -1,4 +1,18 @@
+pub mod atlas;
+pub mod camera;
+pub mod editor;
+pub mod encounter;
+pub mod fog;
+pub mod interaction;
 pub mod map;
 pub mod map_file_utils;
+pub mod map_format;
+pub mod map_generator;
+pub mod minimap;
 pub mod navigation;
+pub mod npc;
+pub mod pathfinding;
+pub mod random;
+pub mod trigger;
+pub mod validator;
 pub mod viewport;
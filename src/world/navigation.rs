@@ -1,13 +1,16 @@
-use crossterm::event::{self, Event, KeyCode, MouseEvent};
+use crate::tui::size_guard;
+use crate::world::map::{Ability, Map};
+use crossterm::event::{Event, KeyCode, MouseEvent};
 use rusqlite::types::FromSql;
 use rusqlite::types::{FromSqlResult, ValueRef};
 use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
 use std::{
     io::{self, Write},
     time::Duration,
 };
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -16,6 +19,13 @@ pub enum Direction {
 }
 
 impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
     pub fn dx(&self) -> isize {
         match self {
             Direction::Left => -1,
@@ -31,6 +41,15 @@ impl Direction {
             _ => 0,
         }
     }
+
+    /// The direction of a single step from one tile to a neighbouring one
+    pub fn between(from: (usize, usize), to: (usize, usize)) -> Option<Direction> {
+        let dx = to.0 as isize - from.0 as isize;
+        let dy = to.1 as isize - from.1 as isize;
+        Direction::ALL
+            .into_iter()
+            .find(|direction| direction.dx() == dx && direction.dy() == dy)
+    }
 }
 
 impl ToSql for Direction {
@@ -66,6 +85,10 @@ pub enum NavigationAction {
     Left,
     Right,
     Inspect,           // For future expansion, e.g., pressing 'x'
+    ToggleMinimap,     // 'm'
+    WorldMap,          // Tab, shows the whole map until the next key press
+    ScrollLogUp,       // PageUp, back through older messages
+    ScrollLogDown,     // PageDown
     Exit,              // Exit action (when pressing esc)
     Resize,            // When player resizes viewport, we have to handle that as an action
     None,              // Default case for no action
@@ -84,6 +107,58 @@ pub fn action_to_direction(action: NavigationAction) -> Option<Direction> {
     }
 }
 
+/// The result of the player trying to move in a direction
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// The player wasn't facing that way, so they turned to face it
+    Turned(Direction),
+    Moved {
+        x: usize,
+        y: usize,
+    },
+    /// The player is facing that way, but can't move there. Holds the bump message.
+    Blocked(String),
+}
+
+/// Works out what happens when the player, standing at (x, y) and facing `facing`,
+/// tries to move in `direction`.
+///
+/// The first press of a direction only turns the player; pressing it again moves them,
+/// as long as the tile they're facing is in the map, and passable with their abilities.
+pub fn try_move(
+    map: &Map,
+    x: usize,
+    y: usize,
+    facing: Direction,
+    direction: Direction,
+    abilities: &[Ability],
+) -> MoveOutcome {
+    if facing != direction {
+        return MoveOutcome::Turned(direction);
+    }
+
+    let next_x = x as isize + direction.dx();
+    let next_y = y as isize + direction.dy();
+    if !map.in_bounds(next_x, next_y) {
+        return MoveOutcome::Blocked("You can't go any further that way.".to_string());
+    }
+
+    let (next_x, next_y) = (next_x as usize, next_y as usize);
+    if map.npc_at(next_x, next_y).is_some() {
+        return MoveOutcome::Blocked("Someone is in the way.".to_string());
+    }
+
+    let tile = map.get_tile(next_x, next_y);
+    if tile.is_passable(abilities) {
+        MoveOutcome::Moved {
+            x: next_x,
+            y: next_y,
+        }
+    } else {
+        MoveOutcome::Blocked(tile.bump_message())
+    }
+}
+
 pub fn prompt_for_action() -> Option<NavigationAction> {
     let mut stdout = io::stdout();
     // let prompt = "\rMove around the world... ";
@@ -92,18 +167,25 @@ pub fn prompt_for_action() -> Option<NavigationAction> {
     stdout.flush().unwrap();
 
     loop {
-        if let Ok(true) = event::poll(Duration::from_millis(100)) {
-            if let Ok(Event::Key(key_event)) = event::read() {
-                return match key_event.code {
-                    KeyCode::Up => Some(NavigationAction::Up),
-                    KeyCode::Down => Some(NavigationAction::Down),
-                    KeyCode::Left => Some(NavigationAction::Left),
-                    KeyCode::Right => Some(NavigationAction::Right),
-                    KeyCode::Char('x') => Some(NavigationAction::Inspect),
-                    KeyCode::Esc => Some(NavigationAction::Exit), // Allow exit with Esc
-                    _ => break,
-                };
-            }
+        if let Some(event) = size_guard::read_event(Duration::from_millis(100)) {
+            let key_event = match event {
+                Event::Key(key_event) => key_event,
+                Event::Resize(..) => return Some(NavigationAction::Resize),
+                _ => continue,
+            };
+            return match key_event.code {
+                KeyCode::Up => Some(NavigationAction::Up),
+                KeyCode::Down => Some(NavigationAction::Down),
+                KeyCode::Left => Some(NavigationAction::Left),
+                KeyCode::Right => Some(NavigationAction::Right),
+                KeyCode::Char('x') => Some(NavigationAction::Inspect),
+                KeyCode::Char('m') => Some(NavigationAction::ToggleMinimap),
+                KeyCode::Tab => Some(NavigationAction::WorldMap),
+                KeyCode::PageUp => Some(NavigationAction::ScrollLogUp),
+                KeyCode::PageDown => Some(NavigationAction::ScrollLogDown),
+                KeyCode::Esc => Some(NavigationAction::Exit), // Allow exit with Esc
+                _ => break,
+            };
         }
     }
     None
@@ -121,11 +203,102 @@ pub fn get_player_sprite(direction: &NavigationAction) -> String {
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::world::map::TileType;
+    use crate::world::npc::NpcPlacement;
 
     #[test]
     fn test_create_viewport() {}
 
+    // A 3x3 map of grass, with a wall to the right of the center, and water below it
+    fn test_map() -> Map {
+        let mut map = Map::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                map.set_tile(x, y, TileType::Grass);
+            }
+        }
+        map.set_tile(2, 1, TileType::Wall);
+        map.set_tile(1, 2, TileType::Water);
+        map
+    }
+
+    #[test]
+    fn test_try_move_turns_first() {
+        let map = test_map();
+
+        let outcome = try_move(&map, 1, 1, Direction::Up, Direction::Left, &[]);
+
+        assert_eq!(outcome, MoveOutcome::Turned(Direction::Left));
+    }
+
+    #[test]
+    fn test_try_move_onto_walkable_tile() {
+        let map = test_map();
+
+        let outcome = try_move(&map, 1, 1, Direction::Left, Direction::Left, &[]);
+
+        assert_eq!(outcome, MoveOutcome::Moved { x: 0, y: 1 });
+    }
+
+    #[test]
+    fn test_try_move_into_wall() {
+        let map = test_map();
+
+        let outcome = try_move(&map, 1, 1, Direction::Right, Direction::Right, &[]);
+
+        assert_eq!(
+            outcome,
+            MoveOutcome::Blocked("You bump into a wall.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_try_move_into_water_needs_swim() {
+        let map = test_map();
+
+        assert!(matches!(
+            try_move(&map, 1, 1, Direction::Down, Direction::Down, &[]),
+            MoveOutcome::Blocked(_)
+        ));
+        assert_eq!(
+            try_move(
+                &map,
+                1,
+                1,
+                Direction::Down,
+                Direction::Down,
+                &[Ability::Swim]
+            ),
+            MoveOutcome::Moved { x: 1, y: 2 }
+        );
+    }
+
+    #[test]
+    fn test_try_move_into_npc() {
+        let mut map = test_map();
+        map.add_npc(NpcPlacement::new("narrator", 1, 0, 1, Direction::Right));
+
+        assert_eq!(
+            try_move(&map, 1, 1, Direction::Left, Direction::Left, &[]),
+            MoveOutcome::Blocked("Someone is in the way.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_try_move_off_the_map() {
+        let map = test_map();
+
+        assert!(matches!(
+            try_move(&map, 0, 0, Direction::Up, Direction::Up, &[]),
+            MoveOutcome::Blocked(_)
+        ));
+        assert!(matches!(
+            try_move(&map, 0, 0, Direction::Left, Direction::Left, &[]),
+            MoveOutcome::Blocked(_)
+        ));
+    }
+
     // TODO: Add tests! For everything!
     // - Direction
     //  - to sql
@@ -0,0 +1,351 @@
+//! Non-player characters, as they're placed on a map, and how they move around it
+use crate::world::map::Map;
+use crate::world::navigation::Direction;
+use crate::world::pathfinding::{find_path, find_path_where, manhattan_distance};
+use crate::world::random::tick_rng;
+use crate::world::trigger::TriggerArea;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// How an NPC is drawn, unless their placement says otherwise
+pub const DEFAULT_NPC_SPRITE: char = '&';
+
+/// How an NPC moves around the map, one step (at most) per tick
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NpcBehavior {
+    Stationary,
+    /// Walks to each waypoint in turn, then back to the first, and so on
+    Patrol {
+        route: Vec<(usize, usize)>,
+        next: usize, // Index of the waypoint they're walking to
+    },
+    /// Ambles around at random, without leaving the area
+    Wander {
+        area: TriggerArea,
+    },
+    /// Walks up to the player wherever they are, and stays next to them
+    Follow,
+    /// Like Follow, but only once the player is within `range` tiles
+    Approach {
+        range: usize,
+    },
+}
+
+impl NpcBehavior {
+    pub fn patrol(route: Vec<(usize, usize)>) -> Self {
+        NpcBehavior::Patrol { route, next: 0 }
+    }
+}
+
+/// Where an NPC stands on a map, and what talking to them does
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NpcPlacement {
+    pub id: String,  // Unique within its map
+    pub npc_id: i32, // The NPC in the non_player_characters table
+    pub x: usize,
+    pub y: usize,
+    pub facing: Direction,
+    pub sprite: char,
+    pub dialogue: Option<String>, // Root name of the dialogue started by inspecting them
+    pub behavior: NpcBehavior,
+}
+
+impl NpcPlacement {
+    pub fn new(id: &str, npc_id: i32, x: usize, y: usize, facing: Direction) -> Self {
+        NpcPlacement {
+            id: id.to_string(),
+            npc_id,
+            x,
+            y,
+            facing,
+            sprite: DEFAULT_NPC_SPRITE,
+            dialogue: None,
+            behavior: NpcBehavior::Stationary,
+        }
+    }
+
+    pub fn with_sprite(mut self, sprite: char) -> Self {
+        self.sprite = sprite;
+        self
+    }
+
+    pub fn with_dialogue(mut self, root_name: &str) -> Self {
+        self.dialogue = Some(root_name.to_string());
+        self
+    }
+
+    pub fn with_behavior(mut self, behavior: NpcBehavior) -> Self {
+        self.behavior = behavior;
+        self
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// Takes this NPC's next step (if any) on the map.
+    ///
+    /// `occupied` holds the tiles they can't walk onto: the player's, and everyone else's.
+    fn tick(
+        &mut self,
+        map: &Map,
+        player: (usize, usize),
+        occupied: &[(usize, usize)],
+        rng: &mut StdRng,
+    ) {
+        let position = self.position();
+
+        let step = match &mut self.behavior {
+            NpcBehavior::Stationary => None,
+            NpcBehavior::Patrol { route, next } => {
+                if route.is_empty() {
+                    return;
+                }
+                if route[*next % route.len()] == position {
+                    *next = (*next + 1) % route.len();
+                }
+                let waypoint = route[*next % route.len()];
+                // Wait for whoever's in the way to move
+                find_path(map, position, waypoint, &[], occupied)
+                    .and_then(|path| path.first().copied())
+            }
+            NpcBehavior::Wander { area } => {
+                // Stand around about half the time
+                if rng.gen_bool(0.5) {
+                    return;
+                }
+                let direction = Direction::ALL[rng.gen_range(0..Direction::ALL.len())];
+                self.facing = direction;
+
+                let x = position.0 as isize + direction.dx();
+                let y = position.1 as isize + direction.dy();
+                (map.in_bounds(x, y)
+                    && area.contains(x as usize, y as usize)
+                    && !occupied.contains(&(x as usize, y as usize))
+                    && map.get_tile(x as usize, y as usize).is_passable(&[]))
+                .then_some((x as usize, y as usize))
+            }
+            NpcBehavior::Follow => self.step_towards(map, player, occupied),
+            NpcBehavior::Approach { range } => {
+                if manhattan_distance(position, player) > *range {
+                    return;
+                }
+                self.step_towards(map, player, occupied)
+            }
+        };
+
+        if let Some(step) = step {
+            if let Some(direction) = Direction::between(position, step) {
+                self.facing = direction;
+            }
+            self.x = step.0;
+            self.y = step.1;
+        }
+    }
+
+    /// The first step on the way to standing next to the player, who they face once they're there
+    fn step_towards(
+        &mut self,
+        map: &Map,
+        player: (usize, usize),
+        occupied: &[(usize, usize)],
+    ) -> Option<(usize, usize)> {
+        let position = self.position();
+        if manhattan_distance(position, player) == 1 {
+            self.facing = Direction::between(position, player).unwrap();
+            return None;
+        }
+
+        find_path_where(map, position, &[], occupied, |tile| {
+            manhattan_distance(tile, player) == 1
+        })
+        .and_then(|path| path.first().copied())
+    }
+}
+
+/// Moves every NPC on the map by one tick, in the order the map lists them.
+///
+/// Given the same map, player position, seed, and tick, NPCs always move the same way, so
+/// wandering can be replayed (and tested).
+pub fn tick_npcs(map: &mut Map, player: (usize, usize), seed: u64, tick: u64) {
+    for i in 0..map.get_npcs().len() {
+        let mut npc = map.get_npcs()[i].clone();
+
+        let mut occupied: Vec<(usize, usize)> = map
+            .get_npcs()
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, other)| other.position())
+            .collect();
+        occupied.push(player);
+
+        let mut rng = tick_rng(seed, tick, i as u64);
+        npc.tick(map, player, &occupied, &mut rng);
+
+        map.get_npcs_mut()[i] = npc;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::map::TileType;
+
+    // A 7x5 field of grass, with a wall at (3, 1)
+    fn test_map() -> Map {
+        let mut map = Map::new(7, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                map.set_tile(x, y, TileType::Grass);
+            }
+        }
+        map.set_tile(3, 1, TileType::Wall);
+        map
+    }
+
+    fn npc_positions(map: &Map) -> Vec<(usize, usize)> {
+        map.get_npcs().iter().map(|npc| npc.position()).collect()
+    }
+
+    #[test]
+    fn test_stationary_npcs_stay_put() {
+        let mut map = test_map();
+        map.add_npc(NpcPlacement::new("guard", 1, 2, 2, Direction::Down));
+
+        for tick in 0..10 {
+            tick_npcs(&mut map, (0, 0), 42, tick);
+        }
+
+        assert_eq!(npc_positions(&map), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn test_patrol_walks_the_route_and_loops() {
+        let mut map = test_map();
+        map.add_npc(
+            NpcPlacement::new("guard", 1, 2, 1, Direction::Down)
+                .with_behavior(NpcBehavior::patrol(vec![(2, 1), (4, 1)])),
+        );
+
+        let mut positions = Vec::new();
+        for tick in 0..8 {
+            tick_npcs(&mut map, (0, 4), 42, tick);
+            positions.push(map.get_npcs()[0].position());
+        }
+
+        // Around the wall, and back again
+        assert_eq!(
+            positions,
+            vec![
+                (2, 0),
+                (3, 0),
+                (4, 0),
+                (4, 1),
+                (4, 0),
+                (3, 0),
+                (2, 0),
+                (2, 1)
+            ]
+        );
+        assert_eq!(map.get_npcs()[0].facing, Direction::Down);
+    }
+
+    #[test]
+    fn test_patrol_waits_for_the_player() {
+        let mut map = test_map();
+        map.add_npc(
+            NpcPlacement::new("guard", 1, 0, 0, Direction::Right)
+                .with_behavior(NpcBehavior::patrol(vec![(0, 0), (2, 0)])),
+        );
+
+        // The player is standing in the only doorway
+        map.set_tile(0, 1, TileType::Wall);
+        tick_npcs(&mut map, (1, 0), 42, 0);
+        assert_eq!(npc_positions(&map), vec![(0, 0)]);
+
+        tick_npcs(&mut map, (1, 1), 42, 1);
+        assert_eq!(npc_positions(&map), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_wander_is_deterministic_and_stays_in_its_area() {
+        let area = TriggerArea {
+            x: 4,
+            y: 2,
+            width: 3,
+            height: 3,
+        };
+        let wanderer = NpcPlacement::new("sheep", 1, 5, 3, Direction::Down)
+            .with_behavior(NpcBehavior::Wander { area });
+
+        let walk = |seed: u64| {
+            let mut map = test_map();
+            map.add_npc(wanderer.clone());
+            let mut positions = Vec::new();
+            for tick in 0..50 {
+                tick_npcs(&mut map, (0, 0), seed, tick);
+                positions.push(map.get_npcs()[0].position());
+            }
+            positions
+        };
+
+        let positions = walk(42);
+        assert_eq!(positions, walk(42));
+        assert_ne!(positions, walk(43));
+        assert!(positions.iter().all(|(x, y)| area.contains(*x, *y)));
+        // It did actually wander
+        assert!(positions.iter().any(|position| *position != (5, 3)));
+    }
+
+    #[test]
+    fn test_follow_walks_up_to_the_player() {
+        let mut map = test_map();
+        map.add_npc(
+            NpcPlacement::new("dog", 1, 6, 4, Direction::Up).with_behavior(NpcBehavior::Follow),
+        );
+
+        for tick in 0..10 {
+            tick_npcs(&mut map, (2, 4), 42, tick);
+        }
+
+        let dog = &map.get_npcs()[0];
+        assert_eq!(dog.position(), (3, 4));
+        assert_eq!(dog.facing, Direction::Left);
+    }
+
+    #[test]
+    fn test_approach_only_within_range() {
+        let mut map = test_map();
+        map.add_npc(
+            NpcPlacement::new("merchant", 1, 6, 0, Direction::Down)
+                .with_behavior(NpcBehavior::Approach { range: 3 }),
+        );
+
+        tick_npcs(&mut map, (0, 4), 42, 0);
+        assert_eq!(npc_positions(&map), vec![(6, 0)]);
+
+        tick_npcs(&mut map, (6, 3), 42, 1);
+        assert_eq!(npc_positions(&map), vec![(6, 1)]);
+        tick_npcs(&mut map, (6, 3), 42, 2);
+        assert_eq!(npc_positions(&map), vec![(6, 2)]);
+        tick_npcs(&mut map, (6, 3), 42, 3);
+        assert_eq!(npc_positions(&map), vec![(6, 2)]);
+    }
+
+    #[test]
+    fn test_npcs_dont_walk_into_each_other() {
+        let mut map = test_map();
+        map.add_npc(
+            NpcPlacement::new("dog", 1, 0, 4, Direction::Up).with_behavior(NpcBehavior::Follow),
+        );
+        map.add_npc(NpcPlacement::new("guard", 1, 1, 4, Direction::Up));
+
+        for tick in 0..10 {
+            tick_npcs(&mut map, (3, 4), 42, tick);
+        }
+
+        // Around the guard, not through them
+        assert_eq!(npc_positions(&map), vec![(2, 4), (1, 4)]);
+    }
+}
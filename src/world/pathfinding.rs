@@ -0,0 +1,201 @@
+//! Shortest paths over a map's tiles, for anything that walks around on its own
+use crate::world::map::{Ability, Map};
+use crate::world::navigation::Direction;
+use std::collections::VecDeque;
+
+/// Finds a shortest path from `start` to `goal`, moving one tile up, down, left, or right at a
+/// time, over tiles that are passable with `abilities` and not in `occupied`.
+///
+/// The path leaves out `start` and ends on `goal`. It's empty if `start` is the goal, and
+/// `None` if the goal can't be reached.
+pub fn find_path(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    abilities: &[Ability],
+    occupied: &[(usize, usize)],
+) -> Option<Vec<(usize, usize)>> {
+    find_path_where(map, start, abilities, occupied, |tile| tile == goal)
+}
+
+/// Like `find_path`, but to the nearest tile that `is_goal` accepts.
+///
+/// Ties are broken the same way every time (neighbours are searched in `Direction::ALL`'s
+/// order), so anything following the path moves deterministically.
+pub fn find_path_where(
+    map: &Map,
+    start: (usize, usize),
+    abilities: &[Ability],
+    occupied: &[(usize, usize)],
+    is_goal: impl Fn((usize, usize)) -> bool,
+) -> Option<Vec<(usize, usize)>> {
+    let (width, height) = (map.get_width(), map.get_height());
+    if start.0 >= width || start.1 >= height {
+        return None;
+    }
+
+    // Where each visited tile was reached from
+    let mut came_from: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; width]; height];
+    came_from[start.1][start.0] = Some(start);
+
+    let mut queue = VecDeque::from([start]);
+    while let Some(tile) = queue.pop_front() {
+        if is_goal(tile) {
+            let mut path = Vec::new();
+            let mut current = tile;
+            while current != start {
+                path.push(current);
+                current = came_from[current.1][current.0].unwrap();
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for direction in Direction::ALL {
+            let x = tile.0 as isize + direction.dx();
+            let y = tile.1 as isize + direction.dy();
+            if !map.in_bounds(x, y) {
+                continue;
+            }
+
+            let next = (x as usize, y as usize);
+            if came_from[next.1][next.0].is_some()
+                || occupied.contains(&next)
+                || !map.get_tile(next.0, next.1).is_passable(abilities)
+            {
+                continue;
+            }
+
+            came_from[next.1][next.0] = Some(tile);
+            queue.push_back(next);
+        }
+    }
+
+    None
+}
+
+/// How many steps it takes to walk from `start` to every tile of the map, over tiles that are
+/// passable with `abilities`. Indexed by [y][x]; `None` where the tile can't be reached.
+pub fn distances_from(
+    map: &Map,
+    start: (usize, usize),
+    abilities: &[Ability],
+) -> Vec<Vec<Option<usize>>> {
+    let mut distances = vec![vec![None; map.get_width()]; map.get_height()];
+    if !map.in_bounds(start.0 as isize, start.1 as isize) {
+        return distances;
+    }
+    distances[start.1][start.0] = Some(0);
+
+    let mut queue = VecDeque::from([start]);
+    while let Some(tile) = queue.pop_front() {
+        let distance = distances[tile.1][tile.0].unwrap();
+        for direction in Direction::ALL {
+            let x = tile.0 as isize + direction.dx();
+            let y = tile.1 as isize + direction.dy();
+            if !map.in_bounds(x, y) {
+                continue;
+            }
+
+            let (x, y) = (x as usize, y as usize);
+            if distances[y][x].is_none() && map.get_tile(x, y).is_passable(abilities) {
+                distances[y][x] = Some(distance + 1);
+                queue.push_back((x, y));
+            }
+        }
+    }
+
+    distances
+}
+
+/// The number of steps between two tiles, ignoring anything in the way
+pub fn manhattan_distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::map::TileType;
+
+    // A 5x3 map of grass, with a wall down the middle that has a gap at the bottom
+    fn test_map() -> Map {
+        let mut map = Map::new(5, 3);
+        for y in 0..3 {
+            for x in 0..5 {
+                map.set_tile(x, y, TileType::Grass);
+            }
+        }
+        map.set_tile(2, 0, TileType::Wall);
+        map.set_tile(2, 1, TileType::Wall);
+        map
+    }
+
+    #[test]
+    fn test_find_path_around_walls() {
+        let map = test_map();
+
+        let path = find_path(&map, (0, 0), (4, 0), &[], &[]).unwrap();
+
+        assert_eq!(
+            path,
+            vec![
+                (0, 1),
+                (0, 2),
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (3, 1),
+                (3, 0),
+                (4, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_path_to_start_is_empty() {
+        let map = test_map();
+        assert_eq!(find_path(&map, (1, 1), (1, 1), &[], &[]), Some(vec![]));
+    }
+
+    #[test]
+    fn test_find_path_blocked() {
+        let mut map = test_map();
+        map.set_tile(2, 2, TileType::Water);
+
+        assert_eq!(find_path(&map, (0, 0), (4, 0), &[], &[]), None);
+        assert_eq!(
+            find_path(&map, (0, 0), (4, 0), &[Ability::Swim], &[]).map(|path| path.len()),
+            Some(8)
+        );
+
+        // Someone standing in the gap blocks it just as well
+        map.set_tile(2, 2, TileType::Grass);
+        assert_eq!(find_path(&map, (0, 0), (4, 0), &[], &[(2, 2)]), None);
+    }
+
+    #[test]
+    fn test_distances_from() {
+        let map = test_map();
+
+        let distances = distances_from(&map, (0, 0), &[]);
+
+        assert_eq!(distances[0][0], Some(0));
+        assert_eq!(distances[2][2], Some(4));
+        assert_eq!(distances[0][4], Some(8));
+        assert_eq!(distances[0][2], None); // A wall
+    }
+
+    #[test]
+    fn test_find_path_where() {
+        let map = test_map();
+
+        // The nearest tile next to (4, 1)
+        let path = find_path_where(&map, (0, 2), &[], &[(4, 1)], |tile| {
+            manhattan_distance(tile, (4, 1)) == 1
+        })
+        .unwrap();
+
+        assert_eq!(path, vec![(1, 2), (2, 2), (3, 2), (3, 1)]);
+    }
+}
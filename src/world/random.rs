@@ -0,0 +1,39 @@
+//! Randomness in the world, derived from the player's seed.
+//!
+//! Each roll gets its own RNG, made from the seed, the tick it happens on, and which stream
+//! it's for (e.g. which NPC is moving), so the same save always plays out the same way.
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The stream random encounters are rolled on. NPCs use their index on the map.
+pub const ENCOUNTER_STREAM: u64 = u64::MAX;
+
+pub fn tick_rng(seed: u64, tick: u64, stream: u64) -> StdRng {
+    StdRng::seed_from_u64(mix(seed, tick, stream))
+}
+
+/// Mixes the seed with the tick and stream (splitmix64's finalizer)
+fn mix(seed: u64, tick: u64, stream: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add(tick.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add(stream.wrapping_mul(0xD1B5_4A32_D192_ED03));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_tick_rng_is_reproducible() {
+        let roll = |seed, tick, stream| tick_rng(seed, tick, stream).gen::<u64>();
+
+        assert_eq!(roll(42, 7, 0), roll(42, 7, 0));
+        assert_ne!(roll(42, 7, 0), roll(43, 7, 0));
+        assert_ne!(roll(42, 7, 0), roll(42, 8, 0));
+        assert_ne!(roll(42, 7, 0), roll(42, 7, 1));
+    }
+}
@@ -0,0 +1,118 @@
+//! Triggers are regions of a map that fire an event when the player steps into them,
+//! or inspects them (presses 'x' while facing them).
+use serde::{Deserialize, Serialize};
+
+/// What happens when a trigger fires
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerEvent {
+    StartDialogue { root_name: String },
+    StartBattle { npc_id: i32 },
+    OpenBookBuilder,
+    Warp { map: String, spawn: String }, // To a named spawn point
+    AdvanceStage { epic: String, stage: String },
+}
+
+/// How the player sets a trigger off
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerActivation {
+    Enter,   // Stepping onto any tile in the area
+    Inspect, // Facing any tile in the area, and pressing 'x'
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerFrequency {
+    Once, // Only ever fires once per player, even across sessions
+    Repeat,
+}
+
+/// A rectangle of tiles, with (x, y) as the top left corner
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerArea {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl TriggerArea {
+    /// An area covering a single tile
+    pub fn tile(x: usize, y: usize) -> Self {
+        TriggerArea {
+            x,
+            y,
+            width: 1,
+            height: 1,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trigger {
+    pub id: String, // Unique within its map. Used to remember which triggers have fired.
+    pub area: TriggerArea,
+    pub activation: TriggerActivation,
+    pub frequency: TriggerFrequency,
+    pub event: TriggerEvent,
+}
+
+impl Trigger {
+    pub fn new(
+        id: &str,
+        area: TriggerArea,
+        activation: TriggerActivation,
+        frequency: TriggerFrequency,
+        event: TriggerEvent,
+    ) -> Self {
+        Trigger {
+            id: id.to_string(),
+            area,
+            activation,
+            frequency,
+            event,
+        }
+    }
+
+    pub fn is_activated_by(&self, x: usize, y: usize, activation: TriggerActivation) -> bool {
+        self.activation == activation && self.area.contains(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_area_contains() {
+        let area = TriggerArea {
+            x: 2,
+            y: 3,
+            width: 2,
+            height: 1,
+        };
+
+        assert!(area.contains(2, 3));
+        assert!(area.contains(3, 3));
+        assert!(!area.contains(4, 3));
+        assert!(!area.contains(2, 4));
+        assert!(!area.contains(1, 3));
+    }
+
+    #[test]
+    fn test_is_activated_by() {
+        let trigger = Trigger::new(
+            "sign",
+            TriggerArea::tile(1, 1),
+            TriggerActivation::Inspect,
+            TriggerFrequency::Repeat,
+            TriggerEvent::OpenBookBuilder,
+        );
+
+        assert!(trigger.is_activated_by(1, 1, TriggerActivation::Inspect));
+        assert!(!trigger.is_activated_by(1, 1, TriggerActivation::Enter));
+        assert!(!trigger.is_activated_by(0, 1, TriggerActivation::Inspect));
+    }
+}
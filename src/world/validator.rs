@@ -0,0 +1,413 @@
+//! Checks that the maps hang together, so that a broken map is caught before anyone plays it:
+//! maps that don't load (e.g. an unknown tile code), spawn points inside walls, triggers,
+//! NPCs, or portals that can't be reached, and portals to maps or spawns that don't exist.
+//!
+//! Run with `--check-maps`, and by the test suite on every map in `MAPS_DIR`.
+use crate::world::atlas::{World, WorldError};
+use crate::world::interaction::ContentKind;
+use crate::world::map::{Map, TileType};
+use crate::world::navigation::Direction;
+use crate::world::trigger::{TriggerActivation, TriggerArea, TriggerEvent};
+use std::fmt;
+use std::io;
+
+/// Something wrong with a map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapProblem {
+    /// The map file couldn't be loaded
+    Unreadable(String),
+    /// A tile that only the game places, like the player, is in the map itself
+    ReservedTile {
+        x: usize,
+        y: usize,
+        tile: TileType,
+    },
+    NoSpawns,
+    SpawnNotPassable {
+        spawn: String,
+        x: usize,
+        y: usize,
+    },
+    /// Nothing at (x, y) can be walked to, or stood next to, from any spawn
+    Unreachable {
+        what: String,
+        x: usize,
+        y: usize,
+    },
+    MissingPortalTarget {
+        trigger: String,
+        error: String,
+    },
+    /// A content entry on a tile it does nothing on, e.g. text on a chest
+    MisplacedContent {
+        id: String,
+        tile: TileType,
+    },
+    /// Contents and triggers that only happen once are recorded by id, so they can't share one
+    DuplicateId(String),
+}
+
+impl fmt::Display for MapProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapProblem::Unreadable(error) => write!(f, "can't be loaded: {}", error),
+            MapProblem::ReservedTile { x, y, tile } => write!(
+                f,
+                "{},{} is a {} tile, which can't be placed in a map",
+                x,
+                y,
+                tile.name()
+            ),
+            MapProblem::NoSpawns => write!(f, "has no spawn points, so it can't be entered"),
+            MapProblem::SpawnNotPassable { spawn, x, y } => {
+                write!(f, "spawn {} at {},{} can't be stood on", spawn, x, y)
+            }
+            MapProblem::Unreachable { what, x, y } => {
+                write!(f, "{} at {},{} can't be reached from a spawn", what, x, y)
+            }
+            MapProblem::MissingPortalTarget { trigger, error } => {
+                write!(f, "portal {} leads nowhere: {}", trigger, error)
+            }
+            MapProblem::MisplacedContent { id, tile } => {
+                write!(f, "content {} does nothing on a {} tile", id, tile.name())
+            }
+            MapProblem::DuplicateId(id) => {
+                write!(f, "{} is used for more than one trigger or content", id)
+            }
+        }
+    }
+}
+
+/// Checks a map on its own, i.e. everything except where its portals lead
+pub fn validate_map(map: &Map) -> Vec<MapProblem> {
+    let mut problems = Vec::new();
+
+    for y in 0..map.get_height() {
+        for x in 0..map.get_width() {
+            let tile = map.get_tile(x, y);
+            if matches!(tile, TileType::Player | TileType::PlayerFocus) {
+                problems.push(MapProblem::ReservedTile { x, y, tile });
+            }
+        }
+    }
+
+    if map.get_spawns().is_empty() {
+        problems.push(MapProblem::NoSpawns);
+    }
+    for (spawn, (x, y)) in map.get_spawns() {
+        if !map.get_tile(*x, *y).is_passable(&[]) {
+            problems.push(MapProblem::SpawnNotPassable {
+                spawn: spawn.clone(),
+                x: *x,
+                y: *y,
+            });
+        }
+    }
+
+    let reachable = reachable_tiles(map);
+    let can_stand_on = |x: usize, y: usize| reachable[y][x];
+    let can_stand_next_to = |x: usize, y: usize| {
+        Direction::ALL.iter().any(|direction| {
+            let (nx, ny) = (x as isize + direction.dx(), y as isize + direction.dy());
+            map.in_bounds(nx, ny) && reachable[ny as usize][nx as usize]
+        })
+    };
+    let in_area = |area: &TriggerArea, check: &dyn Fn(usize, usize) -> bool| {
+        (area.y..area.y + area.height).any(|y| (area.x..area.x + area.width).any(|x| check(x, y)))
+    };
+
+    for trigger in map.get_triggers() {
+        let what = match trigger.event {
+            TriggerEvent::Warp { .. } => format!("portal {}", trigger.id),
+            _ => format!("trigger {}", trigger.id),
+        };
+        let reached = match trigger.activation {
+            TriggerActivation::Enter => in_area(&trigger.area, &can_stand_on),
+            TriggerActivation::Inspect => in_area(&trigger.area, &can_stand_next_to),
+        };
+        if !reached {
+            problems.push(MapProblem::Unreachable {
+                what,
+                x: trigger.area.x,
+                y: trigger.area.y,
+            });
+        }
+    }
+
+    for npc in map.get_npcs() {
+        if !can_stand_next_to(npc.x, npc.y) {
+            problems.push(MapProblem::Unreachable {
+                what: format!("NPC {}", npc.id),
+                x: npc.x,
+                y: npc.y,
+            });
+        }
+    }
+
+    for content in map.get_contents() {
+        let tile = map.get_tile(content.x, content.y);
+        let fits = match content.kind {
+            ContentKind::Text(_) => matches!(tile, TileType::Sign | TileType::Bookshelf),
+            ContentKind::Item { .. } | ContentKind::Page(_) => {
+                matches!(tile, TileType::Chest | TileType::Bookshelf)
+            }
+            ContentKind::Key(_) => tile == TileType::LockedDoor,
+        };
+        if !fits {
+            problems.push(MapProblem::MisplacedContent {
+                id: content.id.clone(),
+                tile,
+            });
+        } else if !can_stand_next_to(content.x, content.y) {
+            problems.push(MapProblem::Unreachable {
+                what: format!("content {}", content.id),
+                x: content.x,
+                y: content.y,
+            });
+        }
+    }
+
+    let mut ids: Vec<&str> = map
+        .get_triggers()
+        .iter()
+        .map(|trigger| trigger.id.as_str())
+        .chain(map.get_contents().iter().map(|content| content.id.as_str()))
+        .collect();
+    ids.sort();
+    for pair in ids.windows(2) {
+        if pair[0] == pair[1] && !problems.contains(&MapProblem::DuplicateId(pair[0].to_string())) {
+            problems.push(MapProblem::DuplicateId(pair[0].to_string()));
+        }
+    }
+
+    problems
+}
+
+/// Checks every map in the world, including where their portals lead. Returns each map that
+/// has problems, by name, in the order of `World::map_names`.
+pub fn validate_world(world: &mut World) -> io::Result<Vec<(String, Vec<MapProblem>)>> {
+    let mut reports = Vec::new();
+
+    for name in world.map_names()? {
+        let map = match world.map(&name) {
+            Ok(map) => map.clone(),
+            Err(e) => {
+                reports.push((name, vec![MapProblem::Unreadable(e.to_string())]));
+                continue;
+            }
+        };
+
+        let mut problems = validate_map(&map);
+        for trigger in map.get_triggers() {
+            if let TriggerEvent::Warp { map, spawn } = &trigger.event {
+                // A target map that doesn't load is reported against that map, too
+                if let Err(e) = world.spawn_point(map, spawn) {
+                    let error = match e {
+                        WorldError::MapFile(_) | WorldError::MismatchedName { .. } => {
+                            format!("the {} map can't be loaded", map)
+                        }
+                        _ => e.to_string(),
+                    };
+                    problems.push(MapProblem::MissingPortalTarget {
+                        trigger: trigger.id.clone(),
+                        error,
+                    });
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            reports.push((name, problems));
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Which tiles the player can walk to from any spawn point. Doors count as passable, since
+/// the player can open them.
+fn reachable_tiles(map: &Map) -> Vec<Vec<bool>> {
+    let (width, height) = (map.get_width(), map.get_height());
+    let mut reachable = vec![vec![false; width]; height];
+    let walkable = |x: usize, y: usize| {
+        let tile = map.get_tile(x, y);
+        tile.is_passable(&[]) || matches!(tile, TileType::Door | TileType::LockedDoor)
+    };
+
+    let mut stack: Vec<(usize, usize)> = map
+        .get_spawns()
+        .values()
+        .copied()
+        .filter(|(x, y)| walkable(*x, *y))
+        .collect();
+    for (x, y) in &stack {
+        reachable[*y][*x] = true;
+    }
+
+    while let Some((x, y)) = stack.pop() {
+        for direction in Direction::ALL {
+            let (nx, ny) = (x as isize + direction.dx(), y as isize + direction.dy());
+            if !map.in_bounds(nx, ny) {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if !reachable[ny][nx] && walkable(nx, ny) {
+                reachable[ny][nx] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::interaction::TileContent;
+    use crate::world::map_format::parse_map;
+
+    // A room with a wall down the middle, and a door in it
+    const ROOM: &str = "\
+[map]
+name = room
+
+[legend]
+'#' = wall
+'.' = grass
+'+' = door
+'~' = water
+'?' = sign
+
+[layer ground]
+#######
+#..#~?#
+#..+~.#
+#######
+
+[spawns]
+start = 1,1
+
+[triggers]
+book = 2,2 enter once book_builder
+shore = 5,2 enter repeat warp room start
+
+[npcs]
+guard = 1 2,1 down
+";
+
+    fn room() -> Map {
+        parse_map(ROOM).unwrap()
+    }
+
+    #[test]
+    fn test_valid_map() {
+        let mut map = room();
+        map.set_tile(4, 2, TileType::Bridge);
+
+        assert_eq!(validate_map(&map), vec![]);
+    }
+
+    #[test]
+    fn test_unreachable_things() {
+        let mut map = room();
+        map.add_content(TileContent::new(
+            "note",
+            5,
+            1,
+            ContentKind::Text("Hello".to_string()),
+        ));
+
+        let problems = validate_map(&map);
+
+        // Past the water; the door doesn't count as a wall
+        assert_eq!(
+            problems,
+            vec![
+                MapProblem::Unreachable {
+                    what: "portal shore".to_string(),
+                    x: 5,
+                    y: 2
+                },
+                MapProblem::Unreachable {
+                    what: "content note".to_string(),
+                    x: 5,
+                    y: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_spawns() {
+        let mut map = room();
+        map.add_spawn("stuck", 0, 0);
+
+        assert!(validate_map(&map).contains(&MapProblem::SpawnNotPassable {
+            spawn: "stuck".to_string(),
+            x: 0,
+            y: 0
+        }));
+
+        let map = Map::new(2, 2);
+        assert_eq!(validate_map(&map), vec![MapProblem::NoSpawns]);
+    }
+
+    #[test]
+    fn test_reserved_tiles() {
+        let mut map = room();
+        map.set_tile(4, 2, TileType::Bridge);
+        map.set_tile(1, 2, TileType::Player);
+
+        assert_eq!(
+            validate_map(&map),
+            vec![MapProblem::ReservedTile {
+                x: 1,
+                y: 2,
+                tile: TileType::Player
+            }]
+        );
+    }
+
+    #[test]
+    fn test_contents() {
+        let mut map = room();
+        map.set_tile(4, 2, TileType::Bridge);
+        map.add_content(TileContent::new(
+            "book",
+            5,
+            1,
+            ContentKind::Text("Shared an id".to_string()),
+        ));
+        map.add_content(TileContent::new(
+            "key",
+            5,
+            1,
+            ContentKind::Key("iron key".to_string()),
+        ));
+
+        assert_eq!(
+            validate_map(&map),
+            vec![
+                MapProblem::MisplacedContent {
+                    id: "key".to_string(),
+                    tile: TileType::Sign
+                },
+                MapProblem::DuplicateId("book".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_problem_messages() {
+        assert_eq!(
+            MapProblem::SpawnNotPassable {
+                spawn: "start".to_string(),
+                x: 1,
+                y: 2
+            }
+            .to_string(),
+            "spawn start at 1,2 can't be stood on"
+        );
+    }
+}
@@ -1,75 +1,341 @@
-use crate::terminal_utils;
-use crate::world::map::{tile_to_char, Map, TileType};
+use crate::tui::backend::{Backend, TerminalBackend};
+use crate::tui::frame::{Frame, Style};
+use crate::world::camera::Camera;
+use crate::world::fog::{Fog, TileVisibility};
+use crate::world::map::Map;
+use crate::world::minimap::{minimap, world_map};
 use crate::world::navigation::Direction;
-use crossterm::terminal::{self, size as terminal_size};
+use crossterm::terminal::size as terminal_size;
 
 pub struct Viewport {
     pub width: usize,
     pub height: usize,
+    camera: Camera,
+    backend: TerminalBackend, // Knows what's on the screen, so only what changed gets redrawn
+    show_minimap: bool,
+    message_line: bool, // Whether frames have a line under the map for messages
+    fired_triggers: Vec<String>, // Left off the minimap, if they only fire once
 }
 
+// Rows of the terminal that aren't map: the message line, and one spare
 const HEIGHT_ADJUSTMENT: usize = 2;
+const MAX_SIZE: u16 = 2000;
+
+/// How much of a terminal of the given size the map gets
+fn map_area(term_width: u16, term_height: u16) -> (usize, usize) {
+    let width = term_width.min(MAX_SIZE) as usize;
+    // Very short terminals have no room for the map at all
+    let height = (term_height.min(MAX_SIZE) as usize).saturating_sub(HEIGHT_ADJUSTMENT);
+    (width, height)
+}
 
 impl Viewport {
-    // FIXME: store the -2 adjustment we have to do to height as a constant
     pub fn new() -> Self {
         let (term_width, term_height) = terminal_size().unwrap_or((80, 24)); // Default to 80x24 if it fails
-        let max_size = 2000;
+        let (width, height) = map_area(term_width, term_height);
 
-        let width = std::cmp::min(term_width as usize, max_size);
-        let height = std::cmp::min(term_height as usize, max_size) - HEIGHT_ADJUSTMENT as usize;
+        Viewport {
+            width,
+            height,
+            camera: Camera::new(),
+            backend: TerminalBackend::stdout().with_hidden_cursor(),
+            show_minimap: true,
+            message_line: true,
+            fired_triggers: Vec::new(),
+        }
+    }
 
-        Viewport { width, height }
+    /// Lets the player move around within a box in the middle of the screen without the map
+    /// scrolling, which also means less to redraw
+    pub fn with_dead_zone(mut self, width: usize, height: usize) -> Self {
+        self.camera = self.camera.with_dead_zone(width, height);
+        self
     }
 
-    pub fn update_size(&mut self) {
-        terminal_utils::clear_console(None);
+    /// Leaves the message line out of frames, for when messages are shown somewhere else
+    /// (like the navigation screen's message log)
+    pub fn without_message_line(mut self) -> Self {
+        self.message_line = false;
+        self
+    }
+
+    /// Which of the map's triggers the player has already fired, so the ones that only fire
+    /// once aren't pointed out any more
+    pub fn set_fired_triggers(&mut self, fired: &[String]) {
+        self.fired_triggers = fired.to_vec();
+    }
+
+    pub fn toggle_minimap(&mut self) {
+        self.show_minimap = !self.show_minimap;
+    }
+
+    /// Snaps the camera back onto the player, e.g. when they've warped somewhere else
+    pub fn reset_camera(&mut self) {
+        self.camera.reset();
+    }
 
+    /// Picks up the terminal's size. If it changed, the next render redraws everything.
+    pub fn update_size(&mut self) {
         if let Ok((w, h)) = terminal_size() {
-            let max_size = 2000;
-            self.width = w.min(max_size) as usize;
-            self.height = (h.min(max_size) - HEIGHT_ADJUSTMENT as u16) as usize;
+            self.resize(w, h);
         }
     }
 
-    pub fn render(&self, map: &Map, player_x: usize, player_y: usize, player_direction: Direction) {
-        terminal::disable_raw_mode().unwrap();
+    /// Fits the map to a terminal of the given size, e.g. for drawing frames off-screen
+    pub fn with_size(mut self, term_width: u16, term_height: u16) -> Self {
+        self.resize(term_width, term_height);
+        self
+    }
 
-        let start_x = player_x.saturating_sub(self.width / 2);
-        let start_y = player_y.saturating_sub(self.height / 2);
-        let end_x = (start_x + self.width).min(map.get_width());
-        let end_y = (start_y + self.height).min(map.get_height());
+    fn resize(&mut self, term_width: u16, term_height: u16) {
+        let (width, height) = map_area(term_width, term_height);
+        self.fit(width, height);
+    }
 
-        for y in start_y..end_y {
-            for x in start_x..end_x {
-                let tile = if x == player_x && y == player_y {
-                    TileType::Player
-                } else if x as isize == player_x as isize + player_direction.dx()
-                    && y as isize == player_y as isize + player_direction.dy()
-                {
-                    TileType::PlayerFocus
-                } else {
-                    map.get_tile(x, y)
-                };
+    /// Sets the size of the map itself, e.g. to fill a pane of the screen
+    pub fn fit(&mut self, width: usize, height: usize) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.backend.invalidate();
+        }
+    }
 
-                print!("{}", tile_to_char(tile, Some(player_direction)));
+    /// Draws the part of the map around the player into a frame, with the message (if any)
+    /// on the line under it. Moves the camera along with the player.
+    ///
+    /// With fog of war, tiles the player remembers are dimmed, and ones they've never seen
+    /// are left blank.
+    pub fn draw(
+        &mut self,
+        map: &Map,
+        player_x: usize,
+        player_y: usize,
+        player_direction: Direction,
+        message: Option<&str>,
+        fog: Option<&Fog>,
+    ) -> Frame {
+        let mut frame = self.draw_cells(map, (player_x, player_y), message, |x, y| {
+            match fog.map_or(TileVisibility::Visible, |fog| fog.visibility(x, y)) {
+                TileVisibility::Visible => (
+                    map.char_at(x, y, player_x, player_y, player_direction),
+                    Style::Normal,
+                ),
+                TileVisibility::Remembered => (map.terrain_char_at(x, y), Style::Dim),
+                TileVisibility::Unseen => (' ', Style::Normal),
             }
-            println!();
+        });
+
+        // In the top right corner, as long as it leaves most of the screen to the map
+        if self.show_minimap {
+            let minimap = minimap(
+                map,
+                fog,
+                &self.fired_triggers,
+                (player_x, player_y),
+                player_direction,
+            );
+            if minimap.get_width() * 2 <= self.width && minimap.get_height() * 2 <= self.height {
+                frame.blit(self.width - minimap.get_width(), 0, &minimap);
+            }
+        }
+
+        frame
+    }
+
+    /// Draws the part of the map around `focus`, with `cell` deciding how each map tile
+    /// looks, and the message (if any) on the line under it. Moves the camera along with
+    /// the focus.
+    pub fn draw_cells(
+        &mut self,
+        map: &Map,
+        focus: (usize, usize),
+        message: Option<&str>,
+        cell: impl Fn(usize, usize) -> (char, Style),
+    ) -> Frame {
+        let mut frame = Frame::new(self.width, self.height + self.message_line as usize);
+
+        let view = self.camera.follow(map, focus, self.width, self.height);
+        for screen_y in 0..self.height {
+            for screen_x in 0..self.width {
+                if let Some((x, y)) = view.map_tile(screen_x, screen_y) {
+                    let (c, style) = cell(x, y);
+                    frame.set_styled(screen_x, screen_y, c, style);
+                }
+            }
+        }
+
+        if let Some(message) = message.filter(|_| self.message_line) {
+            frame.print(0, self.height, message);
         }
 
-        terminal::enable_raw_mode().unwrap();
+        frame
+    }
+
+    /// Draws the world map over the whole screen, including the message line
+    pub fn render_world_map(
+        &mut self,
+        map: &Map,
+        player_x: usize,
+        player_y: usize,
+        player_direction: Direction,
+        fog: Option<&Fog>,
+    ) {
+        let frame = world_map(
+            map,
+            fog,
+            &self.fired_triggers,
+            (player_x, player_y),
+            player_direction,
+            self.width,
+            self.height + 1,
+        );
+        self.present(frame);
+    }
+
+    /// Draws the map to the terminal, only writing the cells that changed since the last
+    /// render. Expects raw mode to be on.
+    pub fn render(
+        &mut self,
+        map: &Map,
+        player_x: usize,
+        player_y: usize,
+        player_direction: Direction,
+        message: Option<&str>,
+        fog: Option<&Fog>,
+    ) {
+        let frame = self.draw(map, player_x, player_y, player_direction, message, fog);
+        self.present(frame);
+    }
+
+    /// Forgets what's on the screen, so the next render redraws everything, e.g. after
+    /// something else wrote to the terminal
+    pub fn invalidate(&mut self) {
+        self.backend.invalidate();
+    }
+
+    /// Writes the frame to the terminal, diffed against the last one
+    pub fn present(&mut self, frame: Frame) {
+        self.backend.draw(&frame).unwrap();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::world::fog::Explored;
+    use crate::world::map::TileType;
+    use crate::world::npc::NpcPlacement;
 
     #[test]
     fn test_create_viewport() {}
 
+    fn viewport(width: usize, height: usize) -> Viewport {
+        Viewport {
+            width,
+            height,
+            camera: Camera::new(),
+            backend: TerminalBackend::stdout(),
+            show_minimap: false,
+            message_line: true,
+            fired_triggers: Vec::new(),
+        }
+    }
+
+    // A 4x3 map of grass, walled in on the right, with someone in the bottom left
+    fn test_map() -> Map {
+        let mut map = Map::new(4, 3);
+        for y in 0..3 {
+            for x in 0..4 {
+                map.set_tile(x, y, TileType::Grass);
+            }
+            map.set_tile(3, y, TileType::Wall);
+        }
+        map.add_npc(NpcPlacement::new("guard", 1, 0, 2, Direction::Up).with_sprite('G'));
+        map
+    }
+
+    #[test]
+    fn test_draw() {
+        let frame = viewport(6, 3).draw(&test_map(), 1, 1, Direction::Right, Some("Hello"), None);
+
+        // Narrower than the screen, so it's centered
+        assert_eq!(frame.row(0), " ...# ");
+        assert_eq!(frame.row(1), " .█▶# ");
+        assert_eq!(frame.row(2), " G..# ");
+        assert_eq!(frame.row(3), "Hello ");
+    }
+
+    #[test]
+    fn test_moving_only_redraws_what_changed() {
+        let map = test_map();
+        let mut viewport = viewport(6, 3);
+
+        let before = viewport.draw(&map, 1, 1, Direction::Right, None, None);
+        let after = viewport.draw(&map, 1, 1, Direction::Down, None, None);
+
+        let changed: Vec<(usize, usize)> = after
+            .diff(Some(&before))
+            .iter()
+            .map(|change| (change.x, change.y))
+            .collect();
+        assert_eq!(changed, vec![(3, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_map_area() {
+        assert_eq!(map_area(80, 24), (80, 22));
+        assert_eq!(map_area(80, 1), (80, 0));
+        assert_eq!(map_area(5000, 5000), (2000, 1998));
+    }
+
+    #[test]
+    fn test_draw_on_a_tiny_terminal() {
+        let frame = viewport(6, 0).draw(&test_map(), 1, 1, Direction::Right, Some("Hello"), None);
+
+        assert_eq!(frame.get_height(), 1);
+        assert_eq!(frame.row(0), "Hello ");
+    }
+
+    #[test]
+    fn test_draw_with_fog() {
+        let mut map = test_map();
+        map.set_tile(1, 2, TileType::Wall); // Hides the guard's corner from (2, 1)
+        let mut fog = Fog::new(Explored::new(4, 3));
+
+        // Nothing seen yet, so nothing to draw
+        let frame = viewport(6, 3).draw(&map, 2, 1, Direction::Right, None, Some(&fog));
+        assert_eq!(frame.row(2), "      ");
+
+        fog.update(&map, (0, 1));
+        fog.update(&map, (2, 1));
+        let frame = viewport(6, 3).draw(&map, 2, 1, Direction::Right, None, Some(&fog));
+
+        // The guard's corner is remembered, dimmed, and without the guard in it
+        assert_eq!(frame.row(2), " .#.# ");
+        assert_eq!(frame.get_styled(1, 2), Some(('.', Style::Dim)));
+        assert_eq!(frame.get_styled(2, 2), Some(('#', Style::Normal)));
+    }
+
+    #[test]
+    fn test_draw_minimap() {
+        let map = test_map();
+        let mut viewport = viewport(20, 10);
+        viewport.toggle_minimap();
+
+        let frame = viewport.draw(&map, 1, 1, Direction::Right, None, None);
+
+        // The 4x3 map fits in the minimap unscaled
+        assert_eq!(frame.row(0), "              ┌────┐");
+        assert_eq!(frame.row(2).chars().skip(14).collect::<String>(), "│.▶.#│");
+
+        // It doesn't fit on a small screen
+        let mut viewport = self::viewport(10, 5);
+        viewport.toggle_minimap();
+        let frame = viewport.draw(&map, 1, 1, Direction::Right, None, None);
+        assert!(!frame.row(0).contains('┌'));
+    }
+
     // TODO: Add tests! For everything!
-    // - create viewport
-    // - render
     // - update size
 }
@@ -0,0 +1,113 @@
+#[cfg(test)]
+use tbg::models::dialogue::character_type::CharacterType;
+use tbg::models::dialogue::dialogue::{self, Dialogue};
+use tbg::models::dialogue::dialogue_response::DialogueResponse;
+use tbg::models::non_player_character::non_player_character::NPC;
+use tbg::test_utils;
+
+fn root_dialogue(id: u32, root_name: &str) -> Dialogue {
+    Dialogue::new(
+        id,
+        1,
+        CharacterType::NonPlayerCharacter,
+        Some(true),
+        Some(root_name.to_string()),
+        "It's a trap!".to_string(),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+#[test]
+fn test_load_dialogue_by_id() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let dialogue = dialogue::load(conn, 1).unwrap().unwrap();
+
+    assert_eq!(dialogue.id, 1);
+    assert!(dialogue.is_root_node());
+    assert!(dialogue::load(conn, 999_999).unwrap().is_none());
+}
+
+#[test]
+fn test_load_dialogue_with_responses() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let dialogue = dialogue::load_by_root_name(conn, "first_quest")
+        .unwrap()
+        .unwrap();
+
+    let responses = dialogue.get_responses().as_ref().unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].text, "No, I'm just looking around.");
+    assert!(responses.iter().all(|r| r.dialogue_id == dialogue.id));
+}
+
+#[test]
+fn test_root_name_with_quotes() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let root_name = "the 'quoted' \"root\"; --";
+    root_dialogue(5000, root_name).create(conn).unwrap();
+
+    let dialogue = dialogue::load_by_root_name(conn, root_name)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(dialogue.id, 5000);
+    assert_eq!(dialogue.get_text(), "It's a trap!");
+}
+
+#[test]
+fn test_injected_root_name_matches_nothing() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    // Interpolated into the query, these would have matched (or broken) the query
+    for root_name in ["' OR '1'='1", "x'; DROP TABLE dialogue; --", "'"] {
+        assert!(dialogue::load_by_root_name(conn, root_name)
+            .unwrap()
+            .is_none());
+    }
+
+    // And nothing was dropped
+    assert!(dialogue::load(conn, 1).unwrap().is_some());
+}
+
+#[test]
+fn test_response_with_quotes() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    root_dialogue(5001, "quoted_responses")
+        .create(conn)
+        .unwrap();
+    DialogueResponse::new(5001, "I'd rather \"not\"".to_string(), 1)
+        .create(conn)
+        .unwrap();
+
+    let dialogue = dialogue::load(conn, 5001).unwrap().unwrap();
+
+    assert_eq!(
+        dialogue.get_responses().as_ref().unwrap()[0].text,
+        "I'd rather \"not\""
+    );
+}
+
+#[test]
+fn test_duplicate_dialogue_id_is_an_error() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    // Dialogue 1 is seeded from the character_creation fixture
+    assert!(root_dialogue(1, "duplicate").create(conn).is_err());
+}
+
+#[test]
+fn test_load_npc_by_id() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let narrator = NPC::load_by_id(conn, 1).unwrap().unwrap();
+
+    assert_eq!(narrator.name, "Narrator");
+    assert!(NPC::load_by_id(conn, 999).unwrap().is_none());
+}
@@ -1,8 +1,13 @@
 use std::time::Duration;
 #[cfg(test)]
 use tbg::game_engine::interface_mode::InterfaceMode;
+use tbg::models::game_state::explored_map::ExploredMap;
+use tbg::models::game_state::fired_trigger::FiredTrigger;
 use tbg::models::game_state::game_state::SqlDuration;
+use tbg::models::game_state::opened_door::OpenedDoor;
 use tbg::test_utils;
+use tbg::world::atlas::STARTING_MAP;
+use tbg::world::fog::Explored;
 use tbg::{world::navigation::Direction, GameState, Player};
 
 // Test saving a new game state
@@ -17,12 +22,13 @@ fn test_save_game_state() {
         tbg::models::player::height::Height::Average,
     );
 
-    let loaded_player = player.create(&conn);
+    let loaded_player = player.create(&conn).unwrap();
 
-    let game_state = GameState::new(loaded_player.id).create(&conn);
+    let game_state = GameState::new(loaded_player.id).create(&conn).unwrap();
 
     assert_eq!(game_state.player_id, 1);
     assert_eq!(game_state.current_stage, "character_creation");
+    assert_eq!(game_state.current_map, STARTING_MAP);
     assert_eq!(game_state.x, 0);
     assert_eq!(game_state.y, 0);
 }
@@ -46,23 +52,25 @@ fn test_update_game_state() {
         tbg::models::player::height::Height::Tall,
     );
 
-    let created_player = player.create(&conn);
+    let created_player = player.create(&conn).unwrap();
 
     let mut game_state = GameState::new(created_player.id);
 
     // Save initial game state
-    game_state.create(&conn);
+    game_state.create(&conn).unwrap();
 
     // Modify game state
     game_state.current_stage = "level_2".to_string();
+    game_state.current_map = "cave".to_string();
     game_state.x = 42;
     game_state.y = 84;
 
     // Update in database
-    let updated_game_state = game_state.update(&conn);
+    let updated_game_state = game_state.update(&conn).unwrap();
 
     // Verify updates persisted
     assert_eq!(updated_game_state.current_stage, "level_2");
+    assert_eq!(updated_game_state.current_map, "cave");
     assert_eq!(updated_game_state.x, 42);
     assert_eq!(updated_game_state.y, 84);
 }
@@ -77,13 +85,14 @@ fn test_load_game_state() {
         tbg::models::player::height::Height::Tall,
     );
 
-    let loaded_player = player.create(&conn);
+    let loaded_player = player.create(&conn).unwrap();
 
     let game_state = GameState {
         player_id: loaded_player.id,
         interface_mode: InterfaceMode::Dialogue,
         current_epic: "level_1".to_string(),
         current_stage: "dungeon_1".to_string(),
+        current_map: STARTING_MAP.to_string(),
         x: 10,
         y: 20,
         direction: Direction::Up,
@@ -93,7 +102,7 @@ fn test_load_game_state() {
     };
 
     // Save game state
-    game_state.create(&conn);
+    game_state.create(&conn).unwrap();
 
     // Load the saved game state
     let loaded_game_state = GameState::load_for_player(&conn, loaded_player.id).unwrap();
@@ -106,3 +115,111 @@ fn test_load_game_state() {
     assert_eq!(loaded_game_state.x, 10);
     assert_eq!(loaded_game_state.y, 20);
 }
+
+// Updating a game state that was never saved is an error, not a panic
+#[test]
+fn test_update_missing_game_state() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    assert!(GameState::new(999).update(&conn).is_err());
+}
+
+#[test]
+fn test_fired_triggers() {
+    let conn = &test_utils::setup_test_db().conn;
+    let player = Player::new(
+        "Test Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Average,
+    )
+    .create(conn)
+    .unwrap();
+
+    assert!(!FiredTrigger::has_fired(conn, player.id, "demo", "book_tutorial").unwrap());
+
+    let fired_trigger = FiredTrigger::new(player.id, "demo", "book_tutorial");
+    fired_trigger.create(conn).unwrap();
+    // Firing again is fine, and doesn't record it twice
+    fired_trigger.create(conn).unwrap();
+
+    assert!(FiredTrigger::has_fired(conn, player.id, "demo", "book_tutorial").unwrap());
+    assert!(!FiredTrigger::has_fired(conn, player.id, "other_map", "book_tutorial").unwrap());
+    assert_eq!(
+        FiredTrigger::load_for_player(conn, player.id)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_explored_maps() {
+    let conn = &test_utils::setup_test_db().conn;
+    let player = Player::new(
+        "Test Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Average,
+    )
+    .create(conn)
+    .unwrap();
+
+    assert!(ExploredMap::load(conn, player.id, "demo")
+        .unwrap()
+        .is_none());
+
+    let mut explored = Explored::new(10, 10);
+    explored.explore(3, 4);
+    ExploredMap::new(player.id, "demo", &explored)
+        .save(conn)
+        .unwrap();
+    // Saving again replaces the record
+    explored.explore(9, 9);
+    ExploredMap::new(player.id, "demo", &explored)
+        .save(conn)
+        .unwrap();
+
+    let loaded = ExploredMap::load(conn, player.id, "demo")
+        .unwrap()
+        .unwrap()
+        .to_explored()
+        .unwrap();
+    assert_eq!(loaded, explored);
+    assert_eq!(
+        ExploredMap::load_for_player(conn, player.id).unwrap().len(),
+        1
+    );
+}
+
+#[test]
+fn test_opened_doors() {
+    let conn = &test_utils::setup_test_db().conn;
+    let player = Player::new(
+        "Test Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Average,
+    )
+    .create(conn)
+    .unwrap();
+
+    let opened_door = OpenedDoor::new(player.id, "demo", 3, 4);
+    opened_door.create(conn).unwrap();
+    // Opening it again doesn't record it twice
+    opened_door.create(conn).unwrap();
+    OpenedDoor::new(player.id, "other_map", 3, 4)
+        .create(conn)
+        .unwrap();
+
+    let opened_doors = OpenedDoor::load_for_map(conn, player.id, "demo").unwrap();
+    assert_eq!(opened_doors.len(), 1);
+    assert_eq!((opened_doors[0].x, opened_doors[0].y), (3, 4));
+
+    // Shutting it forgets it
+    OpenedDoor::delete(conn, player.id, "demo", 3, 4).unwrap();
+    assert!(OpenedDoor::load_for_map(conn, player.id, "demo")
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        OpenedDoor::load_for_player(conn, player.id).unwrap().len(),
+        1
+    );
+}
@@ -0,0 +1,214 @@
+use rusqlite::Connection;
+use std::fs;
+#[cfg(test)]
+use tbg::db::error::DbError;
+use tbg::db::migrations::{latest_version, run_migrations, schema_version, Migration, MIGRATIONS};
+use tbg::db::save::get_save_path;
+use tbg::test_utils;
+use uuid::Uuid;
+
+fn table_exists(conn: &Connection, table_name: &str) -> bool {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        [table_name],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+// Migrating a save with data in it backs it up first, which the tests should clean up after
+fn remove_backups_of(db_name: &str) {
+    for entry in fs::read_dir("saves/backups").unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .to_string_lossy()
+            .contains(db_name.trim_end_matches(".db"))
+        {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
+
+fn create_widgets_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("CREATE TABLE widgets (id INTEGER PRIMARY KEY)", [])?;
+    Ok(())
+}
+
+fn create_widgets_table_then_fail(conn: &Connection) -> rusqlite::Result<()> {
+    create_widgets_table(conn)?;
+    conn.execute("INSERT INTO no_such_table (id) VALUES (1)", [])?;
+    Ok(())
+}
+
+#[test]
+fn test_new_save_is_at_latest_version() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    assert_eq!(schema_version(conn).unwrap(), latest_version());
+}
+
+#[test]
+fn test_pending_migrations_are_applied() {
+    let mut test_db = test_utils::setup_test_db();
+    let save_path = get_save_path(Some(&test_db.test_db_path));
+    let migrations = [Migration {
+        version: latest_version() + 1,
+        description: "Add widgets",
+        up: create_widgets_table,
+    }];
+
+    run_migrations(&mut test_db.conn, &save_path, &migrations).unwrap();
+
+    assert_eq!(schema_version(&test_db.conn).unwrap(), latest_version() + 1);
+    assert!(table_exists(&test_db.conn, "widgets"));
+
+    // Running again is a no-op
+    run_migrations(&mut test_db.conn, &save_path, &migrations).unwrap();
+
+    remove_backups_of(&test_db.test_db_path);
+}
+
+#[test]
+fn test_failed_migration_leaves_save_untouched() {
+    let mut test_db = test_utils::setup_test_db();
+    let save_path = get_save_path(Some(&test_db.test_db_path));
+    let migrations = [Migration {
+        version: latest_version() + 1,
+        description: "Add widgets, badly",
+        up: create_widgets_table_then_fail,
+    }];
+
+    let error = run_migrations(&mut test_db.conn, &save_path, &migrations).unwrap_err();
+
+    match &error {
+        DbError::Migration(e) => {
+            assert_eq!(e.from_version, latest_version());
+            assert_eq!(e.version, latest_version() + 1);
+
+            let backup_path = e.backup_path.as_ref().expect("Expected a backup");
+            assert!(backup_path.exists());
+        }
+        other => panic!("Expected a migration error, got {:?}", other),
+    }
+    assert!(error.to_string().contains("Add widgets, badly"));
+
+    // Rolled back
+    assert_eq!(schema_version(&test_db.conn).unwrap(), latest_version());
+    assert!(!table_exists(&test_db.conn, "widgets"));
+
+    remove_backups_of(&test_db.test_db_path);
+}
+
+#[test]
+fn test_newer_schema_version_is_rejected() {
+    let mut test_db = test_utils::setup_test_db();
+    let save_path = get_save_path(Some(&test_db.test_db_path));
+    test_db
+        .conn
+        .pragma_update(None, "user_version", latest_version() + 1)
+        .unwrap();
+
+    let error = run_migrations(&mut test_db.conn, &save_path, MIGRATIONS).unwrap_err();
+
+    assert!(matches!(error, DbError::UnknownSchemaVersion { .. }));
+}
+
+// What the game gets when the player picks a save from a newer version of TBG
+#[test]
+fn test_opening_newer_save_is_an_error() {
+    let test_db = test_utils::setup_test_db();
+    test_db
+        .conn
+        .pragma_update(None, "user_version", latest_version() + 1)
+        .unwrap();
+
+    let error = tbg::db::connection::get_connection(Some(&test_db.test_db_path)).unwrap_err();
+
+    assert!(matches!(
+        error,
+        DbError::UnknownSchemaVersion { version, .. } if version == latest_version() + 1
+    ));
+    // The message the title menu shows
+    assert!(error
+        .to_string()
+        .ends_with(&format!("only knows up to version {}", latest_version())));
+}
+
+// A save from before schema versioning: the original tables, but version 0
+#[test]
+fn test_unversioned_save_is_migrated() {
+    let db_name = format!("test_{}.db", Uuid::new_v4());
+    let save_path = get_save_path(Some(&db_name));
+    {
+        let mut conn = Connection::open(&save_path).unwrap();
+        run_migrations(&mut conn, &save_path, &MIGRATIONS[..1]).unwrap();
+        conn.pragma_update(None, "user_version", 0).unwrap();
+        conn.execute(
+            "INSERT INTO dialogue (id, character_id, character_type, text) VALUES (1, 1, 'non_player_character', 'Hello!')",
+            [],
+        )
+        .unwrap();
+    }
+
+    let conn = tbg::db::connection::get_connection(Some(&db_name)).unwrap();
+
+    assert_eq!(schema_version(&conn).unwrap(), latest_version());
+    let created_at: Option<String> = conn
+        .query_row("SELECT created_at FROM dialogue WHERE id = 1", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert!(created_at.is_some());
+
+    drop(conn);
+    fs::remove_file(&save_path).unwrap();
+    remove_backups_of(&db_name);
+}
+
+// Rebuilding the pages table keeps the pages that were in it, and the books they're in
+#[test]
+fn test_pages_survive_being_made_nullable() {
+    let db_name = format!("test_{}.db", Uuid::new_v4());
+    let save_path = get_save_path(Some(&db_name));
+    {
+        let mut conn = Connection::open(&save_path).unwrap();
+        run_migrations(&mut conn, &save_path, &MIGRATIONS[..7]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO players (id, name, gender, height, background, main_arc, created_at, updated_at) VALUES (1, 'Test Player', 0, 0, 0, 0, 0, 0);
+             INSERT INTO page_actions (id, name) VALUES (3, 'Strike');
+             INSERT INTO book_passives (id, name) VALUES (1, 'Sturdy');
+             INSERT INTO books (id, player_id, book_art_type, book_passive_id) VALUES (1, 1, 2, 1);
+             INSERT INTO pages (id, player_id, book_art_type, primary_action_id, front, back) VALUES (1, 1, 2, 3, 'Front', 'Back');
+             INSERT INTO book_pages (book_id, page_id, position_index) VALUES (1, 1, 0);",
+        )
+        .unwrap();
+    }
+
+    let conn = tbg::db::connection::get_connection(Some(&db_name)).unwrap();
+
+    let page: (i64, Option<i64>, String) = conn
+        .query_row(
+            "SELECT book_art_type, primary_action_id, front FROM pages WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!(page, (2, Some(3), "Front".to_string()));
+    let book_pages: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM book_pages WHERE page_id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(book_pages, 1);
+    conn.execute(
+        "INSERT INTO pages (player_id, front, back) VALUES (1, 'Found', '')",
+        [],
+    )
+    .unwrap();
+
+    drop(conn);
+    fs::remove_file(&save_path).unwrap();
+    remove_backups_of(&db_name);
+}
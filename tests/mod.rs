@@ -1,3 +1,10 @@
+pub mod dialogue_tests;
 pub mod game_state_tests;
+pub mod migration_tests;
 pub mod player_tests;
+pub mod render_tests;
+pub mod save_document_tests;
+pub mod save_tests;
+pub mod seed_tests;
 pub mod terminal_utils_tests;
+pub mod world_tests;
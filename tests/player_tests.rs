@@ -1,3 +1,5 @@
+use tbg::models::book::page::Page;
+use tbg::models::player::inventory_item::InventoryItem;
 #[cfg(test)]
 use tbg::test_utils;
 use tbg::Player;
@@ -13,7 +15,7 @@ fn test_save_player() {
         tbg::models::player::height::Height::Tall,
     );
 
-    let created_player = player.create(&conn);
+    let created_player = player.create(&conn).unwrap();
 
     assert_eq!(created_player.name, "Test Player");
 }
@@ -39,7 +41,7 @@ fn test_load_player() {
         tbg::models::player::height::Height::Short,
     );
 
-    let created_player = player.create(&conn);
+    let created_player = player.create(&conn).unwrap();
 
     assert_eq!(created_player.name, "Test Player W Gender");
     assert_eq!(
@@ -51,3 +53,92 @@ fn test_load_player() {
         tbg::models::player::height::Height::Short
     );
 }
+
+// Names are bound as parameters, so quotes are stored as-is
+#[test]
+fn test_player_name_with_quotes() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let player = Player::new(
+        "O'Brien \"the Bold\"; DROP TABLE players; --".to_string(),
+        tbg::models::player::gender::Gender::Unspecified,
+        tbg::models::player::height::Height::Average,
+    );
+
+    let created_player = player.create(&conn).unwrap();
+    let loaded_player = Player::load_by_id(&conn, created_player.id)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        loaded_player.name,
+        "O'Brien \"the Bold\"; DROP TABLE players; --"
+    );
+}
+
+// Updating a player that was never saved is an error, not a panic
+#[test]
+fn test_update_missing_player() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let player = Player::new(
+        "Never Saved".to_string(),
+        tbg::models::player::gender::Gender::Male,
+        tbg::models::player::height::Height::Average,
+    );
+
+    assert!(player.update(&conn).is_err());
+}
+
+#[test]
+fn test_inventory() {
+    let conn = &test_utils::setup_test_db().conn;
+    let player = Player::new(
+        "Test Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Short,
+    )
+    .create(conn)
+    .unwrap();
+
+    assert!(!InventoryItem::has(conn, player.id, "gold coin").unwrap());
+
+    InventoryItem::new(player.id, "gold coin", 3)
+        .add(conn)
+        .unwrap();
+    // Adding more of the same item adds to the count
+    InventoryItem::new(player.id, "gold coin", 2)
+        .add(conn)
+        .unwrap();
+    InventoryItem::new(player.id, "iron key", 1)
+        .add(conn)
+        .unwrap();
+
+    assert!(InventoryItem::has(conn, player.id, "gold coin").unwrap());
+    let inventory = InventoryItem::load_for_player(conn, player.id).unwrap();
+    assert_eq!(inventory.len(), 2);
+    assert_eq!(
+        (inventory[0].item.as_str(), inventory[0].count),
+        ("gold coin", 5)
+    );
+}
+
+#[test]
+fn test_found_pages() {
+    let conn = &test_utils::setup_test_db().conn;
+    let player = Player::new(
+        "Test Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Short,
+    )
+    .create(conn)
+    .unwrap();
+
+    let page = Page::found(player.id, "iron key").create(conn).unwrap();
+
+    assert_eq!(page.front, "iron key");
+    assert_eq!(page.primary_action_id, None);
+    assert_eq!(Page::load_for_player(conn, player.id).unwrap(), vec![page]);
+    // Pages aren't items, even when they're named like one
+    assert!(!InventoryItem::has(conn, player.id, "iron key").unwrap());
+}
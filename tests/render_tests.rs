@@ -0,0 +1,317 @@
+//! Snapshot tests for what the TUI draws. Each test draws to an in-memory screen, and checks
+//! its text against a file in `tests/snapshots`.
+//!
+//! To update the snapshots after changing how something looks, run the tests with
+//! `UPDATE_SNAPSHOTS=1`, and check the changes to the files before committing them.
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::Color;
+use std::env;
+use std::fs;
+use tbg::game_engine::interactions::methods::menu_select;
+use tbg::tui::backend::{Backend, MemoryBackend};
+use tbg::tui::frame::{Frame, Style};
+use tbg::tui::layout::{NavigationLayout, Rect};
+use tbg::tui::menu::Menu;
+use tbg::tui::message_log::MessageLog;
+use tbg::tui::sidebar::Status;
+use tbg::tui::size_guard::too_small_screen;
+use tbg::tui::window::Window;
+use tbg::world::fog::{Explored, Fog};
+use tbg::world::map::Map;
+use tbg::world::map_format::parse_map;
+use tbg::world::minimap::world_map;
+use tbg::world::navigation::Direction;
+use tbg::world::viewport::Viewport;
+
+fn assert_snapshot(name: &str, backend: &MemoryBackend) {
+    let path = format!("tests/snapshots/{}.txt", name);
+    let actual = backend.snapshot() + "\n";
+
+    if env::var("UPDATE_SNAPSHOTS").is_ok() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "No snapshot at {}; run with UPDATE_SNAPSHOTS=1 to make it",
+            path
+        )
+    });
+    assert_eq!(actual, expected, "{} doesn't match its snapshot", name);
+}
+
+// A small walled garden, with a pond and a house
+const GARDEN: &str = "\
+[map]
+name = garden
+
+[legend]
+'#' = wall
+'.' = grass
+'~' = water
+'+' = door
+'?' = sign
+
+[layer ground]
+##############
+#....?.......#
+#..~~~...###.#
+#..~~~...#.+.#
+#........###.#
+##############
+
+[spawns]
+start = 2,1
+
+[npcs]
+gardener = 1 7,4 left
+";
+
+fn garden() -> Map {
+    parse_map(GARDEN).unwrap()
+}
+
+#[test]
+fn test_window_snapshot() {
+    let mut backend = MemoryBackend::new(32, 9);
+
+    Window::new("Welcome to\n\x1b[33mthe Book Game\x1b[39m")
+        .render_to(&mut backend)
+        .unwrap();
+
+    assert_snapshot("window", &backend);
+    // The color codes are kept, but take up no room
+    let (x, y) = (9, 4);
+    assert_eq!(backend.screen().get(x, y), Some('t'));
+    assert_eq!(backend.screen().get_color(x, y), Some(Color::DarkYellow));
+}
+
+#[test]
+fn test_window_with_wide_characters_snapshot() {
+    let mut backend = MemoryBackend::new(20, 6);
+
+    Window::new("本を読む\nA book")
+        .render_to(&mut backend)
+        .unwrap();
+
+    assert_snapshot("window_wide", &backend);
+}
+
+#[test]
+fn test_window_too_narrow_for_its_text() {
+    let mut backend = MemoryBackend::new(12, 6);
+
+    // Used to panic, working out the padding; now it's wrapped, and paged
+    Window::new("A line much longer than the window")
+        .render_to(&mut backend)
+        .unwrap();
+
+    assert_snapshot("window_narrow", &backend);
+}
+
+const NARRATION: &str = "You wake in a meadow, with no memory of how you got there. \x1b[33mA \
+    gentle breeze carries the scent of wildflowers,\x1b[39m and somewhere beyond the hills, \
+    a bell is ringing.\n\nA path leads north, towards the village.";
+
+#[test]
+fn test_window_wraps_long_lines_snapshot() {
+    let mut backend = MemoryBackend::new(40, 16);
+
+    Window::new(NARRATION).render_to(&mut backend).unwrap();
+
+    assert_snapshot("window_wrapped", &backend);
+    // The color carries on past the line it starts on
+    let screen = backend.screen();
+    let y = (0..screen.get_height())
+        .find(|&y| screen.row(y).contains("wildflowers"))
+        .unwrap();
+    let x = screen.row(y).chars().position(|c| c == 'w').unwrap();
+    assert_eq!(screen.get_color(x, y), Some(Color::DarkYellow));
+}
+
+#[test]
+fn test_window_pages_snapshot() {
+    let mut backend = MemoryBackend::new(30, 9);
+
+    Window::new(NARRATION).render_to(&mut backend).unwrap();
+    assert_snapshot("window_page_1", &backend);
+
+    // Past the last page just shows the last page
+    Window::new(NARRATION)
+        .with_page(10)
+        .render_to(&mut backend)
+        .unwrap();
+    assert_snapshot("window_page_last", &backend);
+}
+
+#[test]
+fn test_window_in_tiny_frames() {
+    // Too small for anything, but shouldn't panic
+    for (width, height) in [(0, 0), (1, 1), (1, 5), (5, 1)] {
+        let mut frame = Frame::new(width, height);
+        Window::new(NARRATION).draw(&mut frame);
+        assert!((0..height).all(|y| frame.row(y).trim().is_empty()));
+    }
+
+    // Just the corners
+    let mut frame = Frame::new(2, 2);
+    Window::new(NARRATION).draw(&mut frame);
+    assert_eq!(frame.row(0), "╭╮");
+    assert_eq!(frame.row(1), "╰╯");
+}
+
+#[test]
+fn test_menu_snapshot() {
+    let mut backend = MemoryBackend::new(40, 14);
+    let mut menu = Menu::new("Where to?", vec!["The meadow", "The village", "The castle"])
+        .with_disabled(2, "the gate is shut");
+    menu.handle_key(KeyEvent::from(KeyCode::Down));
+
+    menu_select::draw(&mut backend, &menu.render(menu_select::menu_width(40)));
+
+    assert_snapshot("menu", &backend);
+    let screen = backend.screen();
+    let row = (0..14)
+        .find(|y| screen.row(*y).contains("The village"))
+        .unwrap();
+    let x = screen.row(row).chars().position(|c| c == '>').unwrap();
+    assert_eq!(screen.get_styled(x, row), Some(('>', Style::Reverse)));
+}
+
+#[test]
+fn test_wide_menu_snapshot() {
+    let mut backend = MemoryBackend::new(40, 10);
+    // As wide as the window has room for, with the marker and shortcut in front of it
+    let label = "A very long way round, past the mill";
+    let widest = menu_select::menu_width(40) - "> 1. ".len();
+    let menu = Menu::new("Which way?", vec![&label[..widest], "Home"]);
+
+    menu_select::draw(&mut backend, &menu.render(menu_select::menu_width(40)));
+
+    // Still on one line
+    assert_snapshot("menu_wide", &backend);
+}
+
+#[test]
+fn test_filtered_menu_snapshot() {
+    let mut backend = MemoryBackend::new(40, 12);
+    let mut menu = Menu::new("Pick a fruit", vec!["Apple", "Banana", "Cherry"]);
+    menu.handle_key(KeyEvent::from(KeyCode::Char('a')));
+    menu.handle_key(KeyEvent::from(KeyCode::Char('n')));
+
+    menu_select::draw(&mut backend, &menu.render(menu_select::menu_width(40)));
+
+    assert_snapshot("menu_filtered", &backend);
+}
+
+#[test]
+fn test_map_view_snapshot() {
+    let mut backend = MemoryBackend::new(20, 8);
+    let mut viewport = Viewport::new().with_size(20, 8);
+    viewport.toggle_minimap();
+
+    let frame = viewport.draw(
+        &garden(),
+        2,
+        1,
+        Direction::Down,
+        Some("You're in the garden."),
+        None,
+    );
+    backend.draw(&frame).unwrap();
+
+    assert_snapshot("map_view", &backend);
+}
+
+#[test]
+fn test_map_view_with_fog_and_minimap_snapshot() {
+    let map = garden();
+    let mut backend = MemoryBackend::new(40, 20);
+    let mut viewport = Viewport::new().with_size(40, 20);
+    let mut fog = Fog::new(Explored::new(map.get_width(), map.get_height()));
+    fog.update(&map, (10, 3)); // In the house
+    fog.update(&map, (2, 1));
+
+    let frame = viewport.draw(&map, 2, 1, Direction::Right, None, Some(&fog));
+    backend.draw(&frame).unwrap();
+
+    assert_snapshot("map_view_fog", &backend);
+}
+
+#[test]
+fn test_world_map_snapshot() {
+    let mut backend = MemoryBackend::new(30, 12);
+
+    let frame = world_map(&garden(), None, &[], (2, 1), Direction::Up, 30, 12);
+    backend.draw(&frame).unwrap();
+
+    assert_snapshot("world_map", &backend);
+}
+
+/// Draws the world navigation screen, the way the routine does
+fn draw_navigation(backend: &mut MemoryBackend, log: &MessageLog) {
+    let (width, height) = backend.size().unwrap();
+    let layout = NavigationLayout::new(Rect::new(0, 0, width, height));
+    let mut viewport = Viewport::new().without_message_line();
+    viewport.toggle_minimap();
+    viewport.fit(layout.map.width, layout.map.height);
+
+    let map = viewport.draw(&garden(), 2, 1, Direction::Down, None, None);
+    let status = Status {
+        name: "Alice".to_string(),
+        stage: "first_steps".to_string(),
+        location: "garden".to_string(),
+    };
+    backend.draw(&layout.draw(&map, &status, log)).unwrap();
+}
+
+fn messages() -> MessageLog {
+    let mut log = MessageLog::new();
+    for message in ["You wake up.", "The door is locked.", "The sign is blank."] {
+        log.push(message);
+    }
+    log
+}
+
+#[test]
+fn test_navigation_layout_snapshot() {
+    let mut backend = MemoryBackend::new(70, 18);
+
+    draw_navigation(&mut backend, &messages());
+
+    assert_snapshot("navigation", &backend);
+}
+
+#[test]
+fn test_navigation_layout_on_a_small_terminal_snapshot() {
+    let mut backend = MemoryBackend::new(30, 10);
+
+    draw_navigation(&mut backend, &messages());
+
+    // No sidebar, and only the latest message
+    assert_snapshot("navigation_small", &backend);
+}
+
+#[test]
+fn test_navigation_layout_with_a_long_message_snapshot() {
+    let mut backend = MemoryBackend::new(50, 18);
+    let mut log = messages();
+    log.push(
+        "The old man by the well tells you about the castle to the north, and the dragon \
+         that's said to sleep under it.",
+    );
+
+    draw_navigation(&mut backend, &log);
+
+    // Wrapped to the log's width, rather than running off the edge
+    assert_snapshot("navigation_long_message", &backend);
+}
+
+#[test]
+fn test_too_small_screen_snapshot() {
+    let mut backend = MemoryBackend::new(36, 8);
+
+    backend.draw(&too_small_screen(36, 8)).unwrap();
+
+    assert_snapshot("too_small", &backend);
+}
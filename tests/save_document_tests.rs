@@ -0,0 +1,290 @@
+#[cfg(test)]
+use rusqlite::Connection;
+use std::fs;
+use tbg::db::migrations::{latest_version, schema_version};
+use tbg::db::save::SaveSlot;
+use tbg::db::save_document::{
+    export_save, import_save, BookPageRecord, BookRecord, SaveDocument, SaveDocumentError,
+    SAVE_DOCUMENT_VERSION,
+};
+use tbg::models::book::page::Page;
+use tbg::models::game_state::explored_map::ExploredMap;
+use tbg::models::game_state::opened_door::OpenedDoor;
+use tbg::models::player::gender::Gender;
+use tbg::models::player::height::Height;
+use tbg::models::player::inventory_item::InventoryItem;
+use tbg::test_utils;
+use tbg::world::fog::Explored;
+use tbg::{GameState, Player};
+use uuid::Uuid;
+
+// Deletes the imported slot when the test is done, like `TestDb`
+struct ImportedSlot(SaveSlot);
+
+impl ImportedSlot {
+    fn new() -> Self {
+        ImportedSlot(SaveSlot::new(&format!("test_{}", Uuid::new_v4())))
+    }
+}
+
+impl Drop for ImportedSlot {
+    fn drop(&mut self) {
+        if self.0.exists() {
+            fs::remove_file(self.0.path()).unwrap();
+        }
+    }
+}
+
+fn create_run(conn: &Connection) -> Player {
+    let player = Player::new("Test Player".to_string(), Gender::Female, Height::Tall)
+        .create(conn)
+        .unwrap();
+    let mut game_state = GameState::new(player.id).create(conn).unwrap();
+    game_state.x = 3;
+    game_state.y = 4;
+    game_state.update(conn).unwrap();
+    conn.execute(
+        "INSERT INTO decisions (player_id, dialogue_id, response_id, deliberation_time) VALUES (?1, 7, 1, 1500)",
+        [player.id],
+    )
+    .unwrap();
+    let mut explored = Explored::new(4, 3);
+    explored.explore(1, 2);
+    ExploredMap::new(player.id, "meadow", &explored)
+        .save(conn)
+        .unwrap();
+    InventoryItem::new(player.id, "iron key", 1)
+        .add(conn)
+        .unwrap();
+    Page::found(player.id, "A torn page").create(conn).unwrap();
+    OpenedDoor::new(player.id, "meadow", 5, 6)
+        .create(conn)
+        .unwrap();
+
+    player
+}
+
+fn exported_document() -> SaveDocument {
+    let conn = &test_utils::setup_test_db().conn;
+    create_run(conn);
+    SaveDocument::export(conn).unwrap()
+}
+
+fn book(id: i64, player_id: i32) -> BookRecord {
+    let now = chrono::Local::now().naive_local();
+    BookRecord {
+        id,
+        player_id,
+        book_art_type: 0,
+        book_passive_id: 1,
+        front_cover: None,
+        inside_front_cover: None,
+        inside_back_cover: None,
+        back_cover: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+fn page(id: i64, player_id: i32) -> Page {
+    Page {
+        id,
+        player_id,
+        book_art_type: Some(0),
+        primary_action_id: Some(1),
+        secondary_action_id: None,
+        page_passive_id: None,
+        front: "Front".to_string(),
+        back: "Back".to_string(),
+    }
+}
+
+fn assert_invalid(document: &SaveDocument) {
+    assert!(
+        matches!(document.validate(), Err(SaveDocumentError::Invalid(_))),
+        "Expected {:?} to be rejected",
+        document
+    );
+}
+
+#[test]
+fn test_export_import_round_trip() {
+    let test_db = test_utils::setup_test_db();
+    let player = create_run(&test_db.conn);
+    let source = SaveSlot::new(test_db.test_db_path.trim_end_matches(".db"));
+    let imported = ImportedSlot::new();
+    let file = std::env::temp_dir().join(format!("{}.yaml", imported.0));
+
+    export_save(&source, &file).unwrap();
+    import_save(&file, &imported.0).unwrap();
+    fs::remove_file(&file).unwrap();
+
+    let original = SaveDocument::export(&test_db.conn).unwrap();
+    let conn = tbg::db::connection::get_connection(Some(&imported.0.file_name())).unwrap();
+    let copy = SaveDocument::export(&conn).unwrap();
+
+    assert_eq!(copy.player, player);
+    assert_eq!(copy.game_state, original.game_state);
+    assert_eq!(copy.decisions, original.decisions);
+    assert_eq!(copy.explored_maps, original.explored_maps);
+    assert!(copy.explored_maps[0]
+        .to_explored()
+        .unwrap()
+        .is_explored(1, 2));
+    assert_eq!(copy.inventory[0].item, "iron key");
+    // Found pages don't have actions, so there's no content for them to need
+    assert_eq!(copy.pages, original.pages);
+    assert_eq!(copy.pages[0].front, "A torn page");
+    assert_eq!(copy.opened_doors[0].map_name, "meadow");
+    assert_eq!(copy.game_state.x, 3);
+}
+
+#[test]
+fn test_export_leaves_old_saves_alone() {
+    let test_db = test_utils::setup_test_db();
+    create_run(&test_db.conn);
+    let old_version = latest_version() - 1;
+    test_db
+        .conn
+        .pragma_update(None, "user_version", old_version)
+        .unwrap();
+    let source = SaveSlot::new(test_db.test_db_path.trim_end_matches(".db"));
+    let file = std::env::temp_dir().join(format!("{}.yaml", source));
+
+    let result = export_save(&source, &file);
+
+    // Exporting only reads, so it doesn't migrate the save to get it up to date
+    assert!(matches!(result, Err(SaveDocumentError::Invalid(_))));
+    assert!(!file.exists());
+    assert_eq!(schema_version(&test_db.conn).unwrap(), old_version);
+}
+
+#[test]
+fn test_yaml_round_trip() {
+    let document = exported_document();
+
+    let yaml = document.to_yaml().unwrap();
+
+    assert_eq!(SaveDocument::from_yaml(&yaml).unwrap(), document);
+}
+
+#[test]
+fn test_import_refuses_existing_slot() {
+    let test_db = test_utils::setup_test_db();
+    let document = exported_document();
+    let slot = SaveSlot::new(test_db.test_db_path.trim_end_matches(".db"));
+
+    assert!(matches!(
+        document.import(&slot),
+        Err(SaveDocumentError::SlotExists(_))
+    ));
+}
+
+#[test]
+fn test_rejects_newer_format_version() {
+    let mut document = exported_document();
+    document.format_version = SAVE_DOCUMENT_VERSION + 1;
+
+    assert!(matches!(
+        document.validate(),
+        Err(SaveDocumentError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn test_rejects_mismatched_player() {
+    let mut document = exported_document();
+    document.game_state.player_id += 1;
+    assert_invalid(&document);
+
+    let mut document = exported_document();
+    document.decisions[0].player_id += 1;
+    assert_invalid(&document);
+
+    let mut document = exported_document();
+    document.explored_maps[0].player_id += 1;
+    assert_invalid(&document);
+
+    let mut document = exported_document();
+    document.inventory[0].player_id += 1;
+    assert_invalid(&document);
+
+    let mut document = exported_document();
+    document.opened_doors[0].player_id += 1;
+    assert_invalid(&document);
+}
+
+#[test]
+fn test_rejects_corrupt_explored_maps() {
+    let mut document = exported_document();
+    document.explored_maps[0].width += 4; // Needs another byte of tiles
+    assert_invalid(&document);
+
+    let mut document = exported_document();
+    let explored_map = document.explored_maps[0].clone();
+    document.explored_maps.push(explored_map);
+    assert_invalid(&document);
+}
+
+#[test]
+fn test_rejects_duplicate_inventory_items() {
+    let mut document = exported_document();
+    let inventory_item = document.inventory[0].clone();
+    document.inventory.push(inventory_item);
+    assert_invalid(&document);
+}
+
+#[test]
+fn test_rejects_duplicate_opened_doors() {
+    let mut document = exported_document();
+    let opened_door = document.opened_doors[0].clone();
+    document.opened_doors.push(opened_door);
+    assert_invalid(&document);
+}
+
+#[test]
+fn test_rejects_dangling_book_pages() {
+    let mut document = exported_document();
+    let player_id = document.player.id;
+    document.books.push(book(1, player_id));
+    document.pages.push(page(1, player_id));
+    document.book_pages.push(BookPageRecord {
+        book_id: 1,
+        page_id: 2,
+        position_index: 0,
+    });
+
+    assert_invalid(&document);
+}
+
+#[test]
+fn test_rejects_duplicate_positions() {
+    let mut document = exported_document();
+    let player_id = document.player.id;
+    document.books.push(book(1, player_id));
+    document.pages.push(page(1, player_id));
+    document.pages.push(page(2, player_id));
+    for page_id in [1, 2] {
+        document.book_pages.push(BookPageRecord {
+            book_id: 1,
+            page_id,
+            position_index: 0,
+        });
+    }
+
+    assert_invalid(&document);
+}
+
+#[test]
+fn test_import_rejects_missing_content() {
+    let mut document = exported_document();
+    document.decisions[0].dialogue_id = 999_999;
+    let imported = ImportedSlot::new();
+
+    assert!(matches!(
+        document.import(&imported.0),
+        Err(SaveDocumentError::Invalid(_))
+    ));
+    // The half-made slot is cleaned up
+    assert!(!imported.0.exists());
+}
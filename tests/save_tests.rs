@@ -0,0 +1,118 @@
+use std::time::Duration;
+use tbg::db::migrations::latest_version;
+#[cfg(test)]
+use tbg::db::save::{
+    copy_save, delete_save, format_play_time, list_save_slots, load_save_preview, next_slot_name,
+    SaveSlot,
+};
+use tbg::models::game_state::game_state::SqlDuration;
+use tbg::test_utils;
+use tbg::{GameState, Player};
+
+// The test db's file name is "test_{uuid}.db", so its slot name is the file stem
+fn slot_for(test_db: &test_utils::TestDb) -> SaveSlot {
+    SaveSlot::new(test_db.test_db_path.trim_end_matches(".db"))
+}
+
+#[test]
+fn test_save_slot_paths() {
+    let slot = SaveSlot::new("save_1");
+
+    assert_eq!(slot.file_name(), "save_1.db");
+    assert!(slot.path().ends_with("saves/save_1.db"));
+}
+
+#[test]
+fn test_list_save_slots() {
+    let test_db = test_utils::setup_test_db();
+    let slot = slot_for(&test_db);
+
+    let slots = list_save_slots().unwrap();
+
+    assert!(slots.contains(&slot));
+}
+
+#[test]
+fn test_next_slot_name_is_unused() {
+    let name = next_slot_name();
+
+    assert!(name.starts_with("save_"));
+    assert!(!SaveSlot::new(&name).exists());
+}
+
+#[test]
+fn test_preview_of_empty_save() {
+    let test_db = test_utils::setup_test_db();
+
+    let preview = load_save_preview(&slot_for(&test_db)).unwrap();
+
+    assert_eq!(preview.player_name, None);
+    assert_eq!(preview.to_string(), "(empty)");
+}
+
+#[test]
+fn test_preview_of_save_from_newer_version() {
+    let test_db = test_utils::setup_test_db();
+    test_db
+        .conn
+        .pragma_update(None, "user_version", latest_version() + 1)
+        .unwrap();
+
+    let preview = load_save_preview(&slot_for(&test_db)).unwrap();
+
+    assert!(preview.from_newer_version);
+    assert_eq!(preview.to_string(), "(needs a newer version of TBG)");
+}
+
+#[test]
+fn test_preview_of_save_with_player() {
+    let test_db = test_utils::setup_test_db();
+    let conn = &test_db.conn;
+
+    let player = Player::new(
+        "Preview Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Tall,
+    )
+    .create(conn)
+    .unwrap();
+    let mut game_state = GameState::new(player.id).create(conn).unwrap();
+    game_state.current_stage = "book_tutorial".to_string();
+    game_state.total_play_time = SqlDuration(Duration::from_secs(3720));
+    game_state.update(conn).unwrap();
+
+    let preview = load_save_preview(&slot_for(&test_db)).unwrap();
+
+    assert_eq!(preview.player_name, Some("Preview Player".to_string()));
+    assert_eq!(preview.current_epic, Some("intro".to_string()));
+    assert_eq!(preview.current_stage, Some("book_tutorial".to_string()));
+    assert_eq!(preview.play_time, Duration::from_secs(3720));
+    assert!(preview.last_played.is_some());
+    assert!(preview
+        .to_string()
+        .starts_with("Preview Player - intro/book_tutorial - 1h 02m"));
+}
+
+#[test]
+fn test_copy_save() {
+    let test_db = test_utils::setup_test_db();
+    let slot = slot_for(&test_db);
+    let copy = SaveSlot::new(&format!("{}_copy", slot));
+
+    copy_save(&slot, &copy).unwrap();
+    assert!(copy.exists());
+
+    // Copying onto an existing slot is refused
+    assert!(copy_save(&slot, &copy).is_err());
+
+    delete_save(Some(&copy.file_name())).unwrap();
+    assert!(!copy.exists());
+}
+
+#[test]
+fn test_format_play_time() {
+    assert_eq!(format_play_time(Duration::from_secs(0)), "0h 00m");
+    assert_eq!(format_play_time(Duration::from_secs(59)), "0h 00m");
+    assert_eq!(format_play_time(Duration::from_secs(3720)), "1h 02m");
+    assert_eq!(format_play_time(Duration::from_secs(36000)), "10h 00m");
+}
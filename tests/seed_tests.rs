@@ -0,0 +1,106 @@
+#[cfg(test)]
+use rusqlite::Connection;
+use tbg::db::seeds;
+use tbg::models::dialogue::dialogue;
+use tbg::test_utils;
+use tbg::Player;
+
+fn mark_stale(conn: &Connection, name: &str) {
+    conn.execute(
+        "UPDATE content_versions SET hash = 'stale' WHERE name = ?1",
+        [name],
+    )
+    .unwrap();
+}
+
+fn first_dialogue_text(conn: &Connection) -> String {
+    dialogue::load(conn, 1)
+        .unwrap()
+        .unwrap()
+        .get_text()
+        .to_string()
+}
+
+#[test]
+fn test_new_save_records_content_versions() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    for name in ["pages", "non_player_characters", "books", "dialogue"] {
+        assert!(
+            seeds::stored_content_hash(conn, name).unwrap().is_some(),
+            "Expected a content version for {}",
+            name
+        );
+    }
+
+    // Everything is up to date, so nothing is re-seeded
+    assert!(seeds::run(conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_changed_content_is_reseeded() {
+    let conn = &test_utils::setup_test_db().conn;
+    let original_text = first_dialogue_text(conn);
+
+    conn.execute("UPDATE dialogue SET text = 'Edited' WHERE id = 1", [])
+        .unwrap();
+    mark_stale(conn, "dialogue");
+
+    let reseeded = seeds::run(conn).unwrap();
+
+    assert_eq!(reseeded, vec!["dialogue"]);
+    assert_eq!(first_dialogue_text(conn), original_text);
+    assert_ne!(
+        seeds::stored_content_hash(conn, "dialogue").unwrap(),
+        Some("stale".to_string())
+    );
+}
+
+#[test]
+fn test_unchanged_content_is_not_reseeded() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    conn.execute("UPDATE dialogue SET text = 'Edited' WHERE id = 1", [])
+        .unwrap();
+
+    assert!(seeds::run(conn).unwrap().is_empty());
+    assert_eq!(first_dialogue_text(conn), "Edited");
+}
+
+#[test]
+fn test_reseeding_leaves_player_data_alone() {
+    let conn = &test_utils::setup_test_db().conn;
+
+    let player = Player::new(
+        "Test Player".to_string(),
+        tbg::models::player::gender::Gender::Female,
+        tbg::models::player::height::Height::Average,
+    )
+    .create(conn)
+    .unwrap();
+    conn.execute(
+        "INSERT INTO decisions (player_id, dialogue_id, response_id, deliberation_time) VALUES (?1, 7, 1, 1500)",
+        [player.id],
+    )
+    .unwrap();
+
+    mark_stale(conn, "dialogue");
+    mark_stale(conn, "non_player_characters");
+    let reseeded = seeds::run(conn).unwrap();
+
+    assert_eq!(reseeded, vec!["non_player_characters", "dialogue"]);
+    assert_eq!(Player::load(conn).unwrap().unwrap().name, "Test Player");
+    let decisions: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM decisions WHERE player_id = ?1 AND dialogue_id = 7",
+            [player.id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(decisions, 1);
+
+    // The decision still points at the same dialogue, since fixture ids are kept
+    let dialogue = dialogue::load(conn, 7).unwrap().unwrap();
+    assert_eq!(dialogue.get_text(), "Ah, a traveler! Are you lost?");
+    assert_eq!(dialogue.get_responses().as_ref().unwrap().len(), 2);
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+use std::fs;
+use tbg::models::dialogue::dialogue;
+use tbg::models::non_player_character::non_player_character::NPC;
+use tbg::test_utils;
+use tbg::world::atlas::{World, WorldError, DEFAULT_SPAWN, STARTING_MAP};
+use tbg::world::trigger::TriggerEvent;
+use tbg::world::validator::{validate_world, MapProblem};
+use uuid::Uuid;
+
+#[test]
+fn test_every_map_loads() {
+    let mut world = World::default();
+    let names = world.map_names().unwrap();
+
+    assert!(names.contains(&STARTING_MAP.to_string()));
+    for name in names {
+        let map = world.map(&name).unwrap();
+        assert_eq!(map.get_name(), name);
+    }
+}
+
+#[test]
+fn test_starting_map_has_default_spawn() {
+    let mut world = World::default();
+
+    let (x, y) = world.spawn_point(STARTING_MAP, DEFAULT_SPAWN).unwrap();
+
+    let map = world.map(STARTING_MAP).unwrap();
+    assert!(map.get_tile(x, y).is_passable(&[]));
+}
+
+#[test]
+fn test_portals_lead_to_spawn_points() {
+    let mut world = World::default();
+
+    for name in world.map_names().unwrap() {
+        let warps: Vec<(String, String)> = world
+            .map(&name)
+            .unwrap()
+            .get_triggers()
+            .iter()
+            .filter_map(|trigger| match &trigger.event {
+                TriggerEvent::Warp { map, spawn } => Some((map.clone(), spawn.clone())),
+                _ => None,
+            })
+            .collect();
+
+        for (map, spawn) in warps {
+            assert!(
+                world.spawn_point(&map, &spawn).is_ok(),
+                "{} has a portal to {}/{}, which doesn't exist",
+                name,
+                map,
+                spawn
+            );
+        }
+    }
+}
+
+#[test]
+fn test_unknown_map_and_spawn() {
+    let mut world = World::default();
+
+    assert!(matches!(
+        world.map("nowhere"),
+        Err(WorldError::UnknownMap(_))
+    ));
+    assert!(matches!(
+        world.spawn_point(STARTING_MAP, "nowhere"),
+        Err(WorldError::UnknownSpawn { .. })
+    ));
+}
+
+#[test]
+fn test_map_name_must_match_file() {
+    let dir = std::env::temp_dir().join(format!("maps_{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("field.map"),
+        "[map]\nname = meadow\n[legend]\n'.' = grass\n[layer ground]\n..\n",
+    )
+    .unwrap();
+
+    let mut world = World::new(&dir);
+    let result = world.map("field").map(|map| map.get_name().to_string());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(result, Err(WorldError::MismatchedName { .. })));
+}
+
+#[test]
+fn test_npcs_stand_on_passable_tiles_and_have_dialogue() {
+    let conn = &test_utils::setup_test_db().conn;
+    let mut world = World::default();
+
+    for name in world.map_names().unwrap() {
+        let map = world.map(&name).unwrap();
+        for npc in map.get_npcs() {
+            assert!(
+                map.get_tile(npc.x, npc.y).is_passable(&[]),
+                "{} on {} is standing in something",
+                npc.id,
+                name
+            );
+            assert!(NPC::load_by_id(conn, npc.npc_id).unwrap().is_some());
+            if let Some(root_name) = &npc.dialogue {
+                assert!(
+                    dialogue::load_by_root_name(conn, root_name)
+                        .unwrap()
+                        .is_some(),
+                    "{} on {} has no dialogue named {}",
+                    npc.id,
+                    name,
+                    root_name
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_encounter_opponents_exist() {
+    let conn = &test_utils::setup_test_db().conn;
+    let mut world = World::default();
+
+    for name in world.map_names().unwrap() {
+        let map = world.map(&name).unwrap();
+        for table in map.get_encounter_tables() {
+            for opponent in &table.opponents {
+                assert!(
+                    NPC::load_by_id(conn, opponent.npc_id).unwrap().is_some(),
+                    "{} on {} has an unknown opponent {}",
+                    table.id,
+                    name,
+                    opponent.npc_id
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_every_map_is_valid() {
+    let reports = validate_world(&mut World::default()).unwrap();
+
+    let problems: Vec<String> = reports
+        .iter()
+        .flat_map(|(name, problems)| problems.iter().map(move |p| format!("{} {}", name, p)))
+        .collect();
+    assert!(problems.is_empty(), "{}", problems.join("\n"));
+}
+
+#[test]
+fn test_validate_world_finds_broken_maps() {
+    let dir = std::env::temp_dir().join(format!("maps_{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("field.map"),
+        "[map]\nname = field\n[legend]\n'.' = grass\n[layer ground]\n..\n\n\
+         [spawns]\nstart = 0,0\n\n[triggers]\nexit = 1,0 enter repeat warp nowhere start\n",
+    )
+    .unwrap();
+    fs::write(dir.join("broken.map"), "3,3\n3,99\n").unwrap();
+
+    let reports = validate_world(&mut World::new(&dir)).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].0, "broken");
+    assert!(matches!(reports[0].1[..], [MapProblem::Unreadable(_)]));
+    assert_eq!(reports[1].0, "field");
+    assert!(matches!(
+        reports[1].1[..],
+        [MapProblem::MissingPortalTarget { .. }]
+    ));
+}